@@ -0,0 +1,409 @@
+//! Template archives: `.tar.gz` or `.zip` files made by `scaffold pack` and
+//! installed by `scaffold add`
+//!
+//! An archive holds a `manifest.json` at its root and the template under
+//! `<name>/`. The manifest lists every file with its SHA-256, and the archive as a
+//! whole is checked against an expected SHA-256 before it is unpacked. These are
+//! integrity checks against corrupt or truncated files; only a checksum obtained
+//! from somewhere other than the archive's own location says who made it.
+
+use anyhow::{Context, Result, bail, ensure};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::vfs::{DiskFs, EntryKind, TarFs, Vfs, ZipFs};
+
+/// Name of the manifest at the archive root
+pub const MANIFEST: &str = "manifest.json";
+
+/// Extension of the checksum file written next to an archive
+pub const CHECKSUM_EXTENSION: &str = "sha256";
+
+/// Directories never packed into an archive
+const SKIPPED: &[&str] = &[".git"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+
+    /// The format of an archive path or URL, from its extension
+    pub fn detect(path: &str) -> Option<Self> {
+        let path = path.to_lowercase();
+        if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if path.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a path or URL names a template archive
+pub fn is_archive(path: &str) -> bool {
+    ArchiveFormat::detect(path).is_some()
+}
+
+/// What an archive contains, stored as its `manifest.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Version of scaffold that packed the archive
+    pub scaffold_version: String,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path inside the template directory, `/`-separated
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// An archive written by [`pack`]
+pub struct Packed {
+    pub path: PathBuf,
+    pub sha256: String,
+    pub manifest: Manifest,
+}
+
+/// Pack a template directory into `<out_dir>/<name>-<version>.<ext>` (or
+/// `<name>.<ext>` without a version) and write its checksum file alongside
+pub fn pack(
+    template: &Path,
+    name: &str,
+    version: Option<&str>,
+    out_dir: &Path,
+    format: ArchiveFormat,
+) -> Result<Packed> {
+    let mut entries = Vec::new();
+    collect_entries(&DiskFs, template, template, &mut entries)?;
+
+    let manifest = Manifest {
+        name: name.to_string(),
+        version: version.map(str::to_string),
+        scaffold_version: env!("CARGO_PKG_VERSION").to_string(),
+        files: entries
+            .iter()
+            .filter_map(|entry| match &entry.contents {
+                Contents::File(bytes, _) => Some(ManifestFile {
+                    path: entry.path.clone(),
+                    sha256: sha256_hex(bytes),
+                    size: bytes.len() as u64,
+                }),
+                _ => None,
+            })
+            .collect(),
+    };
+
+    let file_name = match version {
+        Some(version) => format!("{}-{}.{}", name, version, format.extension()),
+        None => format!("{}.{}", name, format.extension()),
+    };
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create '{}'", out_dir.display()))?;
+    let path = out_dir.join(&file_name);
+    let file = fs::File::create(&path)
+        .with_context(|| format!("Failed to create '{}'", path.display()))?;
+
+    let write_error = || format!("Failed to write '{}'", path.display());
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut archive = TarFs::new(GzEncoder::new(file, Compression::default()), "");
+            write_archive(&mut archive, &manifest, &entries).with_context(write_error)?;
+            archive
+                .finish()
+                .and_then(|encoder| encoder.finish())
+                .with_context(write_error)?;
+        }
+        ArchiveFormat::Zip => {
+            let mut archive = ZipFs::new(file, "");
+            write_archive(&mut archive, &manifest, &entries).with_context(write_error)?;
+            archive.finish().with_context(write_error)?;
+        }
+    }
+
+    let sha256 = sha256_hex(
+        &fs::read(&path).with_context(|| format!("Failed to read '{}'", path.display()))?,
+    );
+    let checksum_path = checksum_path(&path);
+    fs::write(&checksum_path, format!("{}  {}\n", sha256, file_name))
+        .with_context(|| format!("Failed to write '{}'", checksum_path.display()))?;
+
+    Ok(Packed {
+        path,
+        sha256,
+        manifest,
+    })
+}
+
+/// A template entry, with its path relative to the template directory
+struct Entry {
+    path: String,
+    contents: Contents,
+}
+
+enum Contents {
+    Dir,
+    File(Vec<u8>, Option<u32>),
+    Symlink(PathBuf, bool),
+}
+
+fn collect_entries(fs: &dyn Vfs, root: &Path, dir: &Path, entries: &mut Vec<Entry>) -> Result<()> {
+    let children = fs
+        .read_dir(dir)
+        .with_context(|| format!("Failed to read '{}'", dir.display()))?;
+    for child in children {
+        let relative = child.strip_prefix(root).unwrap_or(&child);
+        if dir == root && SKIPPED.iter().any(|s| relative == Path::new(s)) {
+            continue;
+        }
+        let path = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        match fs.kind(&child)? {
+            EntryKind::Dir => {
+                entries.push(Entry {
+                    path,
+                    contents: Contents::Dir,
+                });
+                collect_entries(fs, root, &child, entries)?;
+            }
+            EntryKind::File => entries.push(Entry {
+                path,
+                contents: Contents::File(fs.read(&child)?, fs.mode(&child)?),
+            }),
+            EntryKind::Symlink => entries.push(Entry {
+                path,
+                contents: Contents::Symlink(fs.read_link(&child)?, child.is_dir()),
+            }),
+        }
+    }
+    Ok(())
+}
+
+fn write_archive(archive: &mut dyn Vfs, manifest: &Manifest, entries: &[Entry]) -> Result<()> {
+    let json = serde_json::to_string_pretty(manifest)?;
+    archive.write(Path::new(MANIFEST), json.as_bytes(), None)?;
+
+    let root = Path::new(&manifest.name);
+    archive.create_dir_all(root)?;
+    for entry in entries {
+        let path = root.join(&entry.path);
+        match &entry.contents {
+            Contents::Dir => archive.create_dir_all(&path)?,
+            Contents::File(bytes, mode) => archive.write(&path, bytes, *mode)?,
+            Contents::Symlink(target, is_dir) => archive.symlink(target, &path, *is_dir)?,
+        }
+    }
+    Ok(())
+}
+
+/// Extract an archive into `dest` and check every file against its manifest
+///
+/// The template ends up in `dest/<manifest.name>`.
+pub fn unpack(archive: &Path, dest: &Path) -> Result<Manifest> {
+    let format = ArchiveFormat::detect(&archive.to_string_lossy())
+        .with_context(|| format!("'{}' is not a .tar.gz or .zip archive", archive.display()))?;
+    let file = fs::File::open(archive)
+        .with_context(|| format!("Failed to open '{}'", archive.display()))?;
+    let extract_error = || format!("Failed to extract '{}'", archive.display());
+    match format {
+        ArchiveFormat::TarGz => tar::Archive::new(GzDecoder::new(file))
+            .unpack(dest)
+            .with_context(extract_error)?,
+        ArchiveFormat::Zip => zip::ZipArchive::new(file)
+            .and_then(|mut zip| zip.extract(dest))
+            .with_context(extract_error)?,
+    }
+
+    let manifest_path = dest.join(MANIFEST);
+    let manifest: Manifest = serde_json::from_str(
+        &fs::read_to_string(&manifest_path).context("Archive has no manifest.json")?,
+    )
+    .context("Failed to parse the archive manifest")?;
+    let mut components = Path::new(&manifest.name).components();
+    ensure!(
+        matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none(),
+        "Invalid template name '{}' in the archive manifest",
+        manifest.name
+    );
+
+    let root = dest.join(&manifest.name);
+    for file in &manifest.files {
+        let path = root.join(&file.path);
+        let bytes = fs::read(&path)
+            .with_context(|| format!("'{}' is listed in the manifest but missing", file.path))?;
+        ensure!(
+            bytes.len() as u64 == file.size && sha256_hex(&bytes) == file.sha256,
+            "'{}' doesn't match the archive manifest",
+            file.path
+        );
+    }
+    let mut entries = Vec::new();
+    collect_entries(&DiskFs, &root, &root, &mut entries)?;
+    let files = entries
+        .iter()
+        .filter(|entry| matches!(entry.contents, Contents::File(..)))
+        .count();
+    ensure!(
+        files == manifest.files.len(),
+        "Archive contains files not listed in its manifest"
+    );
+    Ok(manifest)
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// The checksum file for an archive: `<archive>.sha256`
+pub fn checksum_path(archive: &Path) -> PathBuf {
+    let mut name = archive.as_os_str().to_owned();
+    name.push(".");
+    name.push(CHECKSUM_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// The checksum in a `sha256sum`-style line (`<hex>  <file name>`)
+pub fn parse_checksum(text: &str) -> Result<String> {
+    let checksum = text.split_whitespace().next().unwrap_or_default();
+    ensure!(
+        checksum.len() == 64 && checksum.chars().all(|c| c.is_ascii_hexdigit()),
+        "'{}' is not a SHA-256 checksum",
+        checksum
+    );
+    Ok(checksum.to_lowercase())
+}
+
+/// Fail unless the file's SHA-256 is `expected`
+pub fn verify_checksum(path: &Path, expected: &str) -> Result<()> {
+    let expected = parse_checksum(expected)?;
+    let actual = sha256_hex(
+        &fs::read(path).with_context(|| format!("Failed to read '{}'", path.display()))?,
+    );
+    if actual != expected {
+        bail!(
+            "Checksum mismatch for '{}': expected {}, got {}",
+            path.display(),
+            expected,
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Download a URL to a file
+pub fn download(url: &str, dest: &Path) -> Result<()> {
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("Failed to download {}", url))?;
+    let mut file =
+        fs::File::create(dest).with_context(|| format!("Failed to create '{}'", dest.display()))?;
+    std::io::copy(&mut response.into_reader(), &mut file)
+        .with_context(|| format!("Failed to download {}", url))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(dir: &Path) -> PathBuf {
+        let template = dir.join("api");
+        fs::create_dir_all(template.join("src")).unwrap();
+        fs::create_dir_all(template.join(".git")).unwrap();
+        fs::write(template.join("template.toml"), "name = \"api\"\n").unwrap();
+        fs::write(template.join("src/main.py"), "print('{{name}}')\n").unwrap();
+        fs::write(template.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        template
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            ArchiveFormat::detect("api-1.0.tar.gz"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(ArchiveFormat::detect("api.TGZ"), Some(ArchiveFormat::TarGz));
+        assert_eq!(
+            ArchiveFormat::detect("https://example.com/api.zip"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert!(!is_archive("https://github.com/org/repo.git"));
+    }
+
+    #[test]
+    fn test_pack_and_unpack() {
+        for format in [ArchiveFormat::TarGz, ArchiveFormat::Zip] {
+            let temp = tempfile::TempDir::new().unwrap();
+            let template = template(temp.path());
+            let packed = pack(&template, "api", Some("1.2.0"), temp.path(), format).unwrap();
+            assert_eq!(
+                packed.path,
+                temp.path()
+                    .join(format!("api-1.2.0.{}", format.extension()))
+            );
+            assert_eq!(packed.manifest.files.len(), 2);
+
+            let checksum = fs::read_to_string(checksum_path(&packed.path)).unwrap();
+            assert_eq!(parse_checksum(&checksum).unwrap(), packed.sha256);
+            verify_checksum(&packed.path, &packed.sha256).unwrap();
+            assert!(verify_checksum(&packed.path, &"0".repeat(64)).is_err());
+
+            let dest = temp.path().join("unpacked");
+            let manifest = unpack(&packed.path, &dest).unwrap();
+            assert_eq!(manifest.version.as_deref(), Some("1.2.0"));
+            assert_eq!(
+                fs::read_to_string(dest.join("api/src/main.py")).unwrap(),
+                "print('{{name}}')\n"
+            );
+            assert!(!dest.join("api/.git").exists());
+        }
+    }
+
+    #[test]
+    fn test_unpack_rejects_modified_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let template = template(temp.path());
+        let packed = pack(&template, "api", None, temp.path(), ArchiveFormat::TarGz).unwrap();
+
+        // Repack with a changed file but the original manifest
+        let dest = temp.path().join("unpacked");
+        unpack(&packed.path, &dest).unwrap();
+        fs::write(dest.join("api/src/main.py"), "tampered\n").unwrap();
+        let tampered = temp.path().join("tampered.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            fs::File::create(&tampered).unwrap(),
+            Compression::default(),
+        ));
+        builder.append_dir_all(".", &dest).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let error = unpack(&tampered, &temp.path().join("again")).unwrap_err();
+        assert!(error.to_string().contains("src/main.py"));
+    }
+}
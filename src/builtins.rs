@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Local};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::git;
+
+/// Prefix reserved for generated variables; template variables should not use it
+pub const BUILTIN_PREFIX: &str = "_";
+
+/// Longest string `_random_hex(N)` will generate
+const MAX_RANDOM_HEX: usize = 1024;
+
+/// Matches `_random_hex(N)`
+static RANDOM_HEX_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^_random_hex\((\d+)\)$").unwrap());
+
+/// Source of the current time for `_now.*`
+pub trait Clock {
+    fn now(&self) -> DateTime<FixedOffset>;
+}
+
+/// Source of random bytes for `_uuid` and `_random_hex(N)`
+pub trait RandomSource {
+    fn fill(&mut self, bytes: &mut [u8]) -> Result<()>;
+}
+
+/// The local system clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        Local::now().fixed_offset()
+    }
+}
+
+/// A clock stopped at one instant
+pub struct FixedClock(pub DateTime<FixedOffset>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        self.0
+    }
+}
+
+/// Yields 0x00, 0x01, 0x02, ... (from the given start) so generated values are predictable
+pub struct CountingRandom(pub u8);
+
+impl RandomSource for CountingRandom {
+    fn fill(&mut self, bytes: &mut [u8]) -> Result<()> {
+        for byte in bytes {
+            *byte = self.0;
+            self.0 = self.0.wrapping_add(1);
+        }
+        Ok(())
+    }
+}
+
+/// The operating system's secure random number generator
+pub struct OsRandom;
+
+impl RandomSource for OsRandom {
+    fn fill(&mut self, bytes: &mut [u8]) -> Result<()> {
+        getrandom::fill(bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to generate random bytes: {}", e))
+    }
+}
+
+/// Generates the reserved `_`-prefixed variables available to every template:
+///
+/// - `_now.year`, `_now.month`, `_now.day`, `_now.date`, `_now.time`, `_now.iso`
+/// - `_uuid` (a random v4 UUID, the same everywhere in one project)
+/// - `_git.user_name`, `_git.user_email` (from the user's git config, when set)
+/// - `_env.NAME` (an environment variable, when set)
+/// - `_random_hex(N)` (N random hex characters, the same for each N in one project)
+pub struct Builtins {
+    clock: Box<dyn Clock>,
+    random: Box<dyn RandomSource>,
+    /// `_git.user_name` and `_git.user_email` to use instead of the user's git config
+    git_identity: Option<(String, String)>,
+}
+
+impl Builtins {
+    pub fn new(clock: impl Clock + 'static, random: impl RandomSource + 'static) -> Self {
+        Self {
+            clock: Box::new(clock),
+            random: Box::new(random),
+            git_identity: None,
+        }
+    }
+
+    /// Builtins backed by the system clock and OS randomness
+    pub fn system() -> Self {
+        Self::new(SystemClock, OsRandom)
+    }
+
+    /// Builtins that are the same on every machine and every run, for `scaffold test`:
+    /// midnight UTC on 2000-01-01, counting "random" bytes and a placeholder git identity
+    pub fn fixed() -> Self {
+        let epoch = DateTime::parse_from_rfc3339("2000-01-01T00:00:00+00:00")
+            .expect("valid fixed timestamp");
+        Self {
+            git_identity: Some(("Scaffold Test".to_string(), "test@example.com".to_string())),
+            ..Self::new(FixedClock(epoch), CountingRandom(0))
+        }
+    }
+
+    /// Generate the fixed built-ins (`_now.*`, `_uuid` and `_git.*`)
+    ///
+    /// Parameterized ones (`_env.NAME`, `_random_hex(N)`) are produced on demand by
+    /// [`Builtins::resolve`].
+    pub fn variables(&mut self) -> Result<HashMap<String, String>> {
+        let now = self.clock.now();
+        let mut variables = HashMap::from([
+            ("_now.year".to_string(), now.format("%Y").to_string()),
+            ("_now.month".to_string(), now.format("%m").to_string()),
+            ("_now.day".to_string(), now.format("%d").to_string()),
+            ("_now.date".to_string(), now.format("%Y-%m-%d").to_string()),
+            ("_now.time".to_string(), now.format("%H:%M:%S").to_string()),
+            (
+                "_now.iso".to_string(),
+                now.format("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+            ),
+            ("_uuid".to_string(), self.uuid()?),
+        ]);
+
+        if let Some((user_name, user_email)) = &self.git_identity {
+            variables.insert("_git.user_name".to_string(), user_name.clone());
+            variables.insert("_git.user_email".to_string(), user_email.clone());
+        } else {
+            for (name, key) in [
+                ("_git.user_name", "user.name"),
+                ("_git.user_email", "user.email"),
+            ] {
+                if let Some(value) = git::config_value(key) {
+                    variables.insert(name.to_string(), value);
+                }
+            }
+        }
+
+        Ok(variables)
+    }
+
+    /// Generate a parameterized built-in, or None if `name` isn't one (or the
+    /// environment variable is unset)
+    pub fn resolve(&mut self, name: &str) -> Result<Option<String>> {
+        if let Some(key) = name.strip_prefix("_env.") {
+            return Ok(std::env::var(key).ok());
+        }
+
+        let Some(caps) = RANDOM_HEX_PATTERN.captures(name) else {
+            return Ok(None);
+        };
+        let length: usize = caps[1]
+            .parse()
+            .ok()
+            .filter(|n| (1..=MAX_RANDOM_HEX).contains(n))
+            .with_context(|| {
+                format!(
+                    "_random_hex length must be between 1 and {}",
+                    MAX_RANDOM_HEX
+                )
+            })?;
+
+        let mut bytes = vec![0; length.div_ceil(2)];
+        self.random.fill(&mut bytes)?;
+        let mut hex = to_hex(&bytes);
+        hex.truncate(length);
+        Ok(Some(hex))
+    }
+
+    /// Add values for any parameterized built-ins among `names` that are not set yet
+    ///
+    /// Returns true if anything was added.
+    pub fn resolve_into(
+        &mut self,
+        variables: &mut HashMap<String, String>,
+        names: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<bool> {
+        let mut added = false;
+        for name in names {
+            let name = name.as_ref();
+            if variables.contains_key(name) {
+                continue;
+            }
+            if let Some(value) = self.resolve(name)? {
+                variables.insert(name.to_string(), value);
+                added = true;
+            }
+        }
+        Ok(added)
+    }
+
+    /// A random (version 4) UUID
+    fn uuid(&mut self) -> Result<String> {
+        let mut bytes = [0u8; 16];
+        self.random.fill(&mut bytes)?;
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+        let hex = to_hex(&bytes);
+        Ok(format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        ))
+    }
+}
+
+/// Whether a placeholder name belongs to the reserved built-in namespace
+pub fn is_builtin(name: &str) -> bool {
+    name.starts_with(BUILTIN_PREFIX)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtins() -> Builtins {
+        let now = DateTime::parse_from_rfc3339("2026-03-07T09:05:01+01:00").unwrap();
+        Builtins::new(FixedClock(now), CountingRandom(0))
+    }
+
+    #[test]
+    fn test_builtin_dates_and_uuid() {
+        let vars = builtins().variables().unwrap();
+        assert_eq!(vars["_now.year"], "2026");
+        assert_eq!(vars["_now.month"], "03");
+        assert_eq!(vars["_now.day"], "07");
+        assert_eq!(vars["_now.date"], "2026-03-07");
+        assert_eq!(vars["_now.time"], "09:05:01");
+        assert_eq!(vars["_now.iso"], "2026-03-07T09:05:01+01:00");
+        assert_eq!(vars["_uuid"], "00010203-0405-4607-8809-0a0b0c0d0e0f");
+    }
+
+    #[test]
+    fn test_fixed_builtins() {
+        let vars = Builtins::fixed().variables().unwrap();
+        assert_eq!(vars["_now.iso"], "2000-01-01T00:00:00+00:00");
+        assert_eq!(vars["_uuid"], "00010203-0405-4607-8809-0a0b0c0d0e0f");
+        assert_eq!(vars["_git.user_name"], "Scaffold Test");
+        assert_eq!(vars["_git.user_email"], "test@example.com");
+    }
+
+    #[test]
+    fn test_resolve_random_hex() {
+        let mut builtins = builtins();
+        assert_eq!(
+            builtins.resolve("_random_hex(5)").unwrap().as_deref(),
+            Some("00010")
+        );
+        assert!(builtins.resolve("_random_hex(0)").is_err());
+        assert!(builtins.resolve("_random_hex(99999)").is_err());
+        assert_eq!(builtins.resolve("project_name").unwrap(), None);
+    }
+
+    #[test]
+    fn test_resolve_into_keeps_existing_values() {
+        let mut builtins = builtins();
+        let mut vars = HashMap::from([("_random_hex(4)".to_string(), "abcd".to_string())]);
+        let added = builtins
+            .resolve_into(&mut vars, ["_random_hex(4)", "_random_hex(2)", "name"])
+            .unwrap();
+        assert!(added);
+        assert_eq!(vars["_random_hex(4)"], "abcd");
+        assert_eq!(vars["_random_hex(2)"], "00");
+        assert!(!vars.contains_key("name"));
+    }
+}
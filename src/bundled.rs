@@ -3,9 +3,17 @@ use rust_embed::RustEmbed;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::template::read_template_config;
+use crate::vfs::{EmbeddedFs, Vfs};
+
 #[derive(RustEmbed)]
 #[folder = "bundled-templates/"]
-struct BundledTemplates;
+pub struct BundledTemplates;
+
+/// The bundled templates as a read-only filesystem, one directory per template
+pub fn embedded() -> EmbeddedFs<BundledTemplates> {
+    EmbeddedFs::new()
+}
 
 /// Get the path where bundled templates are extracted
 pub fn get_bundled_templates_dir() -> PathBuf {
@@ -15,15 +23,16 @@ pub fn get_bundled_templates_dir() -> PathBuf {
         .join("bundled-templates")
 }
 
-/// Extract bundled templates if not already present or if version changed
+/// Extract bundled templates if not already present or if the binary or any
+/// template version changed
 /// Returns path to bundled templates directory
 pub fn ensure_bundled_templates() -> Result<PathBuf> {
     let bundled_dir = get_bundled_templates_dir();
     let marker = bundled_dir.join(".version");
-    let current_version = env!("CARGO_PKG_VERSION");
+    let current_version = version_marker();
 
     let needs_extraction = match fs::read_to_string(&marker) {
-        Ok(v) => v.trim() != current_version,
+        Ok(v) => v.trim() != current_version.trim(),
         Err(_) => true,
     };
 
@@ -35,6 +44,23 @@ pub fn ensure_bundled_templates() -> Result<PathBuf> {
     Ok(bundled_dir)
 }
 
+/// The binary version, then `<template> <version>` for each bundled template
+fn version_marker() -> String {
+    let mut marker = format!("scaffold {}\n", env!("CARGO_PKG_VERSION"));
+    let fs = embedded();
+    let Ok(templates) = fs.read_dir(Path::new("")) else {
+        return marker;
+    };
+    for template in templates {
+        let version = read_template_config(&fs, &template.join("template.toml"))
+            .ok()
+            .and_then(|config| config.version)
+            .unwrap_or_else(|| "-".to_string());
+        marker.push_str(&format!("{} {}\n", template.display(), version));
+    }
+    marker
+}
+
 fn extract_bundled_templates(dest: &Path) -> Result<()> {
     // Clear old bundled templates
     if dest.exists() {
@@ -80,4 +106,15 @@ mod tests {
         assert!(BundledTemplates::get("fastapi/template.toml").is_some());
         assert!(BundledTemplates::get("nextjs/template.toml").is_some());
     }
+
+    #[test]
+    fn test_version_marker_lists_templates() {
+        let marker = version_marker();
+        let mut lines = marker.lines();
+        assert_eq!(
+            lines.next(),
+            Some(format!("scaffold {}", env!("CARGO_PKG_VERSION")).as_str())
+        );
+        assert!(lines.any(|line| line.starts_with("fastapi ")));
+    }
 }
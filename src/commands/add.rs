@@ -1,13 +1,21 @@
 use anyhow::{Context, Result, ensure};
 use console::style;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-
-use crate::copy::copy_template_recursive;
-use crate::git::{clone_repo, is_git_url, parse_git_url};
-
-pub fn add_template(templates_dir: &Path, template_path: &str, template_name: &str) -> Result<()> {
+use tempfile::TempDir;
+
+use project_scaffold::archive::{checksum_path, download, is_archive, unpack, verify_checksum};
+use project_scaffold::copy::{RenderOptions, copy_template_recursive};
+use project_scaffold::git::{clone_repo, is_git_url, parse_git_url};
+use project_scaffold::vfs::DiskFs;
+
+pub fn add_template(
+    templates_dir: &Path,
+    template_path: &str,
+    template_name: &str,
+    sha256: Option<&str>,
+) -> Result<()> {
     validate_template_name(template_name)?;
 
     let dst = templates_dir.join(template_name);
@@ -18,45 +26,30 @@ pub fn add_template(templates_dir: &Path, template_path: &str, template_name: &s
         anyhow::bail!("Template '{}' already exists", template_name);
     }
 
-    // Determine source path (local or remote)
-    let (src, temp_dir) = if is_git_url(template_path) {
-        let parsed = parse_git_url(template_path);
-        let cloned_dir = clone_repo(&parsed.repo_url)?;
-
-        let src = match &parsed.subpath {
-            Some(subpath) => cloned_dir.join(subpath),
-            None => cloned_dir.clone(),
-        };
-
-        (src, Some(cloned_dir))
-    } else {
-        (PathBuf::from(template_path), None)
-    };
-
-    // Validate source exists
-    if !src.exists() {
-        if let Some(temp) = &temp_dir {
-            let _ = fs::remove_dir_all(temp);
-        }
-        anyhow::bail!("Template path '{}' does not exist", src.display());
-    }
+    let source = fetch_source(template_path, sha256)?;
+    let src = &source.path;
 
     // Validate template.toml exists
     let config_path = src.join("template.toml");
     if !config_path.exists() {
-        if let Some(temp) = &temp_dir {
-            let _ = fs::remove_dir_all(temp);
-        }
         anyhow::bail!("template.toml not found in template directory");
     }
 
-    // Copy template (no exclusions, no progress bar for add)
-    copy_template_recursive(&src, &dst, &HashMap::new(), &HashSet::new(), false, None)?;
-
-    // Clean up temp directory
-    if let Some(temp) = temp_dir {
-        let _ = fs::remove_dir_all(temp);
-    }
+    // Copy template verbatim (no rendering, no exclusions, no progress bar for add)
+    let options = RenderOptions {
+        skip_template_toml: false,
+        render: false,
+        ..RenderOptions::default()
+    };
+    copy_template_recursive(
+        &DiskFs,
+        src,
+        &mut DiskFs,
+        &dst,
+        &HashMap::new(),
+        &options,
+        None,
+    )?;
 
     println!(
         "Template '{}' added successfully.",
@@ -65,7 +58,96 @@ pub fn add_template(templates_dir: &Path, template_path: &str, template_name: &s
     Ok(())
 }
 
-fn validate_template_name(name: &str) -> Result<()> {
+/// A template source on disk: a local directory, or a clone or unpacked archive
+/// in a temporary directory that is removed when this is dropped
+pub(super) struct Source {
+    pub path: PathBuf,
+    _temp_dir: Option<TempDir>,
+}
+
+/// Resolve a local path, git URL (cloning it) or template archive (verifying and
+/// unpacking it) to an existing directory
+///
+/// An archive's checksum is `sha256` if given. A local archive may instead have
+/// it in the `.sha256` file next to it; a downloaded one must be given it, since
+/// a checksum from the same server proves nothing about who made the archive.
+pub(super) fn fetch_source(template_path: &str, sha256: Option<&str>) -> Result<Source> {
+    // Before the git check, which would also claim https:// archive URLs
+    let source = if is_archive(template_path) {
+        fetch_archive(template_path, sha256)?
+    } else if is_git_url(template_path) {
+        let parsed = parse_git_url(template_path);
+        println!("Cloning {}...", style(&parsed.repo_url).dim());
+        let cloned_dir = clone_repo(&parsed.repo_url)?;
+
+        Source {
+            path: match &parsed.subpath {
+                Some(subpath) => cloned_dir.path().join(subpath),
+                None => cloned_dir.path().to_path_buf(),
+            },
+            _temp_dir: Some(cloned_dir),
+        }
+    } else {
+        Source {
+            path: PathBuf::from(template_path),
+            _temp_dir: None,
+        }
+    };
+
+    if !source.path.exists() {
+        anyhow::bail!("Template path '{}' does not exist", source.path.display());
+    }
+    Ok(source)
+}
+
+fn fetch_archive(location: &str, sha256: Option<&str>) -> Result<Source> {
+    let temp_dir = tempfile::Builder::new()
+        .prefix("scaffold-archive-")
+        .tempdir()
+        .context("Failed to create temp directory")?;
+
+    let is_remote = location.starts_with("https://") || location.starts_with("http://");
+    let archive = if is_remote {
+        ensure!(
+            sha256.is_some(),
+            "Pass the archive's checksum with --sha256 to add it from a URL. \
+             Get it from the template's author, not the server hosting the archive."
+        );
+        let file_name = location.rsplit('/').next().unwrap_or(location);
+        let archive = temp_dir.path().join(file_name);
+        println!("Downloading {}...", style(location).dim());
+        download(location, &archive)?;
+        archive
+    } else {
+        let archive = PathBuf::from(location.strip_prefix("file://").unwrap_or(location));
+        ensure!(
+            archive.is_file(),
+            "Template archive '{}' does not exist",
+            archive.display()
+        );
+        archive
+    };
+
+    let expected = match sha256 {
+        Some(sha256) => sha256.to_string(),
+        None => fs::read_to_string(checksum_path(&archive)).with_context(|| {
+            format!(
+                "No checksum file '{}'; pass the checksum with --sha256",
+                checksum_path(&archive).display()
+            )
+        })?,
+    };
+    verify_checksum(&archive, &expected)?;
+
+    let unpacked = temp_dir.path().join("unpacked");
+    let manifest = unpack(&archive, &unpacked)?;
+    Ok(Source {
+        path: unpacked.join(&manifest.name),
+        _temp_dir: Some(temp_dir),
+    })
+}
+
+pub(super) fn validate_template_name(name: &str) -> Result<()> {
     ensure!(!name.is_empty(), "Template name cannot be empty");
     ensure!(
         !name.contains('/') && !name.contains('\\'),
@@ -3,7 +3,7 @@ use clap::Subcommand;
 use console::style;
 use std::fs;
 
-use crate::global_config::{get_config_path, load_global_config, save_global_config};
+use project_scaffold::global_config::{get_config_path, load_global_config, save_global_config};
 
 #[derive(Subcommand)]
 pub enum ConfigAction {
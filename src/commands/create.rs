@@ -1,26 +1,58 @@
-use anyhow::{Result, ensure};
+use anyhow::Result;
 use console::style;
-use dialoguer::{Confirm, FuzzySelect, Input, Select, theme::ColorfulTheme};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::collections::HashMap;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 
-use crate::copy::{copy_template_recursive, count_files, preview_template};
-use crate::global_config::load_global_config;
-use crate::hooks::execute_hooks;
-use crate::template::get_available_templates;
-use crate::variables::evaluate_conditionals;
-
-pub fn create_project_interactive(
-    templates_dir: &Path,
-    template: Option<String>,
-    output: Option<String>,
-    cli_vars: Vec<(String, String)>,
-    use_defaults: bool,
-    dry_run: bool,
-) -> Result<()> {
-    let theme = ColorfulTheme::default();
-    let templates = get_available_templates(templates_dir)?;
+use project_scaffold::builtins::Builtins;
+use project_scaffold::generate::{Generator, git_settings};
+use project_scaffold::global_config::load_global_config;
+use project_scaffold::hooks::HookEvent;
+use project_scaffold::output::{OutputFormat, print_json};
+use project_scaffold::prompt::{DialoguerPrompter, LinePrompter, Prompter};
+use project_scaffold::requirements::{Status, check_all};
+use project_scaffold::template::TemplateConfig;
+use project_scaffold::{GitSummary, Report, ensure_new_directory};
+
+use super::available_templates;
+
+/// Flags for `scaffold create`
+pub struct CreateOptions {
+    /// Template name (interactive selection if None)
+    pub template: Option<String>,
+    /// Output directory (prompted for if None)
+    pub output: Option<String>,
+    /// Variables passed with -v key=value
+    pub vars: Vec<(String, String)>,
+    /// Skip prompts and use defaults (--yes)
+    pub use_defaults: bool,
+    /// Preview without creating files (--dry-run)
+    pub dry_run: bool,
+    /// Force (Some(true)) or skip (Some(false)) git initialization
+    pub git: Option<bool>,
+    /// Leave unresolved placeholders as-is instead of failing (--lenient)
+    pub lenient: bool,
+    /// Don't stop on missing or outdated [requirements] (--skip-requirements)
+    pub skip_requirements: bool,
+    /// Print progress as text, or a JSON manifest at the end
+    pub format: OutputFormat,
+}
+
+pub fn create_project_interactive(templates_dir: &Path, options: CreateOptions) -> Result<()> {
+    let CreateOptions {
+        template,
+        output,
+        vars: cli_vars,
+        use_defaults,
+        dry_run,
+        git,
+        lenient,
+        skip_requirements,
+        format,
+    } = options;
+    let text = format.is_text();
+    let mut prompter = default_prompter();
+    let templates = available_templates(templates_dir)?;
 
     if templates.is_empty() {
         anyhow::bail!(
@@ -40,17 +72,11 @@ pub fn create_project_interactive(
                 )
             })?,
         None => {
-            println!("Select a template:");
             let items: Vec<String> = templates
                 .iter()
                 .map(|t| format!("{} - {}", t.dir_name, t.config.description))
                 .collect();
-
-            let selection = FuzzySelect::with_theme(&theme)
-                .items(&items)
-                .default(0)
-                .interact()?;
-
+            let selection = prompter.choice("template", "Select a template", &items, 0)?;
             &templates[selection]
         }
     };
@@ -58,10 +84,17 @@ pub fn create_project_interactive(
     let template_path = &selected_template.path;
     let config = &selected_template.config;
 
-    println!(
-        "\nCreating project from: {}",
-        style(&config.name).cyan().bold()
-    );
+    if text {
+        println!(
+            "\nCreating project from: {}",
+            style(&config.name).cyan().bold()
+        );
+    }
+
+    // Check tools before asking anything; a preview doesn't need them
+    if !dry_run && !skip_requirements {
+        check_requirements(config)?;
+    }
 
     // Get output directory (interactive if not provided)
     let output_str = match output {
@@ -70,128 +103,102 @@ pub fn create_project_interactive(
             if use_defaults || dry_run {
                 anyhow::bail!("Output directory is required when using --yes or --dry-run flag");
             }
-            Input::with_theme(&theme)
-                .with_prompt("Output directory")
-                .interact_text()?
+            prompter.string("output", "Output directory", None)?
         }
     };
+    if output_str.is_empty() {
+        anyhow::bail!("Output directory is required");
+    }
 
     let output_path = PathBuf::from(&output_str);
 
     // Skip validation in dry run mode
     if !dry_run {
-        validate_output_path(&output_path)?;
+        ensure_new_directory(&output_path)?;
     }
 
-    // Build variables map from CLI args
-    let mut variables: HashMap<String, String> = cli_vars.into_iter().collect();
+    // Global config supplies defaults the template leaves out
+    let mut generator = Generator::new(
+        template_path,
+        config,
+        &output_path,
+        cli_vars.into_iter().collect(),
+        Builtins::system(),
+    )?
+    .with_defaults(load_global_config().defaults);
 
-    // Auto-derive project_name from output directory when using --yes flag
-    if (use_defaults || dry_run) && !variables.contains_key("project_name") {
-        if let Some(dir_name) = output_path.file_name().and_then(|n| n.to_str()) {
-            // Convert hyphens to underscores for valid Python/package names
-            let derived_name = dir_name.replace('-', "_");
-            variables.insert("project_name".to_string(), derived_name);
-        }
+    if use_defaults || dry_run {
+        generator.answer_defaults()?;
     }
 
-    // Load global config for defaults
-    let global_config = load_global_config();
-
     // Prompt for missing variables
-    for var in &config.variables {
-        if variables.contains_key(&var.name) {
-            continue;
-        }
+    generator.prompt_missing(prompter.as_mut())?;
 
-        let var_type = var.var_type.as_deref().unwrap_or("string");
-
-        // Check for default: template default, then global config default
-        let effective_default = var
-            .default
-            .clone()
-            .or_else(|| global_config.defaults.get(&var.name).cloned());
-
-        if use_defaults || dry_run {
-            // Use default or appropriate fallback
-            let value = match var_type {
-                "bool" => effective_default.unwrap_or_else(|| "false".to_string()),
-                "choice" => effective_default
-                    .or_else(|| var.choices.first().cloned())
-                    .unwrap_or_default(),
-                _ => effective_default.unwrap_or_default(),
-            };
-            variables.insert(var.name.clone(), value);
-        } else {
-            // Interactive prompt based on variable type
-            let value = match var_type {
-                "bool" => {
-                    let default_bool = effective_default
-                        .as_ref()
-                        .is_some_and(|d| d == "true" || d == "yes");
-                    Confirm::with_theme(&theme)
-                        .with_prompt(&var.description)
-                        .default(default_bool)
-                        .interact()?
-                        .to_string()
-                }
-                "choice" => {
-                    if var.choices.is_empty() {
-                        anyhow::bail!(
-                            "Variable '{}' is type 'choice' but has no choices defined",
-                            var.name
-                        );
-                    }
-                    let default_idx = effective_default
-                        .as_ref()
-                        .and_then(|d| var.choices.iter().position(|c| c == d))
-                        .unwrap_or(0);
-                    let selection = Select::with_theme(&theme)
-                        .with_prompt(&var.description)
-                        .items(&var.choices)
-                        .default(default_idx)
-                        .interact()?;
-                    var.choices[selection].clone()
-                }
-                _ => {
-                    // String type (default)
-                    let prompt_text = if var.description.is_empty() {
-                        var.name.clone()
-                    } else {
-                        format!("{} ({})", var.description, &var.name)
-                    };
-
-                    match &effective_default {
-                        Some(default) => Input::with_theme(&theme)
-                            .with_prompt(&prompt_text)
-                            .default(default.clone())
-                            .interact_text()?,
-                        None => Input::with_theme(&theme)
-                            .with_prompt(&prompt_text)
-                            .allow_empty(true)
-                            .interact_text()?,
-                    }
-                }
-            };
-
-            variables.insert(var.name.clone(), value);
-        }
-    }
+    // Derive computed variables, run scripts and plan every destination first
+    // so invalid names and unresolved placeholders fail before anything is written
+    let mut generation = generator.finish()?;
 
-    // Build exclusion set from conditionals
-    let excluded_files = evaluate_conditionals(&config.conditionals, &variables);
+    // Decide on git initialization: --git/--no-git override the template's [git] section
+    let git_config = git_settings(config, git);
+
+    let mut manifest = Report {
+        template: selected_template.dir_name.clone(),
+        output: output_path.clone(),
+        dry_run,
+        files: generation.files(),
+        skipped: generation.plan.skipped.clone(),
+        unresolved: Vec::new(),
+        hooks: Vec::new(),
+        git: git_config.map(|g| GitSummary {
+            branch: g.branch.clone(),
+            commit: g.commit,
+        }),
+    };
+
+    if dry_run && !text {
+        manifest.unresolved = generation.unresolved;
+        return print_json(&manifest);
+    }
 
     if dry_run {
         // Preview mode - show what would be created
         println!("\n{}", style("Dry run - no files will be created").yellow());
         println!("\nWould create:\n");
-        preview_template(template_path, &output_path, &variables, &excluded_files, 0)?;
+        generation.preview(&mut std::io::stdout())?;
+        for (path, _) in &generation.added_files {
+            println!("  + {} {}", path, style("(from script)").dim());
+        }
+        if let Some(git_config) = git_config {
+            println!(
+                "\nWould initialize a git repository on branch '{}'",
+                git_config.branch
+            );
+        }
+        if !generation.unresolved.is_empty() {
+            println!("\n{}\n", style("Unresolved placeholders:").yellow());
+            for placeholder in &generation.unresolved {
+                println!("  {}", placeholder);
+            }
+        }
         return Ok(());
     }
 
-    // Count files for progress bar
-    let file_count = count_files(template_path, &excluded_files)?;
-    let progress = ProgressBar::new(file_count as u64);
+    generation.check_unresolved(lenient)?;
+    if !generation.unresolved.is_empty() {
+        eprintln!(
+            "{}: Leaving unresolved placeholders as-is:\n{}",
+            style("Warning").yellow(),
+            generation.unresolved_list()
+        );
+    }
+
+    manifest.unresolved = generation.unresolved.clone();
+
+    let progress = if text {
+        ProgressBar::new(generation.plan.files.len() as u64)
+    } else {
+        ProgressBar::hidden()
+    };
     progress.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} files")
@@ -200,25 +207,41 @@ pub fn create_project_interactive(
     );
 
     // Copy and render template
-    copy_template_recursive(
-        template_path,
-        &output_path,
-        &variables,
-        &excluded_files,
-        true,
-        Some(&progress),
-    )?;
+    generation.write(Some(&progress))?;
 
     progress.finish_and_clear();
 
     // Execute post-create hooks
-    if let Some(ref hooks) = config.hooks {
-        if !hooks.post_create.is_empty() {
+    if config
+        .hooks
+        .as_ref()
+        .is_some_and(|h| !h.post_create.is_empty())
+    {
+        if text {
             println!("\nRunning post-create hooks...");
-            execute_hooks(&hooks.post_create, &output_path)?;
+        }
+        manifest.hooks = generation.run_post_create(&mut |event| {
+            if text {
+                print_hook_event(event);
+            }
+        })?;
+    }
+
+    // Initialize git last so files produced by hooks (e.g. lockfiles) are committed
+    if let Some(git_config) = git_config {
+        if text {
+            println!("\nInitializing git repository...");
+        }
+        generation.init_git(git_config)?;
+        if text {
+            println!("  {}", style("ok").green());
         }
     }
 
+    if !text {
+        return print_json(&manifest);
+    }
+
     println!(
         "\nProject created at: {}\n",
         style(&output_str).green().bold()
@@ -229,11 +252,52 @@ pub fn create_project_interactive(
     Ok(())
 }
 
-fn validate_output_path(path: &Path) -> Result<()> {
-    ensure!(
-        !path.exists(),
-        "Output directory '{}' already exists",
-        path.display()
-    );
+/// Fail if a tool in `[requirements]` is missing or outdated; warn about tools
+/// whose version couldn't be read
+fn check_requirements(config: &TemplateConfig) -> Result<()> {
+    let checks = check_all(&config.requirements)?;
+    for check in checks.iter().filter(|c| c.status == Status::Unknown) {
+        eprintln!("{}: {}", style("Warning").yellow(), check.describe());
+    }
+    let blocking: Vec<String> = checks
+        .iter()
+        .filter(|c| c.is_blocking())
+        .map(|c| format!("  {}", c.describe()))
+        .collect();
+    if !blocking.is_empty() {
+        anyhow::bail!(
+            "Template '{}' needs tools that aren't installed or are too old:\n{}\n\
+             Install them, or pass --skip-requirements to create the project anyway. \
+             Run 'scaffold doctor' for details.",
+            config.name,
+            blocking.join("\n")
+        );
+    }
     Ok(())
 }
+
+/// Show a post-create command starting or finishing
+fn print_hook_event(event: HookEvent) {
+    match event {
+        HookEvent::Started(command) => println!("  Running: {}", style(command).dim()),
+        HookEvent::Finished(result) if result.success => println!("  {}", style("ok").green()),
+        HookEvent::Finished(result) => eprintln!(
+            "  {}: Hook failed: {}",
+            style("Warning").yellow(),
+            result.error
+        ),
+    }
+}
+
+/// Arrow-key prompts on a terminal; plain line-based prompts on stderr when input is
+/// piped or the output goes to a CI log
+pub(super) fn default_prompter() -> Box<dyn Prompter> {
+    if std::io::stdin().is_terminal() && std::io::stderr().is_terminal() {
+        Box::new(DialoguerPrompter::default())
+    } else {
+        Box::new(LinePrompter::new(
+            std::io::stdin().lock(),
+            std::io::stderr(),
+        ))
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use console::style;
+use serde::Serialize;
+use std::path::Path;
+
+use project_scaffold::output::{OutputFormat, print_json};
+use project_scaffold::requirements::{Check, Status, check_all};
+use project_scaffold::template::find_template;
+
+use super::available_templates;
+
+/// One template's requirements, as checked on this machine
+#[derive(Serialize)]
+struct TemplateChecks {
+    template: String,
+    requirements: Vec<Check>,
+}
+
+/// The document printed by `scaffold doctor --format json`
+#[derive(Serialize)]
+struct DoctorReport {
+    /// False if any requirement is missing or outdated
+    ok: bool,
+    templates: Vec<TemplateChecks>,
+}
+
+/// Report which `[requirements]` of one template, or of every template declaring
+/// some, are met on this machine
+pub fn run_doctor(
+    templates_dir: &Path,
+    template: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let templates = match template {
+        Some(name) => vec![find_template(templates_dir, name)?],
+        None => available_templates(templates_dir)?
+            .into_iter()
+            .filter(|t| !t.config.requirements.is_empty())
+            .collect(),
+    };
+    let mut results = Vec::new();
+    for template in &templates {
+        results.push(TemplateChecks {
+            template: template.dir_name.clone(),
+            requirements: check_all(&template.config.requirements)?,
+        });
+    }
+    let checks: Vec<&Check> = results.iter().flat_map(|r| &r.requirements).collect();
+    let count = |status: Status| checks.iter().filter(|c| c.status == status).count();
+    let unmet = count(Status::Missing) + count(Status::Outdated);
+
+    if format == OutputFormat::Json {
+        print_json(&DoctorReport {
+            ok: unmet == 0,
+            templates: results,
+        })?;
+    } else if checks.is_empty() {
+        match template {
+            Some(name) => println!("Template '{}' declares no requirements.", name),
+            None => println!("No templates declare [requirements]."),
+        }
+    } else {
+        for result in &results {
+            println!("{}", style(&result.template).cyan().bold());
+            if result.requirements.is_empty() {
+                println!("  {}", style("no requirements").dim());
+            }
+            for check in &result.requirements {
+                let label = match check.status {
+                    Status::Met => style(format!("{:<9}", "ok")).green(),
+                    Status::Missing => style(format!("{:<9}", "missing")).red(),
+                    Status::Outdated => style(format!("{:<9}", "outdated")).yellow(),
+                    Status::Unknown => style(format!("{:<9}", "unknown")).yellow(),
+                };
+                println!("  {}{}", label, check.describe());
+            }
+            println!();
+        }
+        let mut summary = format!(
+            "{} met, {} missing, {} outdated",
+            count(Status::Met),
+            count(Status::Missing),
+            count(Status::Outdated)
+        );
+        if count(Status::Unknown) > 0 {
+            summary.push_str(&format!(", {} unknown", count(Status::Unknown)));
+        }
+        println!("{}", summary);
+    }
+
+    if unmet > 0 {
+        anyhow::bail!("{} requirement(s) not met", unmet);
+    }
+    Ok(())
+}
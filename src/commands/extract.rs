@@ -0,0 +1,336 @@
+use anyhow::{Context, Result, ensure};
+use console::style;
+use ignore::WalkBuilder;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use project_scaffold::builtins::BUILTIN_PREFIX;
+use project_scaffold::paths::PathPatterns;
+use project_scaffold::variables::apply_filter;
+use project_scaffold::vfs::{DiskFs, Vfs};
+
+use super::add::validate_template_name;
+use super::import::ImportedVariable;
+
+/// Build output and caches, skipped even when there's no .gitignore
+const BUILD_ARTIFACTS: &[&str] = &[
+    ".git",
+    "target",
+    "node_modules",
+    "__pycache__",
+    "*.pyc",
+    ".venv",
+    "venv",
+    ".next",
+    ".gradle",
+    ".pytest_cache",
+    ".mypy_cache",
+    ".ruff_cache",
+    ".tox",
+    ".DS_Store",
+];
+
+/// Case styles a literal is also looked for in, as the placeholder filters that
+/// produce them
+const CASE_STYLES: &[&str] = &[
+    "snake_case",
+    "kebab_case",
+    "camel_case",
+    "pascal_case",
+    "title_case",
+    "snake_case | upper",
+    "lower",
+    "upper",
+];
+
+/// The template.toml written by extract
+#[derive(Serialize)]
+struct ExtractedConfig {
+    name: String,
+    description: String,
+    variables: Vec<ImportedVariable>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    rename: BTreeMap<String, String>,
+}
+
+/// What was copied out of the project
+#[derive(Default)]
+struct Extracted {
+    files: usize,
+    /// Output names needing a filter, by template-relative path
+    rename: BTreeMap<String, String>,
+}
+
+/// Every spelling of the literals being replaced, matched in a single pass so a
+/// placeholder is never replaced again
+struct Replacements {
+    pattern: Option<Regex>,
+    /// Placeholder and variable index for each spelling
+    placeholders: Vec<(String, String, usize)>,
+    /// Occurrences replaced, by variable
+    counts: Vec<usize>,
+}
+
+impl Replacements {
+    fn new(pairs: &[(String, String)]) -> Self {
+        let mut seen = HashSet::new();
+        let mut placeholders = Vec::new();
+        for (index, (literal, variable)) in pairs.iter().enumerate() {
+            if seen.insert(literal.clone()) {
+                placeholders.push((literal.clone(), format!("{{{{{}}}}}", variable), index));
+            }
+            for &style in CASE_STYLES {
+                let spelling = style
+                    .split(" | ")
+                    .try_fold(literal.clone(), |value, filter| {
+                        apply_filter(filter, &value)
+                    });
+                if let Some(spelling) = spelling.filter(|s| !s.is_empty()) {
+                    if seen.insert(spelling.clone()) {
+                        let placeholder = format!("{{{{ {} | {} }}}}", variable, style);
+                        placeholders.push((spelling, placeholder, index));
+                    }
+                }
+            }
+        }
+        // Longest first, so `my_app_core` isn't matched as `my_app`
+        placeholders.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.0.cmp(&b.0)));
+
+        let pattern = (!placeholders.is_empty()).then(|| {
+            let alternatives: Vec<String> = placeholders
+                .iter()
+                .map(|(s, _, _)| regex::escape(s))
+                .collect();
+            Regex::new(&alternatives.join("|")).unwrap()
+        });
+        Self {
+            pattern,
+            placeholders,
+            counts: vec![0; pairs.len()],
+        }
+    }
+
+    fn replace(&mut self, text: &str) -> String {
+        let Some(pattern) = &self.pattern else {
+            return text.to_string();
+        };
+        let placeholders = &self.placeholders;
+        let counts = &mut self.counts;
+        pattern
+            .replace_all(text, |found: &Captures| {
+                let (_, placeholder, index) = placeholders
+                    .iter()
+                    .find(|(spelling, _, _)| spelling == &found[0])
+                    .expect("every match is a spelling");
+                counts[*index] += 1;
+                placeholder.clone()
+            })
+            .into_owned()
+    }
+}
+
+pub fn extract_template(
+    templates_dir: &Path,
+    project_dir: &str,
+    template_name: &str,
+    replace: &[(String, String)],
+) -> Result<()> {
+    validate_template_name(template_name)?;
+    let project = PathBuf::from(project_dir);
+    ensure!(
+        project.is_dir(),
+        "Project directory '{}' does not exist",
+        project_dir
+    );
+
+    let mut names = HashSet::new();
+    for (literal, variable) in replace {
+        ensure!(!literal.is_empty(), "Cannot replace an empty string");
+        validate_variable_name(variable)?;
+        ensure!(
+            names.insert(variable),
+            "Variable '{}' is given more than once",
+            variable
+        );
+    }
+
+    let dst = templates_dir.join(template_name);
+    fs::create_dir_all(templates_dir).context("Failed to create templates directory")?;
+    if dst.exists() {
+        anyhow::bail!("Template '{}' already exists", template_name);
+    }
+
+    let mut replacements = Replacements::new(replace);
+    // Don't leave a half-extracted template behind
+    let extracted = match copy_project(&project, &dst, &mut replacements) {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&dst);
+            return Err(e);
+        }
+    };
+
+    let config = ExtractedConfig {
+        name: template_name.to_string(),
+        description: format!("Extracted from {}", project_dir),
+        variables: replace
+            .iter()
+            .map(|(literal, variable)| {
+                let mut entry = ImportedVariable::new(variable, None);
+                entry.default = Some(literal.clone());
+                entry
+            })
+            .collect(),
+        rename: extracted.rename,
+    };
+    let toml = toml::to_string_pretty(&config).context("Failed to serialize template config")?;
+    fs::write(dst.join("template.toml"), toml).context("Failed to write template.toml")?;
+
+    println!(
+        "Template '{}' extracted from {} ({} file(s)).",
+        style(template_name).cyan().bold(),
+        project_dir,
+        extracted.files
+    );
+    for ((literal, variable), count) in replace.iter().zip(&replacements.counts) {
+        if *count == 0 {
+            println!(
+                "  {} '{}' was not found, so {} is unused",
+                style("!").yellow(),
+                literal,
+                style(variable).bold()
+            );
+        } else {
+            println!(
+                "  {} '{}' -> {} ({} replacement(s))",
+                style("ok").green(),
+                literal,
+                style(variable).bold(),
+                count
+            );
+        }
+    }
+    println!(
+        "\nCheck it with: {}",
+        style(format!("scaffold validate {}", dst.display())).cyan()
+    );
+    Ok(())
+}
+
+/// Copy a project's files, minus ignored paths and build artifacts, replacing
+/// literals in names and text contents
+fn copy_project(project: &Path, dst: &Path, replacements: &mut Replacements) -> Result<Extracted> {
+    let artifacts = PathPatterns::new(
+        &BUILD_ARTIFACTS
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>(),
+    )?;
+    let project_root = project.to_path_buf();
+    let walker = WalkBuilder::new(project)
+        .hidden(false)
+        .parents(false)
+        .git_global(false)
+        // A project that isn't a repository yet still has its .gitignore honoured
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let relative = entry
+                .path()
+                .strip_prefix(&project_root)
+                .unwrap_or(entry.path());
+            !artifacts.is_match(&relative.to_string_lossy().replace('\\', "/"))
+        })
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let fs = DiskFs;
+    let mut out = DiskFs;
+    out.create_dir_all(dst)
+        .with_context(|| format!("Failed to create '{}'", dst.display()))?;
+    let mut extracted = Extracted::default();
+    let mut names: Vec<String> = Vec::new();
+    for entry in walker {
+        let entry = entry.context("Failed to read project directory")?;
+        let path = entry.path();
+        if entry.depth() == 0 {
+            continue;
+        }
+        // Walks are depth first, so the parents' template names are already known
+        let name = entry.file_name().to_string_lossy().to_string();
+        let renamed = replacements.replace(&name);
+        names.truncate(entry.depth() - 1);
+        if renamed.contains('|') {
+            // Windows forbids `|` in file names, so filtered names go in [rename]
+            names.push(name);
+            extracted.rename.insert(names.join("/"), renamed);
+        } else {
+            names.push(renamed);
+        }
+        let target = dst.join(names.join("/"));
+
+        let file_type = entry.file_type().context("Unknown file type")?;
+        if file_type.is_symlink() {
+            let link = fs.read_link(path)?;
+            out.symlink(&link, &target, path.is_dir())?;
+        } else if file_type.is_dir() {
+            out.create_dir_all(&target)?;
+        } else {
+            let contents = fs.read(path)?;
+            let contents = match String::from_utf8(contents) {
+                // Braces already in the project stay literal
+                Ok(text) => replacements
+                    .replace(&text.replace("{{", "\\{{"))
+                    .into_bytes(),
+                // Binary files are copied as-is
+                Err(e) => e.into_bytes(),
+            };
+            out.write(&target, &contents, fs.mode(path)?)?;
+            extracted.files += 1;
+        }
+    }
+    Ok(extracted)
+}
+
+pub(super) fn validate_variable_name(name: &str) -> Result<()> {
+    ensure!(
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        "Invalid variable name '{}': use letters, digits and underscores",
+        name
+    );
+    ensure!(
+        !name.starts_with(BUILTIN_PREFIX),
+        "Variable names starting with '{}' are reserved for built-ins",
+        BUILTIN_PREFIX
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replacements_in_case_styles() {
+        let mut replacements = Replacements::new(&[
+            ("my-app".to_string(), "project_name".to_string()),
+            ("Jane Doe".to_string(), "author".to_string()),
+        ]);
+        assert_eq!(
+            replacements.replace("my-app my_app MyApp MY_APP myApp by Jane Doe <jane_doe>"),
+            "{{project_name}} {{ project_name | snake_case }} {{ project_name | pascal_case }} \
+             {{ project_name | snake_case | upper }} {{ project_name | camel_case }} \
+             by {{author}} <{{ author | snake_case }}>"
+        );
+        assert_eq!(replacements.counts, vec![5, 2]);
+    }
+
+    #[test]
+    fn test_validate_variable_name() {
+        assert!(validate_variable_name("project_name").is_ok());
+        assert!(validate_variable_name("_output").is_err());
+        assert!(validate_variable_name("my-var").is_err());
+    }
+}
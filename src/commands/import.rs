@@ -0,0 +1,711 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use console::style;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use project_scaffold::engine::{JinjaRenderer, Renderer};
+use project_scaffold::paths::{PathPatterns, check_relative_path, ensure_within};
+use project_scaffold::prompt::MULTI_SEPARATOR;
+use project_scaffold::template::ComputedVariable;
+use project_scaffold::vfs::{DiskFs, EntryKind, Vfs};
+
+use super::add::{fetch_source, validate_template_name};
+
+/// A Jinja expression or statement tag
+static JINJA_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)\{\{.*?\}\}|\{%.*?%\}").unwrap());
+
+/// `cookiecutter.name` inside a Jinja tag
+static COOKIECUTTER_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bcookiecutter\.(\w+)").unwrap());
+
+/// What copier leaves out when `_exclude` isn't set
+const COPIER_EXCLUDE: &[&str] = &[
+    "copier.yaml",
+    "copier.yml",
+    "~*",
+    "*.py[co]",
+    "__pycache__",
+    ".git",
+    ".DS_Store",
+    ".svn",
+];
+
+/// Template generators `scaffold import` can translate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// cookiecutter.json with a `{{cookiecutter.*}}` project directory
+    Cookiecutter,
+    /// copier.yml (or copier.yaml)
+    Copier,
+}
+
+impl ImportFormat {
+    fn name(self) -> &'static str {
+        match self {
+            ImportFormat::Cookiecutter => "cookiecutter",
+            ImportFormat::Copier => "copier",
+        }
+    }
+}
+
+/// The template.toml written by import, leaving out everything left at its default
+#[derive(Debug, Serialize)]
+struct ImportedConfig {
+    name: String,
+    description: String,
+    engine: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    copy_without_render: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hooks: Option<ImportedHooks>,
+    variables: Vec<ImportedVariable>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    computed: Vec<ComputedVariable>,
+}
+
+impl ImportedConfig {
+    fn new() -> Self {
+        Self {
+            name: String::new(),
+            description: String::new(),
+            // Both generators render with Jinja2
+            engine: "jinja",
+            copy_without_render: Vec::new(),
+            hooks: None,
+            variables: Vec::new(),
+            computed: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ImportedHooks {
+    post_create: Vec<String>,
+}
+
+/// A `[[variables]]` entry, also written by `scaffold extract`
+#[derive(Debug, PartialEq, Serialize)]
+pub(super) struct ImportedVariable {
+    name: String,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) default: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    var_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    choices: Vec<String>,
+}
+
+impl ImportedVariable {
+    pub(super) fn new(name: &str, description: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.unwrap_or_else(|| describe(name)),
+            default: None,
+            var_type: None,
+            choices: Vec::new(),
+        }
+    }
+}
+
+/// A template translated from another generator, ready to be copied
+struct Translation {
+    config: ImportedConfig,
+    /// Directory whose contents become the template's files
+    root: PathBuf,
+    /// Root-relative paths that aren't copied
+    exclude: PathPatterns,
+    /// Suffix marking the files copier renders; it's stripped, and other files
+    /// containing Jinja are copied without rendering
+    templates_suffix: Option<String>,
+    /// New names for `cookiecutter.*` references, which become plain variables
+    references: Option<HashMap<String, String>>,
+    /// Everything that couldn't be translated, one line each
+    untranslated: Vec<String>,
+}
+
+impl Translation {
+    fn rewrite(&self, text: &str) -> String {
+        match &self.references {
+            Some(references) => rewrite_references(text, references),
+            None => text.to_string(),
+        }
+    }
+}
+
+/// Rewrite `cookiecutter.name` references inside Jinja tags to plain variables
+fn rewrite_references(text: &str, references: &HashMap<String, String>) -> String {
+    JINJA_TAG
+        .replace_all(text, |tag: &Captures| {
+            COOKIECUTTER_REFERENCE
+                .replace_all(&tag[0], |reference: &Captures| {
+                    let name = &reference[1];
+                    references
+                        .get(name)
+                        .map_or(name, String::as_str)
+                        .to_string()
+                })
+                .into_owned()
+        })
+        .into_owned()
+}
+
+pub fn import_template(
+    templates_dir: &Path,
+    template_path: &str,
+    template_name: &str,
+    from: ImportFormat,
+) -> Result<()> {
+    validate_template_name(template_name)?;
+
+    let dst = templates_dir.join(template_name);
+
+    fs::create_dir_all(templates_dir).context("Failed to create templates directory")?;
+
+    if dst.exists() {
+        anyhow::bail!("Template '{}' already exists", template_name);
+    }
+
+    let source = fetch_source(template_path, None)?;
+    let mut translation = match from {
+        ImportFormat::Cookiecutter => translate_cookiecutter(&source.path)?,
+        ImportFormat::Copier => translate_copier(&source.path)?,
+    };
+    translation.config.name = template_name.to_string();
+    translation.config.description = format!("Imported from {} ({})", template_path, from.name());
+
+    // Don't leave a half-imported template behind
+    if let Err(e) = write_template(&mut translation, &dst) {
+        let _ = fs::remove_dir_all(&dst);
+        return Err(e);
+    }
+
+    println!(
+        "Template '{}' imported from {} ({} variable(s)).",
+        style(template_name).cyan().bold(),
+        from.name(),
+        translation.config.variables.len()
+    );
+    if !translation.untranslated.is_empty() {
+        println!("\n{}", style("Not translated:").yellow().bold());
+        for line in &translation.untranslated {
+            println!("  {} {}", style("!").yellow(), line);
+        }
+    }
+    println!(
+        "\nCheck it with: {}",
+        style(format!("scaffold validate {}", dst.display())).cyan()
+    );
+    Ok(())
+}
+
+/// Copy the translated files, then write template.toml
+fn write_template(translation: &mut Translation, dst: &Path) -> Result<()> {
+    let mut out = DiskFs;
+    out.create_dir_all(dst)
+        .with_context(|| format!("Failed to create '{}'", dst.display()))?;
+    let mut verbatim = Vec::new();
+    let root = translation.root.clone();
+    copy_files(translation, &root, dst, "", &mut out, &mut verbatim)?;
+    translation.config.copy_without_render.extend(verbatim);
+
+    let toml = toml::to_string_pretty(&translation.config)
+        .context("Failed to serialize template config")?;
+    fs::write(dst.join("template.toml"), toml).context("Failed to write template.toml")?;
+    Ok(())
+}
+
+/// Copy a directory of the source template, renaming and rewriting as needed.
+/// Files copier wouldn't render but which contain Jinja are added to `verbatim`.
+fn copy_files(
+    translation: &mut Translation,
+    src: &Path,
+    dst: &Path,
+    prefix: &str,
+    out: &mut dyn Vfs,
+    verbatim: &mut Vec<String>,
+) -> Result<()> {
+    let fs = DiskFs;
+    for path in fs
+        .read_dir(src)
+        .with_context(|| format!("Failed to read '{}'", src.display()))?
+    {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let relative = format!("{}{}", prefix, file_name);
+        if translation.exclude.is_match(&relative) {
+            continue;
+        }
+
+        let kind = fs.kind(&path)?;
+        let mut name = translation.rewrite(&file_name);
+        let mut render = true;
+        if let Some(suffix) = &translation.templates_suffix {
+            if kind == EntryKind::File {
+                match name.strip_suffix(suffix.as_str()) {
+                    Some(stripped) => name = stripped.to_string(),
+                    None => render = false,
+                }
+            }
+        }
+        let target = dst.join(&name);
+        let output_relative = format!("{}{}", prefix, name);
+
+        match kind {
+            EntryKind::Dir => {
+                out.create_dir_all(&target)?;
+                copy_files(
+                    translation,
+                    &path,
+                    &target,
+                    &format!("{}/", output_relative),
+                    out,
+                    verbatim,
+                )?;
+            }
+            EntryKind::Symlink => {
+                let link = fs.read_link(&path)?;
+                out.symlink(&link, &target, path.is_dir())?;
+            }
+            EntryKind::File => {
+                let contents = fs.read(&path)?;
+                let mode = fs.mode(&path)?;
+                let contents = match String::from_utf8(contents) {
+                    Ok(text) if !render => {
+                        if ["{{", "{%", "{#"].iter().any(|m| text.contains(m)) {
+                            verbatim.push(output_relative.clone());
+                        }
+                        text.into_bytes()
+                    }
+                    Ok(text) => {
+                        let text = translation.rewrite(&text);
+                        if let Err(e) = JinjaRenderer::default().check(&text) {
+                            translation.untranslated.push(format!(
+                                "{}: the jinja engine can't parse it: {}",
+                                output_relative,
+                                e.to_string().lines().next().unwrap_or_default()
+                            ));
+                        }
+                        text.into_bytes()
+                    }
+                    // Binary files are copied as-is
+                    Err(e) => e.into_bytes(),
+                };
+                out.write(&target, &contents, mode)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn translate_cookiecutter(src: &Path) -> Result<Translation> {
+    let json = fs::read_to_string(src.join("cookiecutter.json"))
+        .context("cookiecutter.json not found in template directory")?;
+    // Parsed into a YAML mapping, which keeps the order variables are asked in
+    let context: Value =
+        serde_json::from_str(&json).context("Failed to parse cookiecutter.json")?;
+    let Value::Mapping(context) = context else {
+        anyhow::bail!("cookiecutter.json must be an object");
+    };
+
+    // The project directory, e.g. {{cookiecutter.project_slug}}, holds the files
+    let mut project_dir = None;
+    let mut untranslated = Vec::new();
+    for path in DiskFs.read_dir(src)? {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if name.contains("{{") && path.is_dir() {
+            untranslated.push(format!(
+                "{}/: its contents are the template's files; the output directory (-o) takes its place",
+                name
+            ));
+            project_dir = Some(path);
+        } else if name == "hooks" {
+            untranslated.push(
+                "hooks/: cookiecutter hooks aren't run; port them to [hooks] post_create or a Rhai script"
+                    .to_string(),
+            );
+        }
+    }
+    let root = project_dir.context("No {{cookiecutter.*}} project directory found")?;
+
+    let prompts = context
+        .get("__prompts__")
+        .and_then(Value::as_mapping)
+        .cloned()
+        .unwrap_or_default();
+    let prompt = |name: &str| match prompts.get(name) {
+        Some(Value::String(prompt)) => Some(prompt.clone()),
+        Some(Value::Mapping(prompt)) => prompt
+            .get("__prompt__")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        _ => None,
+    };
+
+    // Private variables (`_name`, `__name`) become computed variables without the
+    // underscores, unless that name is taken
+    let mut references = HashMap::new();
+    for key in context.keys().filter_map(Value::as_str) {
+        let trimmed = key.trim_start_matches('_');
+        if key.starts_with('_') && !trimmed.is_empty() && !context.contains_key(trimmed) {
+            references.insert(key.to_string(), trimmed.to_string());
+        }
+    }
+    let rewrite = |text: &str| rewrite_references(text, &references);
+
+    let mut config = ImportedConfig::new();
+    for (key, value) in &context {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        match key {
+            "__prompts__" => {}
+            "_copy_without_render" => config
+                .copy_without_render
+                .extend(strings(value).iter().map(|p| rewrite(p))),
+            "_extensions" | "_jinja2_env_vars" | "_new_lines" => {
+                untranslated.push(format!("{}: not supported", key))
+            }
+            _ if key.starts_with('_') => match scalar(value) {
+                Some(value) => config.computed.push(ComputedVariable {
+                    name: references
+                        .get(key)
+                        .cloned()
+                        .unwrap_or_else(|| key.to_string()),
+                    value: rewrite(&value),
+                }),
+                None => untranslated.push(format!(
+                    "{}: only text private variables are supported",
+                    key
+                )),
+            },
+            _ => {
+                let mut variable = ImportedVariable::new(key, prompt(key));
+                match value {
+                    Value::Bool(b) => {
+                        variable.var_type = Some("bool");
+                        variable.default = Some(b.to_string());
+                    }
+                    Value::Sequence(items) => {
+                        variable.var_type = Some("choice");
+                        variable.choices = items
+                            .iter()
+                            .filter_map(scalar)
+                            .map(|choice| rewrite(&choice))
+                            .collect();
+                        variable.default = variable.choices.first().cloned();
+                        if variable.choices.is_empty() {
+                            untranslated.push(format!("{}: choices must be text", key));
+                            continue;
+                        }
+                    }
+                    Value::Mapping(_) => {
+                        untranslated
+                            .push(format!("{}: dictionary variables aren't supported", key));
+                        continue;
+                    }
+                    other => variable.default = scalar(other).map(|d| rewrite(&d)),
+                }
+                config.variables.push(variable);
+            }
+        }
+    }
+
+    Ok(Translation {
+        config,
+        root,
+        exclude: PathPatterns::default(),
+        templates_suffix: None,
+        references: Some(references),
+        untranslated,
+    })
+}
+
+/// Resolve copier's `_subdirectory`, which must stay inside the template: a
+/// copier.yml from a remote repository could otherwise point at any local directory
+fn subdirectory_root(src: &Path, subdirectory: &str) -> Result<PathBuf> {
+    let relative = subdirectory.strip_prefix("./").unwrap_or(subdirectory);
+    let relative = relative.trim_end_matches(['/', '\\']);
+    check_relative_path(relative)?;
+    let root = src.join(relative);
+    ensure_within(&root, src)?;
+    // Also catch symlinks inside the template that lead out of it
+    if let (Ok(resolved), Ok(src)) = (root.canonicalize(), src.canonicalize()) {
+        ensure_within(&resolved, &src)?;
+    }
+    Ok(root)
+}
+
+fn translate_copier(src: &Path) -> Result<Translation> {
+    let config_path = ["copier.yml", "copier.yaml"]
+        .iter()
+        .map(|name| src.join(name))
+        .find(|path| path.is_file())
+        .context("copier.yml not found in template directory")?;
+    let yaml = fs::read_to_string(&config_path).context("Failed to read copier.yml")?;
+    let questions: Value = serde_yaml::from_str(&yaml).context("Failed to parse copier.yml")?;
+    let Value::Mapping(questions) = questions else {
+        anyhow::bail!("copier.yml must be a mapping");
+    };
+
+    let mut config = ImportedConfig::new();
+    let mut root = src.to_path_buf();
+    let mut exclude: Vec<String> = COPIER_EXCLUDE.iter().map(|p| p.to_string()).collect();
+    let mut templates_suffix = ".jinja".to_string();
+    let mut post_create = Vec::new();
+    let mut untranslated = Vec::new();
+
+    for (key, value) in &questions {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        match key {
+            "_subdirectory" => {
+                if let Some(subdirectory) = value.as_str() {
+                    root = subdirectory_root(src, subdirectory).with_context(|| {
+                        format!("Invalid copier _subdirectory '{}'", subdirectory)
+                    })?;
+                }
+            }
+            "_exclude" => {
+                exclude = strings(value);
+                exclude.extend([".git".to_string(), config_path_name(&config_path)]);
+            }
+            "_templates_suffix" => templates_suffix = scalar(value).unwrap_or_default(),
+            "_copy_without_render" => config.copy_without_render.extend(strings(value)),
+            "_tasks" => {
+                for task in value.as_sequence().into_iter().flatten() {
+                    match task.as_str() {
+                        Some(command) => post_create.push(command.to_string()),
+                        None => untranslated
+                            .push("_tasks: only plain command strings are translated".to_string()),
+                    }
+                }
+            }
+            _ if key.starts_with('_') => untranslated.push(format!("{}: not supported", key)),
+            _ => match copier_question(key, value) {
+                Ok((variable, notes)) => {
+                    config.variables.push(variable);
+                    untranslated.extend(notes);
+                }
+                Err(note) => untranslated.push(note),
+            },
+        }
+    }
+
+    if !post_create.is_empty() {
+        config.hooks = Some(ImportedHooks { post_create });
+    }
+    anyhow::ensure!(
+        root.is_dir(),
+        "_subdirectory '{}' does not exist",
+        root.display()
+    );
+
+    Ok(Translation {
+        config,
+        root,
+        exclude: PathPatterns::new(&exclude).context("Invalid _exclude pattern")?,
+        templates_suffix: Some(templates_suffix).filter(|s| !s.is_empty()),
+        references: None,
+        untranslated,
+    })
+}
+
+/// Translate one copier question, with notes on what was lost, or why it
+/// couldn't be translated at all
+fn copier_question(
+    name: &str,
+    question: &Value,
+) -> std::result::Result<(ImportedVariable, Vec<String>), String> {
+    let Value::Mapping(question) = question else {
+        // The short form is just the default
+        let mut variable = ImportedVariable::new(name, None);
+        match question {
+            Value::Bool(b) => {
+                variable.var_type = Some("bool");
+                variable.default = Some(b.to_string());
+            }
+            Value::Sequence(_) | Value::Tagged(_) => {
+                return Err(format!(
+                    "{}: only text and bool defaults are supported",
+                    name
+                ));
+            }
+            other => variable.default = scalar(other),
+        }
+        return Ok((variable, Vec::new()));
+    };
+
+    let field = |key: &str| question.get(key);
+    let mut notes = Vec::new();
+    let mut variable = ImportedVariable::new(
+        name,
+        field("help").and_then(Value::as_str).map(str::to_string),
+    );
+    let kind = field("type").and_then(Value::as_str).unwrap_or("str");
+    let default = field("default");
+
+    if let Some(choices) = field("choices") {
+        variable.choices = choice_values(choices);
+        if variable.choices.is_empty() {
+            return Err(format!("{}: choices must be text", name));
+        }
+        if field("multiselect").and_then(Value::as_bool) == Some(true) {
+            variable.var_type = Some("multi");
+            variable.default = default
+                .map(|d| strings(d).join(MULTI_SEPARATOR))
+                .filter(|d| !d.is_empty());
+        } else {
+            variable.var_type = Some("choice");
+            variable.default = default.and_then(scalar);
+        }
+    } else if kind == "bool" || matches!(default, Some(Value::Bool(_))) {
+        variable.var_type = Some("bool");
+        variable.default = default.and_then(scalar);
+    } else {
+        if kind != "str" {
+            notes.push(format!("{}: imported as a string (was `{}`)", name, kind));
+        }
+        if field("secret").and_then(Value::as_bool) == Some(true) {
+            variable.var_type = Some("secret");
+        }
+        variable.default = default.and_then(scalar);
+    }
+
+    for key in ["when", "validator"] {
+        if field(key).is_some() {
+            notes.push(format!("{}: `{}` isn't supported", name, key));
+        }
+    }
+    Ok((variable, notes))
+}
+
+/// Choice values from a list (of values or `[label, value]` pairs) or a
+/// label-to-value mapping
+fn choice_values(choices: &Value) -> Vec<String> {
+    match choices {
+        Value::Sequence(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::Sequence(pair) => pair.get(1).and_then(scalar),
+                other => scalar(other),
+            })
+            .collect(),
+        Value::Mapping(labels) => labels.values().filter_map(scalar).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A scalar as the string templates see; None for null and collections
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// A list of scalars (or a single one) as strings
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::Sequence(items) => items.iter().filter_map(scalar).collect(),
+        other => scalar(other).into_iter().collect(),
+    }
+}
+
+fn config_path_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// A description from a variable name: `project_slug` -> "Project slug"
+pub(super) fn describe(name: &str) -> String {
+    let words = name.replace(['_', '-'], " ");
+    let mut chars = words.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_references() {
+        let references = HashMap::from([("__slug".to_string(), "slug".to_string())]);
+        assert_eq!(
+            rewrite_references(
+                "cookiecutter.json: {{ cookiecutter.name | upper }} {% if cookiecutter.__slug %}",
+                &references
+            ),
+            "cookiecutter.json: {{ name | upper }} {% if slug %}"
+        );
+    }
+
+    #[test]
+    fn test_copier_question() {
+        let question: Value = serde_yaml::from_str(
+            "type: str\nhelp: Features\nchoices:\n  Continuous integration: ci\n  Docs: docs\n\
+             multiselect: true\ndefault: [ci, docs]\nwhen: \"{{ advanced }}\"",
+        )
+        .unwrap();
+        let (variable, notes) = copier_question("features", &question).unwrap();
+        assert_eq!(
+            variable,
+            ImportedVariable {
+                name: "features".to_string(),
+                description: "Features".to_string(),
+                default: Some("ci, docs".to_string()),
+                var_type: Some("multi"),
+                choices: vec!["ci".to_string(), "docs".to_string()],
+            }
+        );
+        assert_eq!(notes, vec!["features: `when` isn't supported"]);
+
+        let (variable, _) = copier_question("use_docker", &Value::Bool(false)).unwrap();
+        assert_eq!(variable.var_type, Some("bool"));
+        assert_eq!(variable.description, "Use docker");
+        assert!(copier_question("ports", &serde_yaml::from_str("[80]").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_subdirectory_root_stays_inside_template() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let src = temp.path().join("clone");
+        fs::create_dir_all(src.join("template")).unwrap();
+        assert_eq!(
+            subdirectory_root(&src, "./template/").unwrap(),
+            src.join("template")
+        );
+        for subdirectory in ["/home/me/.ssh", "../../..", "template/../..", ""] {
+            assert!(
+                subdirectory_root(&src, subdirectory).is_err(),
+                "{}",
+                subdirectory
+            );
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(temp.path(), src.join("escape")).unwrap();
+            assert!(subdirectory_root(&src, "escape").is_err());
+        }
+    }
+}
@@ -1,11 +1,28 @@
 use anyhow::Result;
 use console::style;
+use serde::Serialize;
 use std::path::Path;
 
-use crate::template::{TemplateSource, get_available_templates};
+use project_scaffold::output::{OutputFormat, print_json};
+use project_scaffold::template::{TemplateConfig, TemplateSource};
 
-pub fn show_template_info(templates_dir: &Path, template_name: &str) -> Result<()> {
-    let templates = get_available_templates(templates_dir)?;
+use super::available_templates;
+
+/// A template's full schema, as printed by `scaffold info --format json`
+#[derive(Serialize)]
+struct TemplateInfo<'a> {
+    name: &'a str,
+    source: TemplateSource,
+    path: &'a Path,
+    config: &'a TemplateConfig,
+}
+
+pub fn show_template_info(
+    templates_dir: &Path,
+    template_name: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let templates = available_templates(templates_dir)?;
 
     let template = templates
         .iter()
@@ -14,6 +31,15 @@ pub fn show_template_info(templates_dir: &Path, template_name: &str) -> Result<(
 
     let config = &template.config;
 
+    if format == OutputFormat::Json {
+        return print_json(&TemplateInfo {
+            name: &template.dir_name,
+            source: template.source,
+            path: &template.path,
+            config,
+        });
+    }
+
     let source_tag = match template.source {
         TemplateSource::Bundled => format!(" {}", style("[bundled]").dim()),
         TemplateSource::Local => String::new(),
@@ -22,6 +48,32 @@ pub fn show_template_info(templates_dir: &Path, template_name: &str) -> Result<(
     println!("{}{}", style(&config.name).cyan().bold(), source_tag);
     println!("{}\n", config.description);
 
+    let mut metadata = Vec::new();
+    if let Some(version) = &config.version {
+        metadata.push(("Version", version.clone()));
+    }
+    if !config.authors.is_empty() {
+        metadata.push(("Authors", config.authors.join(", ")));
+    }
+    if let Some(license) = &config.license {
+        metadata.push(("License", license.clone()));
+    }
+    if let Some(homepage) = &config.homepage {
+        metadata.push(("Homepage", homepage.clone()));
+    }
+    if !config.tags.is_empty() {
+        metadata.push(("Tags", config.tags.join(", ")));
+    }
+    if let Some(required) = &config.min_scaffold_version {
+        metadata.push(("Requires", format!("scaffold {} or newer", required)));
+    }
+    for (label, value) in &metadata {
+        println!("{} {}", style(format!("{}:", label)).bold(), value);
+    }
+    if !metadata.is_empty() {
+        println!();
+    }
+
     if config.variables.is_empty() {
         println!("No variables defined.");
     } else {
@@ -30,6 +82,8 @@ pub fn show_template_info(templates_dir: &Path, template_name: &str) -> Result<(
             let var_type = var.var_type.as_deref().unwrap_or("string");
             let type_str = match var_type {
                 "choice" => format!(" [{}]", var.choices.join("|")),
+                "multi" => format!(" [{}, ...]", var.choices.join("|")),
+                "secret" => " [secret]".to_string(),
                 "bool" => " [yes/no]".to_string(),
                 _ => String::new(),
             };
@@ -48,13 +102,24 @@ pub fn show_template_info(templates_dir: &Path, template_name: &str) -> Result<(
         }
     }
 
+    if !config.computed.is_empty() {
+        println!("\nComputed:\n");
+        for computed in &config.computed {
+            println!(
+                "  {} = {}",
+                style(&computed.name).green().bold(),
+                style(&computed.value).dim()
+            );
+        }
+    }
+
     if !config.conditionals.is_empty() {
         println!("\nConditional files:\n");
         for cond in &config.conditionals {
-            let action = if cond.include.is_some() {
-                format!("include {}", cond.include.as_ref().unwrap())
-            } else {
-                format!("exclude {}", cond.exclude.as_ref().unwrap())
+            let action = match (&cond.include, &cond.exclude) {
+                (Some(include), _) => format!("include {}", include),
+                (None, Some(exclude)) => format!("exclude {}", exclude),
+                (None, None) => continue,
             };
             println!(
                 "  {} when {}",
@@ -65,6 +130,12 @@ pub fn show_template_info(templates_dir: &Path, template_name: &str) -> Result<(
     }
 
     if let Some(ref hooks) = config.hooks {
+        if !hooks.scripts.is_empty() {
+            println!("\nScripts:\n");
+            for script in &hooks.scripts {
+                println!("  {}", style(script).dim());
+            }
+        }
         if !hooks.post_create.is_empty() {
             println!("\nPost-create hooks:\n");
             for cmd in &hooks.post_create {
@@ -73,5 +144,14 @@ pub fn show_template_info(templates_dir: &Path, template_name: &str) -> Result<(
         }
     }
 
+    if let Some(ref git) = config.git {
+        if git.init {
+            println!(
+                "\nGit: initializes a repository on branch {}",
+                style(&git.branch).cyan()
+            );
+        }
+    }
+
     Ok(())
 }
@@ -1,12 +1,56 @@
 use anyhow::Result;
 use console::style;
+use serde::Serialize;
 use std::path::Path;
 
-use crate::template::{TemplateSource, get_available_templates};
+use project_scaffold::output::{OutputFormat, print_json};
+use project_scaffold::template::TemplateSource;
 
-pub fn list_templates(templates_dir: &Path) -> Result<()> {
-    let templates = get_available_templates(templates_dir)?;
+use super::available_templates;
 
+/// A template as listed by `scaffold list --format json`
+#[derive(Serialize)]
+struct TemplateEntry<'a> {
+    name: &'a str,
+    display_name: &'a str,
+    description: &'a str,
+    version: Option<&'a str>,
+    tags: &'a [String],
+    source: TemplateSource,
+    path: &'a Path,
+}
+
+#[derive(Serialize)]
+struct TemplateList<'a> {
+    templates: Vec<TemplateEntry<'a>>,
+}
+
+pub fn list_templates(templates_dir: &Path, tags: &[String], format: OutputFormat) -> Result<()> {
+    let templates: Vec<_> = available_templates(templates_dir)?
+        .into_iter()
+        .filter(|t| tags.iter().all(|tag| t.config.has_tag(tag)))
+        .collect();
+
+    if format == OutputFormat::Json {
+        let entries: Vec<TemplateEntry> = templates
+            .iter()
+            .map(|t| TemplateEntry {
+                name: &t.dir_name,
+                display_name: &t.config.name,
+                description: &t.config.description,
+                version: t.config.version.as_deref(),
+                tags: &t.config.tags,
+                source: t.source,
+                path: &t.path,
+            })
+            .collect();
+        return print_json(&TemplateList { templates: entries });
+    }
+
+    if templates.is_empty() && !tags.is_empty() {
+        println!("No templates tagged {}.", style(tags.join(", ")).cyan());
+        return Ok(());
+    }
     if templates.is_empty() {
         println!(
             "No templates available. Run '{}' to add a template first.",
@@ -21,11 +65,32 @@ pub fn list_templates(templates_dir: &Path) -> Result<()> {
             TemplateSource::Bundled => format!(" {}", style("[bundled]").dim()),
             TemplateSource::Local => String::new(),
         };
+        let version = template
+            .config
+            .version
+            .as_ref()
+            .map(|v| {
+                format!(
+                    " {}",
+                    style(format!("v{}", v.trim_start_matches('v'))).dim()
+                )
+            })
+            .unwrap_or_default();
+        let tags = if template.config.tags.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " {}",
+                style(format!("#{}", template.config.tags.join(" #"))).dim()
+            )
+        };
         println!(
-            "  {}{} - {}",
+            "  {}{}{} - {}{}",
             style(&template.dir_name).cyan().bold(),
+            version,
             source_tag,
-            template.config.description
+            template.config.description,
+            tags
         );
     }
 
@@ -1,15 +1,47 @@
+use anyhow::Result;
+use console::style;
+use std::path::Path;
+
+use project_scaffold::template::{AvailableTemplate, get_available_templates};
+
 mod add;
 pub mod config;
 mod create;
+mod doctor;
+mod extract;
+mod import;
 mod info;
 mod list;
+mod pack;
 mod remove;
+mod template;
+mod test;
 mod validate;
 
 pub use add::add_template;
 pub use config::{ConfigAction, handle_config_command};
-pub use create::create_project_interactive;
+pub use create::{CreateOptions, create_project_interactive};
+pub use doctor::run_doctor;
+pub use extract::extract_template;
+pub use import::{ImportFormat, import_template};
 pub use info::show_template_info;
 pub use list::list_templates;
+pub use pack::pack_template;
 pub use remove::remove_template;
+pub use template::{TemplateAction, handle_template_command};
+pub use test::{TestOptions, run_template_tests};
 pub use validate::validate_template;
+
+/// Every available template, warning on stderr about directories that were skipped
+fn available_templates(templates_dir: &Path) -> Result<Vec<AvailableTemplate>> {
+    let scan = get_available_templates(templates_dir)?;
+    for skipped in &scan.skipped {
+        eprintln!(
+            "{}: Skipping '{}': {}",
+            style("Warning").yellow(),
+            skipped.dir_name,
+            skipped.reason
+        );
+    }
+    Ok(scan.templates)
+}
@@ -0,0 +1,42 @@
+use anyhow::{Context, Result};
+use console::style;
+use std::fs;
+use std::path::Path;
+
+use project_scaffold::archive::{ArchiveFormat, pack};
+use project_scaffold::output::OutputFormat;
+use project_scaffold::template::load_template_config;
+
+use super::add::validate_template_name;
+use super::validate::validate_template;
+
+/// Validate a template and pack it into an archive with a checksum file
+pub fn pack_template(template_path: &str, output: &Path, zip: bool) -> Result<()> {
+    validate_template(template_path, false, OutputFormat::Text)?;
+
+    let template = Path::new(template_path);
+    let config = load_template_config(&template.join("template.toml"))?;
+    // Archives are named after the template directory, like installed templates
+    let name = fs::canonicalize(template)?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    validate_template_name(&name)
+        .with_context(|| format!("Directory name '{}' can't name an archive", name))?;
+
+    let format = if zip {
+        ArchiveFormat::Zip
+    } else {
+        ArchiveFormat::TarGz
+    };
+    let packed = pack(template, &name, config.version.as_deref(), output, format)?;
+
+    println!(
+        "\nPacked '{}' ({} file(s)) into {}",
+        style(&name).cyan().bold(),
+        packed.manifest.files.len(),
+        style(packed.path.display()).cyan()
+    );
+    println!("  sha256 {}", packed.sha256);
+    Ok(())
+}
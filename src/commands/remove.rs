@@ -3,10 +3,12 @@ use console::style;
 use std::fs;
 use std::path::Path;
 
-use crate::template::{TemplateSource, get_available_templates};
+use project_scaffold::template::TemplateSource;
+
+use super::available_templates;
 
 pub fn remove_template(templates_dir: &Path, template_name: &str) -> Result<()> {
-    let templates = get_available_templates(templates_dir)?;
+    let templates = available_templates(templates_dir)?;
 
     let template = templates
         .iter()
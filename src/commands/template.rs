@@ -0,0 +1,417 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use console::style;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use project_scaffold::prompt::{MULTI_SEPARATOR, Prompter, ScriptedPrompter};
+use project_scaffold::variables::apply_filter;
+
+use super::add::validate_template_name;
+use super::create::default_prompter;
+use super::extract::validate_variable_name;
+use super::import::describe;
+
+/// Variable types offered by `template new`, the first being the default
+const VARIABLE_TYPES: &[&str] = &["string", "bool", "choice", "multi", "secret"];
+
+/// Bool variable added for the sample conditional
+const CONDITIONAL_VARIABLE: &str = "include_docs";
+
+#[derive(Subcommand)]
+pub enum TemplateAction {
+    /// Create a template skeleton that passes `scaffold validate`
+    New {
+        /// Directory name of the template
+        name: String,
+        /// Directory to create the template in (defaults to .templates/<name>)
+        #[arg(long)]
+        path: Option<String>,
+        /// Don't prompt; use a project_name variable and the flags below
+        #[arg(short, long)]
+        yes: bool,
+        /// Include a sample conditional directory
+        #[arg(long)]
+        conditional: bool,
+        /// Include a sample post-create hook
+        #[arg(long)]
+        hook: bool,
+        /// Include a sample [[tests]] case
+        #[arg(long)]
+        test: bool,
+    },
+}
+
+/// A variable of the new template
+struct SkeletonVariable {
+    name: String,
+    description: String,
+    var_type: &'static str,
+    default: Option<String>,
+    choices: Vec<String>,
+}
+
+/// Everything `template new` asked for
+struct Skeleton {
+    dir_name: String,
+    name: String,
+    description: String,
+    variables: Vec<SkeletonVariable>,
+    conditional: bool,
+    hook: bool,
+    test: bool,
+}
+
+/// Handle the template subcommand
+pub fn handle_template_command(templates_dir: &Path, action: TemplateAction) -> Result<()> {
+    match action {
+        TemplateAction::New {
+            name,
+            path,
+            yes,
+            conditional,
+            hook,
+            test,
+        } => {
+            validate_template_name(&name)?;
+            let dst = path
+                .map(PathBuf::from)
+                .unwrap_or_else(|| templates_dir.join(&name));
+            if dst.exists() {
+                anyhow::bail!("'{}' already exists", dst.display());
+            }
+
+            let mut prompter: Box<dyn Prompter> = if yes {
+                Box::new(ScriptedPrompter::default())
+            } else {
+                default_prompter()
+            };
+            let mut skeleton = ask_skeleton(prompter.as_mut(), &name, !yes)?;
+            skeleton.conditional |= conditional;
+            skeleton.hook |= hook;
+            skeleton.test |= test;
+
+            for (relative, contents) in skeleton_files(&mut skeleton) {
+                let path = dst.join(&relative);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("Failed to create '{}'", parent.display()))?;
+                }
+                fs::write(&path, contents)
+                    .with_context(|| format!("Failed to write '{}'", path.display()))?;
+                println!("  {} {}", style("+").green(), relative);
+            }
+
+            println!(
+                "\nTemplate '{}' created at {}.",
+                style(&skeleton.name).cyan().bold(),
+                dst.display()
+            );
+            println!(
+                "Check it with: {}",
+                style(format!("scaffold validate {}", dst.display())).cyan()
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Ask for the template's name, description and variables, and (when `samples`)
+/// which samples to include
+fn ask_skeleton(prompter: &mut dyn Prompter, dir_name: &str, samples: bool) -> Result<Skeleton> {
+    let default_name = apply_filter("title_case", dir_name).unwrap_or_default();
+    let name = prompter.string("name", "Template name", Some(&default_name))?;
+    let description = prompter.string("description", "Description", Some("A new template"))?;
+
+    let mut variables: Vec<SkeletonVariable> = Vec::new();
+    loop {
+        let key = format!("variable_{}", variables.len() + 1);
+        // Start with the variable almost every template has
+        let default = if variables.is_empty() {
+            "project_name"
+        } else {
+            ""
+        };
+        let var_name = prompter.string(&key, "Variable name (empty to finish)", Some(default))?;
+        if var_name.is_empty() {
+            break;
+        }
+        if let Err(e) = validate_variable_name(&var_name) {
+            eprintln!("{}: {}", style("Warning").yellow(), e);
+            continue;
+        }
+        if variables.iter().any(|v| v.name == var_name) {
+            eprintln!(
+                "{}: Variable '{}' is already declared",
+                style("Warning").yellow(),
+                var_name
+            );
+            continue;
+        }
+        variables.push(ask_variable(prompter, &key, var_name)?);
+    }
+
+    let mut ask_sample = |name: &str, message: &str| -> Result<bool> {
+        if samples {
+            prompter.bool(name, message, false)
+        } else {
+            Ok(false)
+        }
+    };
+    Ok(Skeleton {
+        dir_name: dir_name.to_string(),
+        name,
+        description,
+        conditional: ask_sample("conditional", "Include a sample conditional directory?")?,
+        hook: ask_sample("hook", "Include a sample post-create hook?")?,
+        test: ask_sample("test", "Include a sample test case?")?,
+        variables,
+    })
+}
+
+fn ask_variable(prompter: &mut dyn Prompter, key: &str, name: String) -> Result<SkeletonVariable> {
+    let description = prompter.string(
+        &format!("{}.description", key),
+        "  Description",
+        Some(&describe(&name)),
+    )?;
+    let types: Vec<String> = VARIABLE_TYPES.iter().map(|t| t.to_string()).collect();
+    let var_type =
+        VARIABLE_TYPES[prompter.choice(&format!("{}.type", key), "  Type", &types, 0)?];
+
+    let mut choices = Vec::new();
+    let default = match var_type {
+        "bool" => Some(
+            prompter
+                .bool(&format!("{}.default", key), "  Default", false)?
+                .to_string(),
+        ),
+        "choice" | "multi" => {
+            let answer = prompter.string(
+                &format!("{}.choices", key),
+                "  Choices (comma-separated)",
+                Some("one, two"),
+            )?;
+            choices = answer
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(str::to_string)
+                .collect();
+            if choices.is_empty() {
+                choices = vec!["one".to_string(), "two".to_string()];
+            }
+            if var_type == "choice" {
+                let index =
+                    prompter.choice(&format!("{}.default", key), "  Default", &choices, 0)?;
+                Some(choices[index].clone())
+            } else {
+                let defaults = vec![false; choices.len()];
+                let picked = prompter.multi(
+                    &format!("{}.default", key),
+                    "  Defaults",
+                    &choices,
+                    &defaults,
+                )?;
+                let picked: Vec<&str> = picked.iter().map(|&i| choices[i].as_str()).collect();
+                Some(picked.join(MULTI_SEPARATOR)).filter(|d| !d.is_empty())
+            }
+        }
+        // Secrets shouldn't have a default checked into the template
+        "secret" => None,
+        _ => {
+            let default = if name == "project_name" {
+                "my-project"
+            } else {
+                ""
+            };
+            Some(prompter.string(&format!("{}.default", key), "  Default", Some(default))?)
+                .filter(|d| !d.is_empty())
+        }
+    };
+
+    Ok(SkeletonVariable {
+        name,
+        description,
+        var_type,
+        default,
+        choices,
+    })
+}
+
+/// The files of the new template, by template-relative path
+fn skeleton_files(skeleton: &mut Skeleton) -> Vec<(String, String)> {
+    if skeleton.conditional
+        && !skeleton
+            .variables
+            .iter()
+            .any(|v| v.name == CONDITIONAL_VARIABLE)
+    {
+        skeleton.variables.push(SkeletonVariable {
+            name: CONDITIONAL_VARIABLE.to_string(),
+            description: "Include the docs/ directory".to_string(),
+            var_type: "bool",
+            default: Some("false".to_string()),
+            choices: Vec::new(),
+        });
+    }
+    // The first text variable titles the README and names things in the samples
+    let title = skeleton
+        .variables
+        .iter()
+        .find(|v| v.var_type == "string")
+        .map(|v| format!("{{{{{}}}}}", v.name));
+
+    let mut files = vec![
+        (
+            "template.toml".to_string(),
+            template_toml(skeleton, title.as_deref()),
+        ),
+        (
+            "README.md".to_string(),
+            format!(
+                "# {}\n\nCreated with `scaffold create {}`.\n",
+                title.as_deref().unwrap_or(&skeleton.name),
+                skeleton.dir_name
+            ),
+        ),
+        ("example.txt".to_string(), example_file(skeleton)),
+    ];
+    if skeleton.conditional {
+        files.push((
+            "docs/index.md".to_string(),
+            format!(
+                "# {} documentation\n\nThis directory is only created when {} is true.\n",
+                title.as_deref().unwrap_or(&skeleton.name),
+                CONDITIONAL_VARIABLE
+            ),
+        ));
+    }
+    files
+}
+
+fn template_toml(skeleton: &Skeleton, title: Option<&str>) -> String {
+    let mut toml = format!(
+        "name = {}\ndescription = {}\nversion = \"0.1.0\"\n",
+        quote(&skeleton.name),
+        quote(&skeleton.description)
+    );
+
+    for (i, variable) in skeleton.variables.iter().enumerate() {
+        if i == 0 {
+            toml.push_str(
+                "\n# Asked for by `scaffold create`; use them as {{name}} in file contents and names\n",
+            );
+        } else {
+            toml.push('\n');
+        }
+        toml.push_str(&format!(
+            "[[variables]]\nname = {}\ndescription = {}\n",
+            quote(&variable.name),
+            quote(&variable.description)
+        ));
+        if variable.var_type != "string" {
+            toml.push_str(&format!("type = {}\n", quote(variable.var_type)));
+        }
+        if !variable.choices.is_empty() {
+            let choices: Vec<String> = variable.choices.iter().map(|c| quote(c)).collect();
+            toml.push_str(&format!("choices = [{}]\n", choices.join(", ")));
+        }
+        if let Some(default) = &variable.default {
+            toml.push_str(&format!("default = {}\n", quote(default)));
+        }
+    }
+
+    if skeleton.conditional {
+        toml.push_str(&format!(
+            "\n# Only created when the condition holds\n[[conditionals]]\ninclude = \"docs\"\nwhen = \"{} == true\"\n",
+            CONDITIONAL_VARIABLE
+        ));
+    }
+    if skeleton.hook {
+        let created = title.map_or(String::new(), |title| format!(" {}", title));
+        toml.push_str(&format!(
+            "\n# Run in the new project once it's created\n[hooks]\npost_create = [{}]\n",
+            quote(&format!("echo Created{}", created))
+        ));
+    }
+    if skeleton.test {
+        let exists = ["README.md", "example.txt"];
+        let mut absent = Vec::new();
+        if skeleton.conditional {
+            absent.push("docs");
+        }
+        toml.push_str(&format!(
+            "\n# Run by `scaffold test`\n[[tests]]\nname = \"defaults\"\nexists = [{}]\n",
+            exists
+                .iter()
+                .map(|p| quote(p))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        if !absent.is_empty() {
+            toml.push_str(&format!(
+                "absent = [{}]\n",
+                absent
+                    .iter()
+                    .map(|p| quote(p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+    toml
+}
+
+fn example_file(skeleton: &Skeleton) -> String {
+    let mut example =
+        "An example of placeholders; rename, rewrite or delete this file.\n\n".to_string();
+    for variable in &skeleton.variables {
+        example.push_str(&format!("{}: {{{{{}}}}}\n", variable.name, variable.name));
+    }
+    if let Some(variable) = skeleton.variables.iter().find(|v| v.var_type == "string") {
+        example.push_str(&format!(
+            "\nFilters change the case: {{{{ {} | snake_case }}}}, {{{{ {} | pascal_case }}}}\n",
+            variable.name, variable.name
+        ));
+    }
+    example
+}
+
+/// A TOML string literal
+fn quote(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use project_scaffold::template::TemplateConfig;
+
+    #[test]
+    fn test_skeleton_from_answers() {
+        let mut prompter = ScriptedPrompter::new([
+            ("description", "Services"),
+            ("variable_2", "database"),
+            ("variable_2.type", "choice"),
+            ("variable_2.choices", "postgres, sqlite"),
+            ("variable_2.default", "sqlite"),
+            ("conditional", "yes"),
+            ("test", "yes"),
+        ]);
+        let mut skeleton = ask_skeleton(&mut prompter, "web-service", true).unwrap();
+        assert_eq!(skeleton.name, "Web Service");
+        let files = skeleton_files(&mut skeleton);
+
+        let config: TemplateConfig = toml::from_str(&files[0].1).unwrap();
+        assert_eq!(config.description, "Services");
+        let names: Vec<&str> = config.variables.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["project_name", "database", CONDITIONAL_VARIABLE]);
+        assert_eq!(config.variables[1].default.as_deref(), Some("sqlite"));
+        assert_eq!(config.conditionals.len(), 1);
+        assert!(config.hooks.is_none());
+        assert_eq!(config.tests[0].absent, ["docs"]);
+        assert!(files.iter().any(|(path, _)| path == "docs/index.md"));
+        assert!(files[2].1.contains("database: {{database}}\n"));
+    }
+}
@@ -0,0 +1,511 @@
+use anyhow::{Context, Result};
+use console::style;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use project_scaffold::builtins::{Builtins, OsRandom, RandomSource};
+use project_scaffold::generate::Generator;
+use project_scaffold::hooks::execute_hooks;
+use project_scaffold::matrix;
+use project_scaffold::output::{OutputFormat, print_json};
+use project_scaffold::paths::ensure_within;
+use project_scaffold::snapshot::Snapshot;
+use project_scaffold::template::{
+    AvailableTemplate, TemplateConfig, TemplateTest, answer_value, find_template,
+};
+use project_scaffold::variables::apply_filter;
+
+/// Lines of a file shown when a content assertion fails
+const EXCERPT_LINES: usize = 20;
+
+/// Flags for `scaffold test`
+pub struct TestOptions {
+    /// Template name, or path to a template directory
+    pub template: String,
+    /// Only run the case with this name
+    pub case: Option<String>,
+    /// Render this answers file (TOML) instead of the template's cases
+    pub answers: Option<PathBuf>,
+    /// Compare each case with a snapshot: Some(None) uses `snapshots/<template>`
+    pub snapshot: Option<Option<PathBuf>>,
+    /// Write snapshots instead of comparing them (--update)
+    pub update: bool,
+    /// Render combinations of bool and choice variables instead of the cases
+    pub matrix: bool,
+    /// Most combinations to render; more are sampled down to this many
+    pub max: usize,
+    /// Seed for sampling combinations (random if None)
+    pub seed: Option<u64>,
+    pub format: OutputFormat,
+}
+
+/// Outcome of one `[[tests]]` case
+#[derive(Serialize)]
+struct CaseResult {
+    name: String,
+    passed: bool,
+    /// The case's snapshot was written (--update)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    snapshot_updated: bool,
+    failures: Vec<Failure>,
+}
+
+/// A failed assertion, with what was actually found
+#[derive(Serialize)]
+struct Failure {
+    message: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    detail: String,
+}
+
+impl Failure {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            detail: String::new(),
+        }
+    }
+
+    fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = detail.into();
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct TestReport<'a> {
+    template: &'a str,
+    passed: usize,
+    failed: usize,
+    tests: &'a [CaseResult],
+}
+
+/// Render each `[[tests]]` case of a template into a temp directory and check its assertions
+pub fn run_template_tests(templates_dir: &Path, options: TestOptions) -> Result<()> {
+    let TestOptions {
+        template,
+        case,
+        answers,
+        snapshot,
+        update,
+        matrix,
+        max,
+        seed,
+        format,
+    } = options;
+    let text = format.is_text();
+    let AvailableTemplate {
+        dir_name: name,
+        path: template_path,
+        config,
+        ..
+    } = find_template(templates_dir, &template)?;
+    for test in &config.tests {
+        test.check_output()?;
+    }
+
+    // Cases built here rather than read from template.toml
+    let generated = if matrix {
+        matrix_cases(&config, &name, max, seed, text)?
+    } else {
+        answers
+            .as_deref()
+            .map(read_answers)
+            .transpose()?
+            .into_iter()
+            .collect()
+    };
+    let cases: Vec<&TemplateTest> = if matrix || answers.is_some() {
+        generated.iter().collect()
+    } else {
+        config
+            .tests
+            .iter()
+            .filter(|t| case.as_ref().is_none_or(|c| &t.name == c))
+            .collect()
+    };
+    if cases.is_empty() {
+        match case {
+            Some(case) => anyhow::bail!("Template '{}' has no test named '{}'", name, case),
+            None => anyhow::bail!("Template '{}' has no [[tests]] in template.toml", name),
+        }
+    }
+
+    // Snapshots of each case live in <dir>/<case output name>
+    let snapshot_dir =
+        snapshot.map(|dir| dir.unwrap_or_else(|| Path::new("snapshots").join(&name)));
+
+    if text && !matrix {
+        println!(
+            "Running {} test(s) for {}\n",
+            cases.len(),
+            style(&name).cyan().bold()
+        );
+    }
+
+    // Each case renders into its own directory here; removed when dropped
+    let root = tempfile::Builder::new()
+        .prefix("scaffold-test-")
+        .tempdir()
+        .context("Failed to create temp directory")?;
+    let mut results = Vec::new();
+    for test in cases {
+        let output_name = test
+            .output
+            .clone()
+            .or_else(|| apply_filter("kebab_case", &test.name))
+            .unwrap_or_else(|| test.name.clone());
+        let snapshot = snapshot_dir
+            .as_ref()
+            .map(|dir| Snapshot::named(dir, &output_name))
+            .transpose()?;
+        let check = SnapshotCheck {
+            snapshot: snapshot.as_ref(),
+            update,
+        };
+        let output = root.path().join(&output_name);
+        ensure_within(&output, root.path())?;
+        let outcome = run_case(&template_path, &config, test, &output, check);
+        // Cases may share an output name, and a matrix renders hundreds of them
+        if output.exists() {
+            fs::remove_dir_all(&output).context("Failed to clean up temp directory")?;
+        }
+
+        if text {
+            let updated = if outcome.snapshot_updated {
+                format!(" {}", style("(snapshot updated)").dim())
+            } else {
+                String::new()
+            };
+            if outcome.failures.is_empty() {
+                // A matrix can be hundreds of combinations; only list the failures
+                if !matrix {
+                    println!("  {}  {}{}", style("ok").green(), test.name, updated);
+                }
+            } else {
+                println!("  {} {}{}", style("FAIL").red().bold(), test.name, updated);
+                for failure in &outcome.failures {
+                    println!("      {}", failure.message);
+                    for line in failure.detail.lines() {
+                        println!("        {}", style_detail_line(line));
+                    }
+                }
+            }
+        }
+        results.push(CaseResult {
+            name: test.name.clone(),
+            passed: outcome.failures.is_empty(),
+            snapshot_updated: outcome.snapshot_updated,
+            failures: outcome.failures,
+        });
+    }
+    let failed = results.iter().filter(|r| !r.passed).count();
+    let passed = results.len() - failed;
+    if text {
+        println!("\n{} passed, {} failed", passed, failed);
+    } else {
+        print_json(&TestReport {
+            template: &name,
+            passed,
+            failed,
+            tests: &results,
+        })?;
+    }
+
+    if failed > 0 {
+        let unit = if matrix { "combination(s)" } else { "test(s)" };
+        anyhow::bail!("{} of {} {} failed", failed, results.len(), unit);
+    }
+    Ok(())
+}
+
+/// One case per combination of the template's bool and choice variables (or a
+/// sample of `max` of them), each with the `[matrix]` answers and checks
+fn matrix_cases(
+    config: &TemplateConfig,
+    name: &str,
+    max: usize,
+    seed: Option<u64>,
+    text: bool,
+) -> Result<Vec<TemplateTest>> {
+    anyhow::ensure!(max > 0, "--max must be at least 1");
+    let axes = matrix::axes(config);
+    if axes.is_empty() {
+        anyhow::bail!(
+            "Template '{}' has no bool or choice variables to combine",
+            name
+        );
+    }
+    let total = matrix::total(&axes);
+    let seed = match seed {
+        Some(seed) => seed,
+        None => {
+            let mut bytes = [0u8; 8];
+            OsRandom.fill(&mut bytes)?;
+            u64::from_le_bytes(bytes)
+        }
+    };
+    let indices = matrix::select(total, max, seed);
+
+    if text {
+        if indices.len() as u128 == total {
+            println!(
+                "Rendering all {} combination(s) for {}\n",
+                total,
+                style(name).cyan().bold()
+            );
+        } else {
+            println!(
+                "Rendering {} of {} combination(s) for {} (--seed {} repeats this sample)\n",
+                indices.len(),
+                total,
+                style(name).cyan().bold(),
+                seed
+            );
+        }
+    }
+
+    Ok(indices
+        .into_iter()
+        .map(|index| {
+            let combination = matrix::combination(&axes, index);
+            let name = matrix::label(&combination, &axes);
+            let mut answers = config.matrix.answers.clone();
+            answers.extend(combination);
+            TemplateTest {
+                name,
+                answers,
+                // Same directory name everywhere, so only the combination differs
+                output: Some("matrix".to_string()),
+                hooks: config.matrix.hooks,
+                commands: config.matrix.commands.clone(),
+                ..TemplateTest::default()
+            }
+        })
+        .collect())
+}
+
+/// Color added and removed lines of a diff; dim everything else
+fn style_detail_line(line: &str) -> console::StyledObject<&str> {
+    if line.starts_with('+') && !line.starts_with("+++") {
+        style(line).green()
+    } else if line.starts_with('-') && !line.starts_with("---") {
+        style(line).red()
+    } else {
+        style(line).dim()
+    }
+}
+
+/// A one-off case from an answers file of `name = value` lines, named after the file
+fn read_answers(path: &Path) -> Result<TemplateTest> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read answers file '{}'", path.display()))?;
+    let values: BTreeMap<String, toml::Value> = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse answers file '{}'", path.display()))?;
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "answers".to_string());
+
+    Ok(TemplateTest {
+        name,
+        answers: values
+            .into_iter()
+            .map(|(name, value)| (name, answer_value(value)))
+            .collect(),
+        ..TemplateTest::default()
+    })
+}
+
+/// Whether and how to check a case against its snapshot
+#[derive(Clone, Copy)]
+struct SnapshotCheck<'a> {
+    snapshot: Option<&'a Snapshot>,
+    /// Write the snapshot instead of comparing
+    update: bool,
+}
+
+struct CaseOutcome {
+    failures: Vec<Failure>,
+    snapshot_updated: bool,
+}
+
+/// Render one case into `output` and collect every failed assertion
+fn run_case(
+    template_path: &Path,
+    config: &TemplateConfig,
+    test: &TemplateTest,
+    output: &Path,
+    check: SnapshotCheck,
+) -> CaseOutcome {
+    let mut outcome = CaseOutcome {
+        failures: Vec::new(),
+        snapshot_updated: false,
+    };
+
+    // Answers only come from the test and built-ins are fixed, so results are the
+    // same on every machine and every run
+    let answers = test.answers.clone().into_iter().collect();
+    let generator = Generator::new(template_path, config, output, answers, Builtins::fixed());
+    let rendered = generator.and_then(|mut g| {
+        g.answer_defaults()?;
+        let generation = g.finish()?;
+        generation.check_unresolved(false)?;
+        generation.write(None)?;
+        Ok(generation)
+    });
+    let mut generation = match rendered {
+        Ok(generation) => generation,
+        Err(err) => {
+            let error = format!("{:#}", err);
+            let (first, rest) = error.split_once('\n').unwrap_or((&error, ""));
+            outcome
+                .failures
+                .push(Failure::new(format!("Rendering failed: {}", first)).with_detail(rest));
+            return outcome;
+        }
+    };
+    let failures = &mut outcome.failures;
+
+    // Snapshots hold the rendered files, before hooks or commands add build output
+    if let Some(snapshot) = check.snapshot {
+        let result =
+            if check.update {
+                snapshot
+                    .update(output)
+                    .map(|()| outcome.snapshot_updated = true)
+            } else if !snapshot.exists() {
+                failures.push(Failure::new(format!(
+                    "no snapshot at {} (run with --update to create it)",
+                    snapshot.dir().display()
+                )));
+                Ok(())
+            } else {
+                snapshot.compare(output).map(|mismatches| {
+                    failures.extend(mismatches.into_iter().map(|m| {
+                        Failure::new(format!("snapshot: {}", m.message)).with_detail(m.diff)
+                    }))
+                })
+            };
+        if let Err(err) = result {
+            failures.push(Failure::new(format!("{:#}", err)));
+        }
+    }
+
+    if test.hooks {
+        match generation.run_post_create(&mut |_| {}) {
+            Ok(hooks) => {
+                for hook in hooks.into_iter().filter(|h| !h.success) {
+                    failures.push(
+                        Failure::new(format!("post_create hook failed: {}", hook.command))
+                            .with_detail(hook.error),
+                    );
+                }
+            }
+            Err(err) => failures.push(Failure::new(format!("{:#}", err))),
+        }
+    }
+
+    for path in &test.exists {
+        if fs::symlink_metadata(output.join(path)).is_err() {
+            failures.push(Failure::new(format!("expected {} to exist", path)));
+        }
+    }
+    for path in &test.absent {
+        if fs::symlink_metadata(output.join(path)).is_ok() {
+            failures.push(Failure::new(format!("expected {} to be absent", path)));
+        }
+    }
+    for (path, patterns) in &test.matches {
+        failures.extend(check_matches(output, path, patterns));
+    }
+
+    if !test.commands.is_empty() {
+        match execute_hooks(&test.commands, output, &mut |_| {}) {
+            Ok(results) => {
+                for result in results.into_iter().filter(|r| !r.success) {
+                    failures.push(
+                        Failure::new(format!("command failed: {}", result.command))
+                            .with_detail(result.error),
+                    );
+                }
+            }
+            Err(err) => failures.push(Failure::new(format!("{:#}", err))),
+        }
+    }
+
+    outcome
+}
+
+/// Check a generated file against content regexes, showing the file when one doesn't match
+fn check_matches(output: &Path, path: &str, patterns: &[String]) -> Vec<Failure> {
+    let content = match fs::read_to_string(output.join(path)) {
+        Ok(content) => content,
+        Err(err) => return vec![Failure::new(format!("cannot read {}: {}", path, err))],
+    };
+
+    patterns
+        .iter()
+        .filter_map(|pattern| {
+            let matched = Regex::new(&format!("(?m){}", pattern)).map(|re| re.is_match(&content));
+            match matched {
+                Ok(true) => None,
+                Ok(false) => Some(
+                    Failure::new(format!("{} does not match /{}/", path, pattern))
+                        .with_detail(excerpt(&content)),
+                ),
+                Err(err) => Some(Failure::new(format!(
+                    "invalid pattern /{}/ for {}: {}",
+                    pattern, path, err
+                ))),
+            }
+        })
+        .collect()
+}
+
+/// The start of a file with line numbers, as shown under a failed content assertion
+fn excerpt(content: &str) -> String {
+    let total = content.lines().count();
+    let mut lines: Vec<String> = content
+        .lines()
+        .take(EXCERPT_LINES)
+        .enumerate()
+        .map(|(i, line)| format!("{:>3} | {}", i + 1, line))
+        .collect();
+    if total > EXCERPT_LINES {
+        lines.push(format!("... ({} more lines)", total - EXCERPT_LINES));
+    }
+    if lines.is_empty() {
+        lines.push("(empty file)".to_string());
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_matches() {
+        let temp = tempfile::TempDir::new().unwrap();
+        fs::write(temp.path().join("README.md"), "# demo\n\nA project\n").unwrap();
+
+        let ok = check_matches(temp.path(), "README.md", &["^# demo$".to_string()]);
+        assert!(ok.is_empty());
+
+        let failed = check_matches(
+            temp.path(),
+            "README.md",
+            &["^A project$".to_string(), "^# other".to_string()],
+        );
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].message, "README.md does not match /^# other/");
+        assert!(failed[0].detail.contains("  1 | # demo"));
+
+        let missing = check_matches(temp.path(), "missing.txt", &["x".to_string()]);
+        assert!(missing[0].message.starts_with("cannot read missing.txt"));
+    }
+}
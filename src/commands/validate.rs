@@ -1,45 +1,269 @@
 use anyhow::Result;
 use console::style;
-use regex::Regex;
-use std::collections::HashSet;
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::template::load_template_config;
+use project_scaffold::builtins::{BUILTIN_PREFIX, is_builtin};
+use project_scaffold::engine::Renderer;
+use project_scaffold::generate::split_multi;
+use project_scaffold::hooks::check_script;
+use project_scaffold::output::{OutputFormat, print_json};
+use project_scaffold::paths::{PathPatterns, PermissionRules, relative_path};
+use project_scaffold::requirements;
+use project_scaffold::template::load_template_config;
+use project_scaffold::variables::{Syntax, computed_order, condition_variable};
 
-pub fn validate_template(template_path: &str) -> Result<()> {
+/// How serious a validation finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// Kinds of findings, each with a stable code for CI scripts to match on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Code {
+    InvalidTemplate,
+    UndefinedVariable,
+    UnusedVariable,
+    MissingDescription,
+    DuplicateVariable,
+    ChoiceWithoutChoices,
+    DefaultNotInChoices,
+    InvalidBoolDefault,
+    UnknownVariableType,
+    MissingConditionalTarget,
+    InvalidCondition,
+    MissingScript,
+    ScriptSyntax,
+    EmptyHookCommand,
+    InvalidPermissions,
+    InvalidCopyPattern,
+    InvalidRenderConfig,
+    MissingRenameSource,
+    ComputedCycle,
+    ComputedShadowsVariable,
+    ReservedPrefix,
+    ImplicitProjectName,
+    TemplateSyntax,
+    InvalidRequirement,
+    InvalidTestOutput,
+}
+
+impl Code {
+    fn code(self) -> &'static str {
+        match self {
+            Code::InvalidTemplate => "S000",
+            Code::UndefinedVariable => "S001",
+            Code::UnusedVariable => "S002",
+            Code::MissingDescription => "S003",
+            Code::DuplicateVariable => "S004",
+            Code::ChoiceWithoutChoices => "S005",
+            Code::DefaultNotInChoices => "S006",
+            Code::InvalidBoolDefault => "S007",
+            Code::UnknownVariableType => "S008",
+            Code::MissingConditionalTarget => "S009",
+            Code::InvalidCondition => "S010",
+            Code::MissingScript => "S011",
+            Code::ScriptSyntax => "S012",
+            Code::EmptyHookCommand => "S013",
+            Code::InvalidPermissions => "S014",
+            Code::InvalidCopyPattern => "S015",
+            Code::InvalidRenderConfig => "S016",
+            Code::MissingRenameSource => "S017",
+            Code::ComputedCycle => "S018",
+            Code::ComputedShadowsVariable => "S019",
+            Code::ReservedPrefix => "S020",
+            Code::ImplicitProjectName => "S021",
+            Code::TemplateSyntax => "S022",
+            Code::InvalidRequirement => "S023",
+            Code::InvalidTestOutput => "S024",
+        }
+    }
+
+    fn severity(self) -> Severity {
+        match self {
+            Code::UndefinedVariable
+            | Code::UnusedVariable
+            | Code::MissingDescription
+            | Code::MissingRenameSource
+            | Code::ReservedPrefix => Severity::Warning,
+            Code::ImplicitProjectName => Severity::Info,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl Serialize for Code {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+/// A single validation finding
+#[derive(Debug, Serialize)]
+struct Diagnostic {
+    code: Code,
+    severity: Severity,
+    message: String,
+    /// Template-relative file, with `:line` when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+}
+
+/// The document printed by `scaffold validate --format json`
+#[derive(Serialize)]
+struct ValidationResult<'a> {
+    path: &'a str,
+    /// Template display name, if template.toml could be parsed
+    template: Option<&'a str>,
+    /// False if there is at least one error (or warning with --strict)
+    valid: bool,
+    errors: usize,
+    warnings: usize,
+    diagnostics: &'a [Diagnostic],
+}
+
+/// Collects findings, printing them as they come in text mode
+struct Report {
+    format: OutputFormat,
+    path: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    fn ok(&self, message: impl AsRef<str>) {
+        if self.format.is_text() {
+            println!("{} {}", style("ok").green(), message.as_ref());
+        }
+    }
+
+    fn add(&mut self, code: Code, message: impl Into<String>, location: Option<&str>) {
+        let message = message.into();
+        let severity = code.severity();
+        if self.format.is_text() {
+            let marker = match severity {
+                Severity::Error => style("x").red(),
+                Severity::Warning => style("!").yellow(),
+                Severity::Info => style("i").cyan(),
+            };
+            let tag = match location {
+                Some(location) => format!("[{} {}]", code.code(), location),
+                None => format!("[{}]", code.code()),
+            };
+            println!("{} {} {}", marker, message, style(tag).dim());
+        }
+        self.diagnostics.push(Diagnostic {
+            code,
+            severity,
+            message,
+            location: location.map(str::to_string),
+        });
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    }
+
+    /// Print the JSON document or text summary, then fail if there are errors
+    /// (or warnings, when `strict`)
+    fn finish(&self, template: Option<&str>, strict: bool) -> Result<()> {
+        let errors = self.count(Severity::Error);
+        let warnings = self.count(Severity::Warning);
+        let valid = errors == 0 && (!strict || warnings == 0);
+
+        if self.format.is_text() {
+            if template.is_some() {
+                println!("\n{} error(s), {} warning(s)", errors, warnings);
+            }
+        } else {
+            print_json(&ValidationResult {
+                path: &self.path,
+                template,
+                valid,
+                errors,
+                warnings,
+                diagnostics: &self.diagnostics,
+            })?;
+        }
+
+        if !valid {
+            let first = self
+                .diagnostics
+                .iter()
+                .find(|d| d.severity == Severity::Error || strict)
+                .map(|d| d.message.as_str())
+                .unwrap_or_default();
+            if errors > 0 {
+                anyhow::bail!(
+                    "Template validation failed with {} error(s): {}",
+                    errors,
+                    first
+                );
+            }
+            anyhow::bail!(
+                "Template validation failed with {} warning(s) (--strict): {}",
+                warnings,
+                first
+            );
+        }
+        Ok(())
+    }
+}
+
+pub fn validate_template(template_path: &str, strict: bool, format: OutputFormat) -> Result<()> {
     let path = PathBuf::from(template_path);
+    let mut report = Report {
+        format,
+        path: template_path.to_string(),
+        diagnostics: Vec::new(),
+    };
 
-    println!("Validating template at: {}\n", style(template_path).cyan());
+    if format.is_text() {
+        println!("Validating template at: {}\n", style(template_path).cyan());
+    }
 
     // Check directory exists
     if !path.exists() {
-        println!("{} Directory does not exist", style("x").red());
-        anyhow::bail!("Template directory '{}' does not exist", template_path);
+        report.add(Code::InvalidTemplate, "Directory does not exist", None);
+        return report.finish(None, strict);
     }
 
     if !path.is_dir() {
-        println!("{} Path is not a directory", style("x").red());
-        anyhow::bail!("'{}' is not a directory", template_path);
+        report.add(Code::InvalidTemplate, "Path is not a directory", None);
+        return report.finish(None, strict);
     }
 
     // Check template.toml exists
     let config_path = path.join("template.toml");
     if !config_path.exists() {
-        println!("{} template.toml not found", style("x").red());
-        anyhow::bail!("template.toml not found in '{}'", template_path);
+        report.add(Code::InvalidTemplate, "template.toml not found", None);
+        return report.finish(None, strict);
     }
-    println!("{} template.toml found", style("ok").green());
+    report.ok("template.toml found");
 
     // Load and validate config
+    let in_config = Some("template.toml");
     let config = match load_template_config(&config_path) {
         Ok(c) => {
-            println!("{} template.toml is valid TOML", style("ok").green());
+            report.ok("template.toml is valid TOML");
             c
         }
         Err(e) => {
-            println!("{} template.toml parse error: {}", style("x").red(), e);
-            anyhow::bail!("Failed to parse template.toml: {}", e);
+            report.add(
+                Code::InvalidTemplate,
+                format!("template.toml parse error: {}", e),
+                in_config,
+            );
+            return report.finish(None, strict);
         }
     };
 
@@ -47,82 +271,408 @@ pub fn validate_template(template_path: &str) -> Result<()> {
     let mut all_have_descriptions = true;
     for var in &config.variables {
         if var.description.is_empty() {
-            println!(
-                "{} Variable '{}' has no description",
-                style("!").yellow(),
-                var.name
+            report.add(
+                Code::MissingDescription,
+                format!("Variable '{}' has no description", var.name),
+                in_config,
             );
             all_have_descriptions = false;
         }
     }
     if all_have_descriptions && !config.variables.is_empty() {
-        println!("{} All variables have descriptions", style("ok").green());
+        report.ok("All variables have descriptions");
     }
 
-    // Check choice variables have choices defined
+    // Check each variable is declared once and doesn't claim the built-in prefix
+    let mut seen = HashSet::new();
     for var in &config.variables {
-        if var.var_type.as_deref() == Some("choice") && var.choices.is_empty() {
-            println!(
-                "{} Variable '{}' is type 'choice' but has no choices",
-                style("x").red(),
-                var.name
+        if !seen.insert(var.name.as_str()) {
+            report.add(
+                Code::DuplicateVariable,
+                format!("Variable '{}' is declared more than once", var.name),
+                in_config,
+            );
+        }
+        if is_builtin(&var.name) {
+            report.add(
+                Code::ReservedPrefix,
+                format!(
+                    "Variable '{}' starts with '{}', which is reserved for built-ins",
+                    var.name, BUILTIN_PREFIX
+                ),
+                in_config,
+            );
+        }
+    }
+
+    // Check variable types and that defaults fit them
+    for var in &config.variables {
+        match var.var_type.as_deref().unwrap_or("string") {
+            "string" | "secret" => {}
+            kind @ ("choice" | "multi") => {
+                if var.choices.is_empty() {
+                    report.add(
+                        Code::ChoiceWithoutChoices,
+                        format!(
+                            "Variable '{}' is type '{}' but has no choices",
+                            var.name, kind
+                        ),
+                        in_config,
+                    );
+                } else if let Some(ref default) = var.default {
+                    let values = match kind {
+                        "multi" => split_multi(default),
+                        _ => vec![default.as_str()],
+                    };
+                    if let Some(default) = values
+                        .into_iter()
+                        .find(|v| !var.choices.iter().any(|c| c == v))
+                    {
+                        report.add(
+                            Code::DefaultNotInChoices,
+                            format!(
+                                "Variable '{}' has default '{}', which is not one of its choices ({})",
+                                var.name,
+                                default,
+                                var.choices.join(", ")
+                            ),
+                            in_config,
+                        );
+                    }
+                }
+            }
+            "bool" => {
+                if let Some(ref default) = var.default {
+                    if !["true", "false", "yes", "no"].contains(&default.as_str()) {
+                        report.add(
+                            Code::InvalidBoolDefault,
+                            format!(
+                                "Variable '{}' is type 'bool' but its default '{}' is not true or false",
+                                var.name, default
+                            ),
+                            in_config,
+                        );
+                    }
+                }
+            }
+            other => report.add(
+                Code::UnknownVariableType,
+                format!(
+                    "Variable '{}' has unknown type '{}' (expected string, choice, multi, bool or secret)",
+                    var.name, other
+                ),
+                in_config,
+            ),
+        }
+    }
+
+    // Check conditionals parse and point at files in the template
+    for cond in &config.conditionals {
+        let target = match (&cond.include, &cond.exclude) {
+            (Some(include), None) => include,
+            (None, Some(exclude)) => exclude,
+            _ => {
+                report.add(
+                    Code::InvalidCondition,
+                    format!(
+                        "Conditional '{}' needs exactly one of include or exclude",
+                        cond.when
+                    ),
+                    in_config,
+                );
+                continue;
+            }
+        };
+        if condition_variable(&cond.when).is_none() {
+            report.add(
+                Code::InvalidCondition,
+                format!(
+                    "Condition '{}' for '{}' is not of the form var == value",
+                    cond.when, target
+                ),
+                in_config,
+            );
+        }
+        if !conditional_target_exists(&path, target) {
+            report.add(
+                Code::MissingConditionalTarget,
+                format!(
+                    "Conditional target '{}' does not exist in the template",
+                    target
+                ),
+                in_config,
             );
         }
     }
 
-    // Collect defined variable names
-    let defined_vars: HashSet<String> = config.variables.iter().map(|v| v.name.clone()).collect();
+    // Check hooks: scripts exist and parse, commands aren't empty
+    if let Some(ref hooks) = config.hooks {
+        for script in &hooks.scripts {
+            match fs::read_to_string(path.join(script)) {
+                Ok(source) => match check_script(&source) {
+                    Ok(()) => report.ok(format!("Script '{}' found", script)),
+                    Err(e) => report.add(
+                        Code::ScriptSyntax,
+                        format!("Script '{}' has a syntax error: {}", script, e),
+                        Some(script),
+                    ),
+                },
+                Err(_) => report.add(
+                    Code::MissingScript,
+                    format!("Script '{}' not found", script),
+                    in_config,
+                ),
+            }
+        }
+        for (index, cmd) in hooks.post_create.iter().enumerate() {
+            if cmd.trim().is_empty() {
+                report.add(
+                    Code::EmptyHookCommand,
+                    format!("post_create command {} is empty", index + 1),
+                    in_config,
+                );
+            }
+        }
+    }
 
-    // Find variables used in template files
-    let used_vars = find_variables_in_files(&path)?;
+    // Check permission overrides parse
+    if !config.permissions.is_empty() {
+        match PermissionRules::new(&config.permissions) {
+            Ok(_) => report.ok("Permission overrides are valid"),
+            Err(e) => report.add(Code::InvalidPermissions, e.to_string(), in_config),
+        }
+    }
 
-    // Check for undefined variables (used but not defined)
-    let undefined: Vec<_> = used_vars.difference(&defined_vars).collect();
-    if !undefined.is_empty() {
-        for var in &undefined {
-            println!(
-                "{} Variable '{{{{{}}}}}' used in files but not defined",
-                style("!").yellow(),
-                var
+    // Collect defined variable names (prompted and computed)
+    let mut defined_vars: HashSet<String> =
+        config.variables.iter().map(|v| v.name.clone()).collect();
+    for computed in &config.computed {
+        if !defined_vars.insert(computed.name.clone()) {
+            report.add(
+                Code::ComputedShadowsVariable,
+                format!(
+                    "Computed variable '{}' is also declared in [[variables]]",
+                    computed.name
+                ),
+                in_config,
             );
         }
     }
 
-    // Check for unused variables (defined but not used)
-    let unused: Vec<_> = defined_vars.difference(&used_vars).collect();
-    if !unused.is_empty() {
-        for var in &unused {
-            println!(
-                "{} Variable '{}' defined but never used",
-                style("!").yellow(),
-                var
+    // Check copy-only patterns parse
+    let copy_without_render = match PathPatterns::new(&config.copy_without_render) {
+        Ok(patterns) => patterns,
+        Err(e) => {
+            report.add(
+                Code::InvalidCopyPattern,
+                format!("copy_without_render: {}", e),
+                in_config,
+            );
+            PathPatterns::default()
+        }
+    };
+
+    // Check render delimiters compile and suit the engine
+    let engine: Arc<dyn Renderer> = match config.renderer() {
+        Ok(engine) => engine,
+        Err(e) => {
+            report.add(
+                Code::InvalidRenderConfig,
+                format!("[render]: {}", e),
+                in_config,
+            );
+            Arc::new(Syntax::default())
+        }
+    };
+
+    // Check rename sources exist
+    for source in config.rename.keys() {
+        if !path.join(source).exists() {
+            report.add(
+                Code::MissingRenameSource,
+                format!("Rename source '{}' does not exist", source),
+                in_config,
+            );
+        }
+    }
+
+    // Check requirements can be compared with what the tools print
+    for (tool, requirement) in &config.requirements {
+        if let Err(e) = requirements::compile(tool, requirement) {
+            report.add(Code::InvalidRequirement, e.to_string(), in_config);
+        }
+    }
+
+    for test in &config.tests {
+        if let Err(e) = test.check_output() {
+            report.add(Code::InvalidTestOutput, e.to_string(), in_config);
+        }
+    }
+
+    // Check computed variables can be evaluated in some order
+    if !config.computed.is_empty() {
+        match computed_order(&config.computed, engine.as_ref()) {
+            Ok(_) => report.ok("Computed variables have no cycles"),
+            Err(e) => report.add(Code::ComputedCycle, e.to_string(), in_config),
+        }
+    }
+
+    // Find variables used in template files and in template.toml (rename targets,
+    // computed values, conditions, defaults, hook commands and git fields),
+    // keeping the first place each one is used
+    let scan = find_variables_in_files(&path, &copy_without_render, engine.as_ref())?;
+    let mut used_vars = scan.variables;
+    for (location, error) in scan.syntax_errors {
+        report.add(
+            Code::TemplateSyntax,
+            format!("Invalid {} template: {}", config.engine.name(), error),
+            Some(&location),
+        );
+    }
+    let git_fields = config.git.iter().flat_map(|g| {
+        [
+            Some(&g.commit_message),
+            g.author_name.as_ref(),
+            g.author_email.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+    });
+    let config_values = config
+        .rename
+        .values()
+        .chain(config.computed.iter().map(|c| &c.value))
+        .chain(config.variables.iter().filter_map(|v| v.default.as_ref()))
+        .chain(config.hooks.iter().flat_map(|h| &h.post_create))
+        .chain(git_fields);
+    let mut config_vars: Vec<String> = config_values
+        .flat_map(|value| engine.find_placeholders(value))
+        .collect();
+    config_vars.extend(
+        config
+            .conditionals
+            .iter()
+            .filter_map(|c| condition_variable(&c.when).map(str::to_string)),
+    );
+    for name in config_vars {
+        used_vars
+            .entry(name)
+            .or_insert_with(|| "template.toml".to_string());
+    }
+
+    // project_name always has a value (the output directory name), so using it
+    // without declaring it is fine
+    if let Some(location) = used_vars.get("project_name") {
+        if !defined_vars.contains("project_name") {
+            report.add(
+                Code::ImplicitProjectName,
+                "Variable 'project_name' is not declared; it defaults to the output directory name",
+                Some(location),
             );
+            defined_vars.insert("project_name".to_string());
         }
     }
 
+    // Check for undefined variables (used but not defined); built-ins are always defined
+    let undefined: Vec<_> = used_vars
+        .iter()
+        .filter(|(name, _)| !defined_vars.contains(*name) && !is_builtin(name))
+        .collect();
+    for (var, location) in &undefined {
+        report.add(
+            Code::UndefinedVariable,
+            format!("Variable '{{{{{}}}}}' used in files but not defined", var),
+            Some(location),
+        );
+    }
+
+    // Check for unused variables (defined but not used)
+    let mut unused: Vec<_> = defined_vars
+        .iter()
+        .filter(|name| !used_vars.contains_key(*name))
+        .collect();
+    unused.sort();
+    for var in &unused {
+        report.add(
+            Code::UnusedVariable,
+            format!("Variable '{}' defined but never used", var),
+            in_config,
+        );
+    }
+
     if undefined.is_empty() && unused.is_empty() && !defined_vars.is_empty() {
-        println!("{} All variables are defined and used", style("ok").green());
+        report.ok("All variables are defined and used");
     }
 
-    println!("\nTemplate: {}", style(&config.name).cyan().bold());
-    println!("Description: {}", config.description);
-    println!("Variables: {}", config.variables.len());
-    println!("Conditionals: {}", config.conditionals.len());
+    if format.is_text() {
+        println!("\nTemplate: {}", style(&config.name).cyan().bold());
+        println!("Description: {}", config.description);
+        println!("Variables: {}", config.variables.len());
+        println!("Conditionals: {}", config.conditionals.len());
+    }
 
-    Ok(())
+    report.finish(Some(&config.name), strict)
 }
 
-/// Find all {{variable}} patterns in template files
-fn find_variables_in_files(dir: &Path) -> Result<HashSet<String>> {
-    let mut vars = HashSet::new();
-    let re = Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap();
+/// Whether a conditional's target (a file name or template-relative path) matches
+/// anything in the template
+fn conditional_target_exists(root: &Path, target: &str) -> bool {
+    if target.contains('/') {
+        return root.join(target).exists();
+    }
+    // Bare names match a file or directory at any depth
+    fn find(dir: &Path, name: &str) -> bool {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return false;
+        };
+        entries.flatten().any(|entry| {
+            entry.file_name() == name
+                || (entry.file_type().is_ok_and(|t| t.is_dir()) && find(&entry.path(), name))
+        })
+    }
+    find(root, target)
+}
+
+/// What a scan of the template files found
+#[derive(Default)]
+struct FileScan {
+    /// Each {{variable}}, mapped to where it's first used (`path:line` for
+    /// contents, `path` for names)
+    variables: BTreeMap<String, String>,
+    /// Files the engine can't parse, as location and error
+    syntax_errors: Vec<(String, String)>,
+}
 
-    find_variables_recursive(dir, &re, &mut vars)?;
+/// Find all {{variable}} patterns and syntax errors in template files
+fn find_variables_in_files(
+    dir: &Path,
+    copy_without_render: &PathPatterns,
+    engine: &dyn Renderer,
+) -> Result<FileScan> {
+    let mut scan = FileScan::default();
 
-    Ok(vars)
+    find_variables_recursive(dir, dir, copy_without_render, engine, &mut scan)?;
+
+    Ok(scan)
 }
 
-fn find_variables_recursive(dir: &Path, re: &Regex, vars: &mut HashSet<String>) -> Result<()> {
+fn find_variables_recursive(
+    root: &Path,
+    dir: &Path,
+    copy_without_render: &PathPatterns,
+    engine: &dyn Renderer,
+    scan: &mut FileScan,
+) -> Result<()> {
+    // Symlinks are copied as links, so their targets are scanned on their own
+    if dir.is_symlink() {
+        return Ok(());
+    }
+
+    let relative = relative_path(dir, root).unwrap_or_default();
+    let mut record = |name: String, location: String| {
+        scan.variables.entry(name).or_insert(location);
+    };
+
     if dir.is_file() {
         // Skip template.toml itself
         if dir.file_name() == Some(std::ffi::OsStr::new("template.toml")) {
@@ -131,30 +681,37 @@ fn find_variables_recursive(dir: &Path, re: &Regex, vars: &mut HashSet<String>)
 
         // Check filename for variables
         if let Some(name) = dir.file_name() {
-            let name_str = name.to_string_lossy();
-            for cap in re.captures_iter(&name_str) {
-                vars.insert(cap[1].to_string());
+            for var in engine.find_placeholders(&name.to_string_lossy()) {
+                record(var, relative.clone());
             }
         }
 
-        // Check file contents (only text files)
-        if let Ok(content) = fs::read_to_string(dir) {
-            for cap in re.captures_iter(&content) {
-                vars.insert(cap[1].to_string());
+        // Check file contents (only text files that get rendered)
+        if !copy_without_render.is_match(&relative) {
+            if let Ok(content) = fs::read_to_string(dir) {
+                if let Err(e) = engine.check(&content) {
+                    scan.syntax_errors
+                        .push((relative.clone(), format!("{:#}", e)));
+                }
+                for (line, var) in engine.find_placeholder_lines(&content) {
+                    record(var, format!("{}:{}", relative, line));
+                }
             }
         }
     } else if dir.is_dir() {
         // Check directory name for variables
-        if let Some(name) = dir.file_name() {
-            let name_str = name.to_string_lossy();
-            for cap in re.captures_iter(&name_str) {
-                vars.insert(cap[1].to_string());
+        if dir != root {
+            if let Some(name) = dir.file_name() {
+                for var in engine.find_placeholders(&name.to_string_lossy()) {
+                    record(var, relative.clone());
+                }
             }
         }
 
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
-            find_variables_recursive(&entry.path(), re, vars)?;
+        let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<std::io::Result<_>>()?;
+        entries.sort_by_key(|e| e.path());
+        for entry in entries {
+            find_variables_recursive(root, &entry.path(), copy_without_render, engine, scan)?;
         }
     }
 
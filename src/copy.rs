@@ -1,184 +1,651 @@
 use anyhow::{Context, Result};
 use console::style;
 use indicatif::ProgressBar;
-use std::collections::{HashMap, HashSet};
-use std::fs;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use crate::variables::substitute_variables;
+use crate::engine::Renderer;
+use crate::paths::{
+    PathPatterns, PermissionRules, ensure_within, invalid_name_reason, normalize_path,
+    relative_path,
+};
+use crate::variables::Syntax;
+use crate::vfs::{EntryKind, Vfs};
 
-/// Count total files in template (excluding template.toml and conditionally excluded files)
-pub fn count_files(path: &Path, excluded: &HashSet<String>) -> Result<usize> {
-    let mut count = 0;
+/// Dotfiles that can be shipped with a leading underscore instead (`_gitignore` -> `.gitignore`),
+/// since packaging tools often drop or mangle the real names
+const DOTFILE_NAMES: &[&str] = &[
+    "gitignore",
+    "gitattributes",
+    "gitmodules",
+    "gitkeep",
+    "npmrc",
+    "npmignore",
+    "yarnrc",
+    "nvmrc",
+    "env",
+    "env.example",
+    "dockerignore",
+    "editorconfig",
+    "prettierrc",
+    "prettierignore",
+    "eslintrc",
+    "eslintignore",
+    "python-version",
+    "pre-commit-config.yaml",
+];
 
-    if path.is_file() {
-        if path.file_name() != Some(std::ffi::OsStr::new("template.toml")) {
-            count += 1;
+/// Suffixes marking a file as a template, stripped from the output name (`main.py.jinja` -> `main.py`)
+const TEMPLATE_SUFFIXES: &[&str] = &[".tmpl", ".jinja"];
+
+/// Apply the `_dotfile` and template suffix naming conventions to a rendered name
+fn apply_name_conventions(name: String) -> String {
+    let name = match name.strip_prefix('_') {
+        Some(dotfile) if DOTFILE_NAMES.contains(&dotfile) => format!(".{}", dotfile),
+        _ => name,
+    };
+    match TEMPLATE_SUFFIXES
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+    {
+        Some(stem) if !stem.is_empty() => stem.to_string(),
+        _ => name,
+    }
+}
+
+/// Options controlling which template entries are copied and how
+pub struct RenderOptions {
+    /// Files or directories to skip (file names or template-relative paths)
+    pub excluded: HashSet<String>,
+    /// Skip the template's own template.toml
+    pub skip_template_toml: bool,
+    /// File mode overrides from the template's `[permissions]` table
+    pub permissions: PermissionRules,
+    /// Files copied verbatim, without variable substitution in their contents
+    pub copy_without_render: PathPatterns,
+    /// Substitute variables at all (false copies the template verbatim, as for `add`)
+    pub render: bool,
+    /// Template language of contents and names
+    pub engine: Arc<dyn Renderer>,
+    /// Explicit output names by template-relative path, from the `[rename]` table
+    pub renames: BTreeMap<String, String>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            excluded: HashSet::new(),
+            skip_template_toml: true,
+            permissions: PermissionRules::default(),
+            copy_without_render: PathPatterns::default(),
+            render: true,
+            engine: Arc::new(Syntax::default()),
+            renames: BTreeMap::new(),
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Substitute variables in a file or symlink name, unless rendering is disabled
+    fn render_name(&self, name: &str, variables: &HashMap<String, String>) -> Result<String> {
+        if self.render {
+            self.engine
+                .render(name, variables)
+                .with_context(|| format!("Failed to render name '{}'", name))
+        } else {
+            Ok(name.to_string())
+        }
+    }
+
+    /// Output name for a template entry: an explicit [rename] target if there is one,
+    /// otherwise the rendered name with dotfile and suffix conventions applied.
+    /// Names produced from variables are checked so answers can't escape the output
+    /// directory or produce names some OS can't store.
+    fn output_name(
+        &self,
+        src: &Path,
+        root: &Path,
+        variables: &HashMap<String, String>,
+    ) -> Result<String> {
+        let name = src
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !self.render {
+            return Ok(name);
         }
-    } else if path.is_dir() {
-        for entry in fs::read_dir(path)? {
-            let entry = entry?;
-            let entry_path = entry.path();
-            let file_name = entry.file_name().to_string_lossy().to_string();
 
-            if excluded.contains(&file_name) {
-                continue;
+        let relative = relative_path(src, root).unwrap_or_else(|| name.clone());
+        let (raw, output) = match self.renames.get(&relative) {
+            Some(target) => (target.as_str(), self.render_name(target, variables)?),
+            None => {
+                let rendered = self.render_name(&name, variables)?;
+                if rendered == name {
+                    // Static names come straight from the template's own files
+                    return Ok(apply_name_conventions(name));
+                }
+                (name.as_str(), apply_name_conventions(rendered))
             }
+        };
 
-            count += count_files(&entry_path, excluded)?;
+        if let Some(reason) = invalid_name_reason(&output) {
+            let sources: Vec<String> = self
+                .engine
+                .find_placeholders(raw)
+                .into_iter()
+                .map(|var| match variables.get(&var) {
+                    Some(value) => format!("'{}' = '{}'", var, value),
+                    None => format!("'{}' (not set)", var),
+                })
+                .collect();
+            let hint = if sources.is_empty() {
+                String::new()
+            } else {
+                format!(". Check the value of variable {}", sources.join(", "))
+            };
+            anyhow::bail!(
+                "Invalid output name '{}' for template path '{}': {}{}",
+                output,
+                relative,
+                reason,
+                hint
+            );
         }
+
+        Ok(output)
     }
 
-    Ok(count)
+    /// Whether a file's contents should be rendered
+    fn renders_contents(&self, src: &Path, root: &Path) -> bool {
+        self.render
+            && !relative_path(src, root).is_some_and(|r| self.copy_without_render.is_match(&r))
+    }
 }
 
-/// Preview what files would be created (for --dry-run)
-pub fn preview_template(
-    src: &Path,
-    dst: &Path,
-    variables: &HashMap<String, String>,
-    excluded: &HashSet<String>,
-    depth: usize,
-) -> Result<()> {
-    let file_name = src.file_name().map(|n| n.to_string_lossy().to_string());
-
-    // Skip template.toml and excluded files
-    if file_name.as_deref() == Some("template.toml") {
-        return Ok(());
+/// Check whether a template entry is excluded, either by its file name or by its
+/// path relative to the template root (e.g. "Dockerfile" or "src/cli.rs")
+fn is_excluded(path: &Path, root: &Path, excluded: &HashSet<String>) -> bool {
+    if excluded.is_empty() {
+        return false;
     }
-    if let Some(ref name) = file_name {
-        if excluded.contains(name) {
-            return Ok(());
+    if let Some(name) = path.file_name() {
+        if excluded.contains(name.to_string_lossy().as_ref()) {
+            return true;
         }
     }
+    relative_path(path, root).is_some_and(|relative| excluded.contains(&relative))
+}
 
-    let indent = "  ".repeat(depth);
-    let dst_name = dst
-        .file_name()
-        .map(|n| substitute_variables(&n.to_string_lossy(), variables))
-        .unwrap_or_else(|| dst.to_string_lossy().to_string());
+/// A placeholder with no value, found while planning the output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedPlaceholder {
+    /// Template-relative path of the file or directory containing it
+    pub path: String,
+    /// Line in the file's contents, or None when the placeholder is in its name
+    pub line: Option<usize>,
+    /// Variable name
+    pub name: String,
+}
 
-    if src.is_dir() {
-        if depth == 0 {
-            println!("  {}/", style(&dst_name).cyan().bold());
-        } else {
-            println!("  {}{}/", indent, style(&dst_name).cyan());
+impl fmt::Display for UnresolvedPlaceholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path, line, self.name),
+            None => write!(f, "{} (file name): {}", self.path, self.name),
         }
+    }
+}
 
-        let mut entries: Vec<_> = fs::read_dir(src)?.filter_map(|e| e.ok()).collect();
-        entries.sort_by_key(|e| e.path());
-
-        for entry in entries {
-            let src_path = entry.path();
-            let entry_name = src_path.file_name().unwrap().to_string_lossy();
-            let dst_file_name = substitute_variables(&entry_name, variables);
-            let dst_path = dst.with_file_name(&dst_name).join(&dst_file_name);
+/// Everything a template would create, worked out without writing anything
+pub struct OutputPlan {
+    /// Destination of every file and symlink
+    pub files: Vec<PathBuf>,
+    /// Placeholders in names or contents that have no value
+    pub unresolved: Vec<UnresolvedPlaceholder>,
+    /// Template-relative paths left out by conditionals or scripts
+    pub skipped: Vec<String>,
+}
 
-            preview_template(&src_path, &dst_path, variables, excluded, depth + 1)?;
-        }
-    } else {
-        println!("  {}{}", indent, dst_name);
-    }
+/// Work out every file and symlink the template would create, without writing
+/// anything. Fails if a rendered name is invalid or a destination (or rendered
+/// symlink target) would land outside `dst`.
+pub fn plan_output(
+    fs: &dyn Vfs,
+    src: &Path,
+    dst: &Path,
+    variables: &HashMap<String, String>,
+    options: &RenderOptions,
+) -> Result<OutputPlan> {
+    let mut plan = OutputPlan {
+        files: Vec::new(),
+        unresolved: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let walk = Walk {
+        fs,
+        root: src,
+        variables,
+        options,
+    };
+    walk.plan_entry(src, dst, dst, &mut plan)?;
+    Ok(plan)
+}
 
-    Ok(())
+/// Write the tree of files that would be created to `out` (for --dry-run)
+pub fn preview_template(
+    fs: &dyn Vfs,
+    src: &Path,
+    dst: &Path,
+    variables: &HashMap<String, String>,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let walk = Walk {
+        fs,
+        root: src,
+        variables,
+        options,
+    };
+    walk.preview_entry(src, dst, 0, out)
 }
 
+/// Render the template at `src` in `fs` into `dst` in `out`
 pub fn copy_template_recursive(
+    fs: &dyn Vfs,
     src: &Path,
+    out: &mut dyn Vfs,
     dst: &Path,
     variables: &HashMap<String, String>,
-    excluded: &HashSet<String>,
-    skip_template_toml: bool,
+    options: &RenderOptions,
     progress: Option<&ProgressBar>,
 ) -> Result<()> {
-    let file_name = src.file_name().map(|n| n.to_string_lossy().to_string());
+    let walk = Walk {
+        fs,
+        root: src,
+        variables,
+        options,
+    };
+    walk.copy_entry(src, out, dst, progress)
+}
+
+/// One pass over a template: where it's read from and how it's rendered
+struct Walk<'a> {
+    fs: &'a dyn Vfs,
+    root: &'a Path,
+    variables: &'a HashMap<String, String>,
+    options: &'a RenderOptions,
+}
 
-    // Skip template.toml and excluded files
-    if skip_template_toml && file_name.as_deref() == Some("template.toml") {
-        return Ok(());
+impl Walk<'_> {
+    /// Whether to leave an entry out: the template's own template.toml (unless
+    /// copied verbatim) or an excluded path
+    fn skips(&self, src: &Path, skip_template_toml: bool) -> bool {
+        let is_config = src.file_name().is_some_and(|n| n == "template.toml");
+        (skip_template_toml && is_config) || is_excluded(src, self.root, &self.options.excluded)
     }
-    if let Some(ref name) = file_name {
-        if excluded.contains(name) {
+
+    fn kind(&self, src: &Path) -> Result<EntryKind> {
+        self.fs.kind(src).with_context(|| {
+            format!(
+                "Failed to read template entry '{}'. Check that it exists and is readable.",
+                src.display()
+            )
+        })
+    }
+
+    fn read_dir(&self, src: &Path) -> Result<Vec<PathBuf>> {
+        self.fs.read_dir(src).with_context(|| {
+            format!(
+                "Failed to read template directory '{}'. Check that it exists and is readable.",
+                src.display()
+            )
+        })
+    }
+
+    fn plan_entry(
+        &self,
+        src: &Path,
+        dst_root: &Path,
+        dst: &Path,
+        plan: &mut OutputPlan,
+    ) -> Result<()> {
+        let (root, variables, options) = (self.root, self.variables, self.options);
+        if self.skips(src, options.skip_template_toml) {
+            if is_excluded(src, root, &options.excluded) {
+                plan.skipped.extend(relative_path(src, root));
+            }
             return Ok(());
         }
+
+        ensure_within(dst, dst_root)?;
+        let relative = relative_path(src, root);
+        let kind = self.kind(src)?;
+
+        if let Some(ref relative) = relative {
+            if options.render {
+                let raw_name = options
+                    .renames
+                    .get(relative)
+                    .cloned()
+                    .or_else(|| src.file_name().map(|n| n.to_string_lossy().to_string()))
+                    .unwrap_or_default();
+                for name in options.engine.find_placeholders(&raw_name) {
+                    if !variables.contains_key(&name) {
+                        plan.unresolved.push(UnresolvedPlaceholder {
+                            path: relative.clone(),
+                            line: None,
+                            name,
+                        });
+                    }
+                }
+            }
+        }
+
+        match kind {
+            EntryKind::Dir => {
+                for src_path in self.read_dir(src)? {
+                    let dst_path = dst.join(options.output_name(&src_path, root, variables)?);
+                    self.plan_entry(&src_path, dst_root, &dst_path, plan)?;
+                }
+            }
+            EntryKind::Symlink => {
+                let (target, _) = self.read_link(src)?;
+                let target = options.render_name(&target.to_string_lossy(), variables)?;
+                ensure_within(&dst.parent().unwrap_or(dst_root).join(target), dst_root)?;
+                plan.files.push(dst.to_path_buf());
+            }
+            EntryKind::File => {
+                if options.renders_contents(src, root) {
+                    // Binary files aren't rendered, so only text files can hold placeholders
+                    if let Ok(content) = self.fs.read_to_string(src) {
+                        let relative = relative.unwrap_or_default();
+                        // Syntax errors fail here, before anything is written
+                        options.engine.check(&content).with_context(|| {
+                            format!("Template file '{}' has a syntax error", relative)
+                        })?;
+                        for (line, name) in options.engine.find_placeholder_lines(&content) {
+                            if !variables.contains_key(&name) {
+                                plan.unresolved.push(UnresolvedPlaceholder {
+                                    path: relative.clone(),
+                                    line: Some(line),
+                                    name,
+                                });
+                            }
+                        }
+                    }
+                }
+                plan.files.push(dst.to_path_buf());
+            }
+        }
+
+        Ok(())
     }
 
-    if src.is_dir() {
+    fn preview_entry(
+        &self,
+        src: &Path,
+        dst: &Path,
+        depth: usize,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        let (root, variables, options) = (self.root, self.variables, self.options);
+        if self.skips(src, true) {
+            return Ok(());
+        }
+
+        let indent = "  ".repeat(depth);
         let dst_name = dst
             .file_name()
-            .map(|n| substitute_variables(&n.to_string_lossy(), variables))
+            .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| dst.to_string_lossy().to_string());
-        let dst_with_substitution = dst
-            .parent()
-            .map_or_else(|| PathBuf::from(&dst_name), |parent| parent.join(&dst_name));
 
-        fs::create_dir_all(&dst_with_substitution).with_context(|| {
-            format!(
-                "Failed to create directory '{}'. Check that you have write permissions.",
-                dst_with_substitution.display()
-            )
-        })?;
+        match self.kind(src)? {
+            EntryKind::Symlink => {
+                let target = self.fs.read_link(src)?;
+                let target = options.render_name(&target.to_string_lossy(), variables)?;
+                writeln!(out, "  {}{} -> {}", indent, dst_name, style(target).dim())?;
+            }
+            EntryKind::Dir => {
+                if depth == 0 {
+                    writeln!(out, "  {}/", style(&dst_name).cyan().bold())?;
+                } else {
+                    writeln!(out, "  {}{}/", indent, style(&dst_name).cyan())?;
+                }
 
-        for entry in fs::read_dir(src).with_context(|| {
-            format!(
-                "Failed to read template directory '{}'. Check that it exists and is readable.",
-                src.display()
-            )
-        })? {
-            let entry = entry?;
-            let src_path = entry.path();
-            let entry_file_name = src_path
-                .file_name()
-                .ok_or_else(|| anyhow::anyhow!("Invalid file path: {}", src_path.display()))?
-                .to_string_lossy();
-            let dst_file_name = substitute_variables(&entry_file_name, variables);
-            let dst_path = dst_with_substitution.join(&dst_file_name);
-
-            copy_template_recursive(
-                &src_path,
-                &dst_path,
-                variables,
-                excluded,
-                skip_template_toml,
-                progress,
-            )?;
-        }
-    } else {
-        // Check if it's a binary file by trying to read as text
-        let content = fs::read(src).with_context(|| {
-            format!(
-                "Failed to read template file '{}'. Check that it exists and is readable.",
-                src.display()
-            )
-        })?;
+                for src_path in self.read_dir(src)? {
+                    let dst_file_name = options.output_name(&src_path, root, variables)?;
+                    let dst_path = dst.join(&dst_file_name);
+                    self.preview_entry(&src_path, &dst_path, depth + 1, out)?;
+                }
+            }
+            EntryKind::File if options.renders_contents(src, root) => {
+                writeln!(out, "  {}{}", indent, dst_name)?;
+            }
+            EntryKind::File => {
+                writeln!(
+                    out,
+                    "  {}{} {}",
+                    indent,
+                    dst_name,
+                    style("(copied as-is)").dim()
+                )?;
+            }
+        }
 
-        // Try to interpret as UTF-8, if it fails, copy as binary
-        match String::from_utf8(content) {
-            Ok(text_content) => {
-                let rendered_content = substitute_variables(&text_content, variables);
-                fs::write(dst, &rendered_content).with_context(|| {
+        Ok(())
+    }
+
+    fn copy_entry(
+        &self,
+        src: &Path,
+        out: &mut dyn Vfs,
+        dst: &Path,
+        progress: Option<&ProgressBar>,
+    ) -> Result<()> {
+        let (root, variables, options) = (self.root, self.variables, self.options);
+        if self.skips(src, options.skip_template_toml) {
+            return Ok(());
+        }
+
+        match self.kind(src)? {
+            EntryKind::Symlink => {
+                self.copy_symlink(src, out, dst)?;
+
+                if let Some(pb) = progress {
+                    pb.inc(1);
+                }
+            }
+            EntryKind::Dir => {
+                out.create_dir_all(dst).with_context(|| {
                     format!(
-                        "Failed to write file '{}'. Check that you have write permissions.",
+                        "Failed to create directory '{}'. Check that you have write permissions.",
                         dst.display()
                     )
                 })?;
+
+                for src_path in self.read_dir(src)? {
+                    let dst_file_name = options.output_name(&src_path, root, variables)?;
+                    let dst_path = dst.join(&dst_file_name);
+                    self.copy_entry(&src_path, out, &dst_path, progress)?;
+                }
             }
-            Err(e) => {
-                // Binary file, copy as-is (get bytes back from error)
-                fs::write(dst, e.into_bytes()).with_context(|| {
+            EntryKind::File => {
+                let content = self.fs.read(src).with_context(|| {
+                    format!(
+                        "Failed to read template file '{}'. Check that it exists and is readable.",
+                        src.display()
+                    )
+                })?;
+
+                // Render text files; binary files and copy-only paths are written as-is
+                let content = if options.renders_contents(src, root) {
+                    match String::from_utf8(content) {
+                        Ok(text_content) => options
+                            .engine
+                            .render(&text_content, variables)
+                            .with_context(|| format!("Failed to render '{}'", src.display()))?
+                            .into_bytes(),
+                        // Binary file, copy as-is (get bytes back from error)
+                        Err(e) => e.into_bytes(),
+                    }
+                } else {
+                    content
+                };
+
+                // Keep the source file's permissions (e.g. executable bits) unless overridden
+                let mode =
+                    match relative_path(src, root).and_then(|r| options.permissions.mode_for(&r)) {
+                        Some(mode) => Some(mode),
+                        None => self.fs.mode(src)?,
+                    };
+                out.write(dst, &content, mode).with_context(|| {
                     format!(
                         "Failed to write file '{}'. Check that you have write permissions.",
                         dst.display()
                     )
                 })?;
+
+                if let Some(pb) = progress {
+                    pb.inc(1);
+                }
             }
         }
 
-        if let Some(pb) = progress {
-            pb.inc(1);
+        Ok(())
+    }
+
+    /// Read a symlink's target and its resolved path, refusing links that resolve
+    /// outside the template root
+    fn read_link(&self, src: &Path) -> Result<(PathBuf, PathBuf)> {
+        let root = self.root;
+        let target = self
+            .fs
+            .read_link(src)
+            .with_context(|| format!("Failed to read symlink '{}'", src.display()))?;
+        let resolved = normalize_path(&src.parent().unwrap_or(root).join(&target));
+
+        if target.is_absolute() || !resolved.starts_with(normalize_path(root)) {
+            anyhow::bail!(
+                "Symlink '{}' points outside the template ('{}'). Only relative links within the template are allowed.",
+                src.display(),
+                target.display()
+            );
         }
+
+        Ok((target, resolved))
     }
 
+    /// Recreate a symlink, refusing links that resolve outside the template root
+    fn copy_symlink(&self, src: &Path, out: &mut dyn Vfs, dst: &Path) -> Result<()> {
+        let (target, resolved) = self.read_link(src)?;
+        let target = PathBuf::from(
+            self.options
+                .render_name(&target.to_string_lossy(), self.variables)?,
+        );
+        let is_dir = self.fs.kind(&resolved).is_ok_and(|k| k == EntryKind::Dir);
+        out.symlink(&target, dst, is_dir).with_context(|| {
+            format!(
+                "Failed to create symlink '{}'. Check that you have write permissions.",
+                dst.display()
+            )
+        })
+    }
+}
+
+/// Write extra files produced by template scripts into the output directory
+pub fn write_generated_files(
+    out: &mut dyn Vfs,
+    dst: &Path,
+    files: &[(String, String)],
+) -> Result<()> {
+    for (relative, contents) in files {
+        let path = dst.join(relative);
+        if let Some(parent) = path.parent() {
+            out.create_dir_all(parent).with_context(|| {
+                format!(
+                    "Failed to create directory '{}'. Check that you have write permissions.",
+                    parent.display()
+                )
+            })?;
+        }
+        out.write(&path, contents.as_bytes(), None)
+            .with_context(|| {
+                format!(
+                    "Failed to write file '{}'. Check that you have write permissions.",
+                    path.display()
+                )
+            })?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::{MemoryEntry, MemoryFs};
+
+    #[test]
+    fn test_render_in_memory() {
+        let mut template = MemoryFs::new();
+        for dir in ["tpl/src", "tpl/docker"] {
+            template.create_dir_all(Path::new(dir)).unwrap();
+        }
+        let files = [
+            ("tpl/template.toml", "name = \"t\""),
+            ("tpl/_gitignore", "target/"),
+            ("tpl/src/{{name}}.rs", "// {{name}} by {{author}}"),
+            ("tpl/docker/Dockerfile", "FROM rust"),
+        ];
+        for (path, contents) in files {
+            template
+                .write(Path::new(path), contents.as_bytes(), None)
+                .unwrap();
+        }
+        template
+            .write(Path::new("tpl/run.sh"), b"#!/bin/sh", Some(0o755))
+            .unwrap();
+        template
+            .symlink(Path::new("src/{{name}}.rs"), Path::new("tpl/main"), false)
+            .unwrap();
+
+        let variables = HashMap::from([("name".to_string(), "app".to_string())]);
+        let options = RenderOptions {
+            excluded: HashSet::from(["docker".to_string()]),
+            ..RenderOptions::default()
+        };
+        let (src, dst) = (Path::new("tpl"), Path::new("out"));
+
+        let plan = plan_output(&template, src, dst, &variables, &options).unwrap();
+        assert_eq!(plan.skipped, vec!["docker"]);
+        assert_eq!(plan.unresolved.len(), 1);
+        assert_eq!(plan.unresolved[0].to_string(), "src/{{name}}.rs:1: author");
+
+        let mut preview = Vec::new();
+        preview_template(&template, src, dst, &variables, &options, &mut preview).unwrap();
+        let preview = String::from_utf8(preview).unwrap();
+        assert!(preview.contains("    app.rs\n"), "{}", preview);
+        assert!(!preview.contains("Dockerfile"));
+
+        let mut out = MemoryFs::new();
+        copy_template_recursive(&template, src, &mut out, dst, &variables, &options, None).unwrap();
+        let written: Vec<&Path> = out.files();
+        assert_eq!(
+            written,
+            plan.files.iter().map(|f| f.as_path()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            out.read_to_string(Path::new("out/src/app.rs")).unwrap(),
+            "// app by {{author}}"
+        );
+        assert!(out.exists(Path::new("out/.gitignore")));
+        assert_eq!(out.mode(Path::new("out/run.sh")).unwrap(), Some(0o755));
+        assert_eq!(
+            out.get(Path::new("out/main")),
+            Some(&MemoryEntry::Symlink(PathBuf::from("src/app.rs")))
+        );
+    }
+}
@@ -0,0 +1,167 @@
+/// Lines of unchanged context shown around each change
+const CONTEXT: usize = 3;
+
+/// Above this many line pairs, changed regions are shown as a whole replacement
+/// instead of computing the smallest diff
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A line-based unified diff from `old` to `new`, or an empty string if they're equal
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+    if ops.iter().all(|(op, _)| *op == Op::Equal) {
+        return String::new();
+    }
+
+    // Line numbers (0-based) in old and new before each op
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for (op, _) in &ops {
+        positions.push((old_pos, new_pos));
+        match op {
+            Op::Equal => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            Op::Delete => old_pos += 1,
+            Op::Insert => new_pos += 1,
+        }
+    }
+    positions.push((old_pos, new_pos));
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&i| ops[i].0 != Op::Equal).collect();
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    let mut i = 0;
+    while i < changes.len() {
+        // Merge changes whose context would overlap into one hunk
+        let mut j = i;
+        while j + 1 < changes.len() && changes[j + 1] - changes[j] <= 2 * CONTEXT + 1 {
+            j += 1;
+        }
+        let start = changes[i].saturating_sub(CONTEXT);
+        let end = (changes[j] + CONTEXT + 1).min(ops.len());
+
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+        for (op, line) in &ops[start..end] {
+            let marker = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            out.push(marker);
+            out.push_str(line);
+            out.push('\n');
+        }
+        i = j + 1;
+    }
+    out
+}
+
+fn hunk_range(start: usize, count: usize) -> String {
+    // An empty range names the line before it, as in `diff -u`
+    let first = if count == 0 { start } else { start + 1 };
+    if count == 1 {
+        first.to_string()
+    } else {
+        format!("{},{}", first, count)
+    }
+}
+
+/// Shortest edit script between two line lists (longest common subsequence)
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<(Op, &str)> = old[..prefix].iter().map(|l| (Op::Equal, *l)).collect();
+    if old_mid.len() * new_mid.len() > MAX_DIFF_CELLS {
+        ops.extend(old_mid.iter().map(|l| (Op::Delete, *l)));
+        ops.extend(new_mid.iter().map(|l| (Op::Insert, *l)));
+    } else {
+        // lcs[i][j] is the LCS length of old_mid[i..] and new_mid[j..]
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                ops.push((Op::Equal, old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                ops.push((Op::Delete, old_mid[i]));
+                i += 1;
+            } else {
+                ops.push((Op::Insert, new_mid[j]));
+                j += 1;
+            }
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|l| (Op::Equal, *l)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), "");
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n";
+        assert_eq!(
+            unified_diff(old, new, "snapshot/a.txt", "rendered/a.txt"),
+            "--- snapshot/a.txt\n+++ rendered/a.txt\n\
+             @@ -2,9 +2,10 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n 9\n 10\n+11\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_separate_hunks() {
+        let old: Vec<String> = (1..=20).map(|n| format!("l{}", n)).collect();
+        let mut new = old.clone();
+        new[1] = "two".to_string();
+        new.remove(18);
+        let diff = unified_diff(&old.join("\n"), &new.join("\n"), "a", "b");
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n l1\n-l2\n+two\n l3\n"));
+        assert!(diff.contains("@@ -16,5 +16,4 @@\n l16\n l17\n l18\n-l19\n l20\n"));
+    }
+
+    #[test]
+    fn test_unified_diff_from_empty() {
+        assert_eq!(
+            unified_diff("", "x\n", "a", "b"),
+            "--- a\n+++ b\n@@ -0,0 +1 @@\n+x\n"
+        );
+    }
+}
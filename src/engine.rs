@@ -0,0 +1,570 @@
+use anyhow::{Context as _, Result};
+use handlebars::template::{Parameter, Template, TemplateElement};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use minijinja::value::Value;
+use minijinja::{AutoEscape, Environment, Error, ErrorKind, State};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as Json};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::LazyLock;
+
+use crate::variables::{FILTERS, Syntax, apply_filter};
+
+/// `_random_hex(N)` calls, which the jinja and handlebars engines resolve like the
+/// simple engine's `{{ _random_hex(N) }}` placeholders
+static RANDOM_HEX_CALL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"_random_hex\s*\(\s*(\d+)\s*\)").unwrap());
+
+/// Template language a template is written in, chosen with `engine = "..."` in
+/// template.toml
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// `{{ name | filter }}` placeholders with `{% raw %}` regions and `\{{` escapes;
+    /// the only engine that honours the `[render]` delimiters
+    #[default]
+    Simple,
+    /// Jinja2 via minijinja, as used by cookiecutter and copier
+    Jinja,
+    /// Handlebars, as used by many yeoman generators
+    Handlebars,
+}
+
+impl Engine {
+    /// The name used in template.toml
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Simple => "simple",
+            Engine::Jinja => "jinja",
+            Engine::Handlebars => "handlebars",
+        }
+    }
+}
+
+/// Renders file contents, file names and template.toml values, and finds the
+/// variables they use
+///
+/// Dotted variable names (`_now.year`, `_git.user_name`) are nested objects for
+/// the jinja and handlebars engines, and the simple engine's filters are available
+/// as jinja filters (`{{ name | snake_case }}`) and handlebars helpers
+/// (`{{snake_case name}}`).
+pub trait Renderer: fmt::Debug + Send + Sync {
+    /// Render `text` with the given answers
+    ///
+    /// The simple engine leaves placeholders without a value as they are; the others
+    /// render them empty. Callers check [`Renderer::find_placeholders`] first.
+    fn render(&self, text: &str, variables: &HashMap<String, String>) -> Result<String>;
+
+    /// Variables `text` uses, with the 1-based line each is first used on
+    fn find_placeholder_lines(&self, text: &str) -> Vec<(usize, String)>;
+
+    /// Fail if `text` isn't valid in this template language
+    fn check(&self, text: &str) -> Result<()>;
+
+    /// Variables `text` uses
+    fn find_placeholders(&self, text: &str) -> Vec<String> {
+        self.find_placeholder_lines(text)
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect()
+    }
+}
+
+impl Renderer for Syntax {
+    fn render(&self, text: &str, variables: &HashMap<String, String>) -> Result<String> {
+        Ok(self.substitute_variables(text, variables))
+    }
+
+    fn find_placeholder_lines(&self, text: &str) -> Vec<(usize, String)> {
+        Syntax::find_placeholder_lines(self, text)
+    }
+
+    fn check(&self, _text: &str) -> Result<()> {
+        // Anything that isn't a placeholder is literal text
+        Ok(())
+    }
+
+    fn find_placeholders(&self, text: &str) -> Vec<String> {
+        Syntax::find_placeholders(self, text)
+    }
+}
+
+/// Turn flat answers into nested objects: `_now.year` becomes `{"_now": {"year": ..}}`
+///
+/// Where a name is both a value and a prefix (`a` and `a.b`), the object wins.
+fn nested(variables: &HashMap<String, String>) -> Json {
+    let mut names: Vec<&String> = variables.keys().collect();
+    // Longest first, so objects are created before plain values could take their place
+    names.sort_by_key(|name| std::cmp::Reverse(name.matches('.').count()));
+
+    let mut root = Map::new();
+    for name in names {
+        // _random_hex(N) is looked up whole by the engines' functions
+        let parts: Vec<&str> = if name.contains('(') {
+            vec![name.as_str()]
+        } else {
+            name.split('.').collect()
+        };
+        insert(&mut root, &parts, answer_value(&variables[name]));
+    }
+    Json::Object(root)
+}
+
+/// Put `value` at `path` unless something is already there or a parent is a value
+fn insert(map: &mut Map<String, Json>, path: &[&str], value: Json) {
+    match path {
+        [] => {}
+        [last] => {
+            map.entry(last.to_string()).or_insert(value);
+        }
+        [first, rest @ ..] => {
+            let entry = map
+                .entry(first.to_string())
+                .or_insert_with(|| Json::Object(Map::new()));
+            if let Json::Object(inner) = entry {
+                insert(inner, rest, value);
+            }
+        }
+    }
+}
+
+/// Bool answers become real booleans, so `{% if docker %}` and `{{#if docker}}`
+/// are false for "false"
+fn answer_value(value: &str) -> Json {
+    match value {
+        "true" => Json::Bool(true),
+        "false" => Json::Bool(false),
+        _ => Json::String(value.to_string()),
+    }
+}
+
+/// Line of the first use of `name` (its first segment, as a whole word), or 1
+fn first_line(text: &str, name: &str) -> usize {
+    let root = name.split(['.', '(']).next().unwrap_or(name);
+    let pattern = format!(r"\b{}\b", regex::escape(root));
+    Regex::new(&pattern)
+        .ok()
+        .and_then(|re| re.find(text))
+        .map(|m| text[..m.start()].matches('\n').count() + 1)
+        .unwrap_or(1)
+}
+
+/// `_random_hex(N)` names for each call in `text`
+fn random_hex_calls(text: &str) -> Vec<(usize, String)> {
+    RANDOM_HEX_CALL
+        .captures_iter(text)
+        .map(|caps| {
+            let start = caps.get(0).map_or(0, |m| m.start());
+            let line = text[..start].matches('\n').count() + 1;
+            (line, format!("_random_hex({})", &caps[1]))
+        })
+        .collect()
+}
+
+/// Jinja2 templates rendered with minijinja
+pub struct JinjaRenderer {
+    env: Environment<'static>,
+}
+
+impl fmt::Debug for JinjaRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("JinjaRenderer")
+    }
+}
+
+impl Default for JinjaRenderer {
+    fn default() -> Self {
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+        // Projects are code, not HTML
+        env.set_auto_escape_callback(|_| AutoEscape::None);
+        for &filter in FILTERS {
+            env.add_filter(filter, move |value: String| {
+                apply_filter(filter, &value).unwrap_or(value)
+            });
+        }
+        env.add_function("_random_hex", |state: &State, length: u32| {
+            state
+                .lookup(&format!("_random_hex({})", length))
+                .unwrap_or(Value::UNDEFINED)
+        });
+        env.set_unknown_method_callback(string_method);
+        Self { env }
+    }
+}
+
+/// The Python string methods cookiecutter and copier templates commonly call,
+/// e.g. `{{ project_name.lower().replace(' ', '_') }}`
+fn string_method(_: &State, value: &Value, method: &str, args: &[Value]) -> Result<Value, Error> {
+    let unknown = || {
+        Error::new(
+            ErrorKind::UnknownMethod,
+            format!("{} has no method named {}", value.kind(), method),
+        )
+    };
+    let text = value.as_str().ok_or_else(unknown)?;
+    let arg = |i: usize| args.get(i).and_then(Value::as_str);
+    let chars = |i: usize| -> Vec<char> { arg(i).unwrap_or("").chars().collect() };
+
+    Ok(match method {
+        "lower" => Value::from(text.to_lowercase()),
+        "upper" => Value::from(text.to_uppercase()),
+        "title" => Value::from(apply_filter("title_case", text).unwrap_or_default()),
+        "capitalize" => {
+            let mut chars = text.chars();
+            Value::from(match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            })
+        }
+        "strip" | "lstrip" | "rstrip" => {
+            let chars = chars(0);
+            let trim = |c: char| {
+                if chars.is_empty() {
+                    c.is_whitespace()
+                } else {
+                    chars.contains(&c)
+                }
+            };
+            Value::from(match method {
+                "strip" => text.trim_matches(trim),
+                "lstrip" => text.trim_start_matches(trim),
+                _ => text.trim_end_matches(trim),
+            })
+        }
+        "replace" => match (arg(0), arg(1)) {
+            (Some(from), Some(to)) => Value::from(text.replace(from, to)),
+            _ => return Err(Error::new(ErrorKind::MissingArgument, "replace(old, new)")),
+        },
+        "startswith" => Value::from(arg(0).is_some_and(|prefix| text.starts_with(prefix))),
+        "endswith" => Value::from(arg(0).is_some_and(|suffix| text.ends_with(suffix))),
+        "split" => Value::from(match arg(0) {
+            Some(separator) => text
+                .split(separator)
+                .map(str::to_string)
+                .collect::<Vec<_>>(),
+            None => text.split_whitespace().map(str::to_string).collect(),
+        }),
+        "join" => {
+            let items = args
+                .first()
+                .ok_or_else(|| Error::new(ErrorKind::MissingArgument, "join(iterable)"))?;
+            let items: Vec<String> = items.try_iter()?.map(|item| item.to_string()).collect();
+            Value::from(items.join(text))
+        }
+        _ => return Err(unknown()),
+    })
+}
+
+impl Renderer for JinjaRenderer {
+    fn render(&self, text: &str, variables: &HashMap<String, String>) -> Result<String> {
+        self.env
+            .render_str(text, Value::from_serialize(nested(variables)))
+            .map_err(|e| anyhow::anyhow!("{:#}", e))
+    }
+
+    fn find_placeholder_lines(&self, text: &str) -> Vec<(usize, String)> {
+        let env = Environment::new();
+        let Ok(template) = env.template_from_str(text) else {
+            return Vec::new();
+        };
+        let globals: BTreeSet<&str> = self.env.globals().map(|(name, _)| name).collect();
+        let mut names: Vec<(usize, String)> = template
+            .undeclared_variables(true)
+            .into_iter()
+            .filter(|name| {
+                let root = name.split('.').next().unwrap_or(name);
+                !globals.contains(root)
+            })
+            .map(|name| {
+                let name = strip_method_calls(text, name);
+                (first_line(text, &name), name)
+            })
+            .collect();
+        names.extend(random_hex_calls(text));
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn check(&self, text: &str) -> Result<()> {
+        let env = Environment::new();
+        env.template_from_str(text)
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("{:#}", e))
+    }
+}
+
+/// Drop trailing attributes that are called as methods (`features.split(', ')`
+/// uses `features`), which Jinja reports as part of the variable path
+fn strip_method_calls(text: &str, mut name: String) -> String {
+    while let Some((root, _)) = name.rsplit_once('.') {
+        if !text.contains(&format!("{}(", name)) {
+            break;
+        }
+        name = root.to_string();
+    }
+    name
+}
+
+/// Handlebars templates
+pub struct HandlebarsRenderer {
+    registry: Handlebars<'static>,
+}
+
+impl fmt::Debug for HandlebarsRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HandlebarsRenderer")
+    }
+}
+
+impl Default for HandlebarsRenderer {
+    fn default() -> Self {
+        let mut registry = Handlebars::new();
+        // Projects are code, not HTML
+        registry.register_escape_fn(handlebars::no_escape);
+        for &filter in FILTERS {
+            registry.register_helper(filter, Box::new(FilterHelper(filter)));
+        }
+        registry.register_helper("_random_hex", Box::new(RandomHexHelper));
+        Self { registry }
+    }
+}
+
+/// One of the simple engine's filters as a helper: `{{snake_case name}}`
+struct FilterHelper(&'static str);
+
+impl HelperDef for FilterHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = h
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .unwrap_or_default();
+        out.write(&apply_filter(self.0, value).unwrap_or_default())?;
+        Ok(())
+    }
+}
+
+/// `{{_random_hex 32}}`, answered from the `_random_hex(32)` built-in
+struct RandomHexHelper;
+
+impl HelperDef for RandomHexHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let length = h
+            .param(0)
+            .and_then(|p| p.value().as_u64())
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("_random_hex", 0))?;
+        let name = format!("_random_hex({})", length);
+        if let Some(value) = ctx.data().get(&name).and_then(Json::as_str) {
+            out.write(value)?;
+        }
+        Ok(())
+    }
+}
+
+/// A variable referenced by a handlebars path, or None for block-local paths
+/// (`this`, `../x`, `@index`)
+fn handlebars_variable(parameter: &Parameter) -> Option<String> {
+    let Parameter::Path(handlebars::Path::Relative((_, raw))) = parameter else {
+        return None;
+    };
+    if raw.is_empty() || raw.starts_with("this") || raw.starts_with("..") || raw.starts_with('@') {
+        return None;
+    }
+    Some(raw.replace('/', "."))
+}
+
+fn scan_handlebars(template: &Template, names: &mut Vec<(usize, String)>) {
+    for (index, element) in template.elements.iter().enumerate() {
+        let line = template.mapping.get(index).map_or(1, |m| m.0);
+        let helper = match element {
+            TemplateElement::Expression(helper)
+            | TemplateElement::HtmlExpression(helper)
+            | TemplateElement::HelperBlock(helper) => helper,
+            _ => continue,
+        };
+
+        let is_call = !helper.params.is_empty() || !helper.hash.is_empty() || helper.block;
+        if !is_call {
+            // A plain {{name}}
+            if let Some(name) = helper.name.as_name() {
+                if let Some(name) = handlebars_variable(&Parameter::Path(
+                    handlebars::Path::Relative((Vec::new(), name.to_string())),
+                )) {
+                    names.push((line, name));
+                }
+            }
+            continue;
+        }
+
+        if helper.name.as_name() == Some("_random_hex") {
+            if let Some(Parameter::Literal(length)) = helper.params.first() {
+                names.push((line, format!("_random_hex({})", length)));
+            }
+            continue;
+        }
+        for parameter in helper.params.iter().chain(helper.hash.values()) {
+            names.extend(handlebars_variable(parameter).map(|name| (line, name)));
+        }
+
+        // Inside #each and #with, names refer to the items, not to answers
+        let scoped = matches!(helper.name.as_name(), Some("each" | "with"));
+        if let Some(body) = helper.template.as_ref().filter(|_| !scoped) {
+            scan_handlebars(body, names);
+        }
+        if let Some(inverse) = &helper.inverse {
+            scan_handlebars(inverse, names);
+        }
+    }
+}
+
+impl Renderer for HandlebarsRenderer {
+    fn render(&self, text: &str, variables: &HashMap<String, String>) -> Result<String> {
+        self.registry
+            .render_template(text, &nested(variables))
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    fn find_placeholder_lines(&self, text: &str) -> Vec<(usize, String)> {
+        let Ok(template) = Template::compile(text) else {
+            return Vec::new();
+        };
+        let mut names = Vec::new();
+        scan_handlebars(&template, &mut names);
+        names
+    }
+
+    fn check(&self, text: &str) -> Result<()> {
+        Template::compile(text)
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .context("Invalid handlebars template")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answers() -> HashMap<String, String> {
+        HashMap::from([
+            ("name".to_string(), "My App".to_string()),
+            ("docker".to_string(), "true".to_string()),
+            ("tests".to_string(), "false".to_string()),
+            ("_now.year".to_string(), "2000".to_string()),
+            ("_random_hex(4)".to_string(), "beef".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_jinja_renderer() {
+        let jinja = JinjaRenderer::default();
+        let text = "# {{ name | snake_case }} ({{ _now.year }})\n\
+                    {% if docker %}FROM rust\n{% endif %}\
+                    {% for i in range(2) %}{{ i }}{% endfor %} {{ _random_hex(4) }}\n";
+        assert_eq!(
+            jinja.render(text, &answers()).unwrap(),
+            "# my_app (2000)\nFROM rust\n01 beef\n"
+        );
+        assert_eq!(
+            jinja.find_placeholder_lines(text),
+            vec![
+                (1, "_now.year".to_string()),
+                (1, "name".to_string()),
+                (2, "docker".to_string()),
+                (3, "_random_hex(4)".to_string()),
+            ]
+        );
+        assert_eq!(
+            jinja.find_placeholder_lines("{% for k in _git.keys() %}{% endfor %}"),
+            vec![(1, "_git".to_string())]
+        );
+        assert!(jinja.check("{% if %}").is_err());
+        assert_eq!(
+            jinja
+                .render("{% if tests %}!{% endif %}", &answers())
+                .unwrap(),
+            ""
+        );
+        assert_eq!(jinja.render("<{{ missing }}>", &answers()).unwrap(), "<>");
+        assert_eq!(
+            jinja
+                .render(
+                    "{{ name.lower().replace(' ', '_') }} {{ '-'.join(name.split()) }}",
+                    &answers()
+                )
+                .unwrap(),
+            "my_app My-App"
+        );
+        assert!(jinja.render("{{ name.nope() }}", &answers()).is_err());
+    }
+
+    #[test]
+    fn test_handlebars_renderer() {
+        let handlebars = HandlebarsRenderer::default();
+        let text = "# {{kebab_case name}} ({{_now.year}})\n\
+                    {{#if docker}}FROM rust{{/if}}{{#if tests}}!{{/if}}\n\
+                    {{#each items}}{{this}}{{/each}}<{{name}}> {{_random_hex 4}}\n";
+        assert_eq!(
+            handlebars.render(text, &answers()).unwrap(),
+            "# my-app (2000)\nFROM rust\n<My App> beef\n"
+        );
+        let names: Vec<String> = handlebars
+            .find_placeholders(text)
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "_now.year",
+                "_random_hex(4)",
+                "docker",
+                "items",
+                "name",
+                "tests"
+            ]
+        );
+        assert!(
+            handlebars
+                .find_placeholder_lines(text)
+                .contains(&(3, "_random_hex(4)".to_string()))
+        );
+        assert!(handlebars.check("{{#if x}}").is_err());
+    }
+
+    #[test]
+    fn test_nested() {
+        let variables = HashMap::from([
+            ("a".to_string(), "1".to_string()),
+            ("a.b".to_string(), "2".to_string()),
+            ("_now.year".to_string(), "2000".to_string()),
+        ]);
+        assert_eq!(
+            nested(&variables),
+            serde_json::json!({"a": {"b": "2"}, "_now": {"year": "2000"}})
+        );
+    }
+}
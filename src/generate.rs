@@ -0,0 +1,494 @@
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+
+use crate::builtins::Builtins;
+use crate::copy::{
+    OutputPlan, RenderOptions, copy_template_recursive, plan_output, preview_template,
+    write_generated_files,
+};
+use crate::engine::Renderer;
+use crate::git::{GitInit, init_repository};
+use crate::hooks::{HookEvent, HookResult, ScriptOutcome, execute_hooks, run_scripts, shell_quote};
+use crate::paths::{PathPatterns, PermissionRules, relative_path};
+use crate::prompt::{MULTI_SEPARATOR, Prompter};
+use crate::template::{GitConfig, TemplateConfig, Variable};
+use crate::variables::{apply_filter, computed_order, evaluate_conditionals};
+use crate::vfs::{DiskFs, Vfs};
+
+/// `[git]` settings used when git is forced on for a template without a `[git]` section
+static DEFAULT_GIT: LazyLock<GitConfig> = LazyLock::new(GitConfig::default);
+
+/// The `[git]` settings to apply, if any: `force` (--git/--no-git) overrides the
+/// template's `init`
+pub fn git_settings(config: &TemplateConfig, force: Option<bool>) -> Option<&GitConfig> {
+    match force {
+        Some(false) => None,
+        Some(true) => Some(config.git.as_ref().unwrap_or(&DEFAULT_GIT)),
+        None => config.git.as_ref().filter(|g| g.init),
+    }
+}
+
+/// Collects the answers for one project, before anything is planned or written
+///
+/// `create` fills it from prompts and `-v` flags; `scaffold test` from the answer
+/// sets in template.toml.
+pub struct Generator<'a> {
+    source: &'a dyn Vfs,
+    template_path: &'a Path,
+    config: &'a TemplateConfig,
+    output: PathBuf,
+    engine: Arc<dyn Renderer>,
+    builtins: Builtins,
+    variables: HashMap<String, String>,
+    /// Fallback defaults by variable name (from the global config)
+    defaults: HashMap<String, String>,
+    default_project_name: String,
+}
+
+impl<'a> Generator<'a> {
+    /// Start from the generated built-ins (_now.year, _uuid, ...); `answers` override them
+    pub fn new(
+        template_path: &'a Path,
+        config: &'a TemplateConfig,
+        output: &Path,
+        answers: HashMap<String, String>,
+        mut builtins: Builtins,
+    ) -> Result<Self> {
+        let engine = config.renderer()?;
+        let mut variables = builtins.variables()?;
+        variables.extend(answers);
+
+        // The output directory's name, e.g. for deriving a package name
+        let output_name = output
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        variables
+            .entry("_output.name".to_string())
+            .or_insert_with(|| output_name.clone());
+
+        // project_name defaults to the output directory name, e.g. my-api -> my_api.
+        // Templates can override this with their own default for project_name.
+        let default_project_name = apply_filter("snake_case", &output_name).unwrap_or(output_name);
+
+        Ok(Self {
+            source: &DiskFs,
+            template_path,
+            config,
+            output: output.to_path_buf(),
+            engine,
+            builtins,
+            variables,
+            defaults: HashMap::new(),
+            default_project_name,
+        })
+    }
+
+    /// Read the template from `source` instead of the disk, e.g. an [`EmbeddedFs`]
+    ///
+    /// [`EmbeddedFs`]: crate::vfs::EmbeddedFs
+    pub fn with_source(mut self, source: &'a dyn Vfs) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Use these defaults for variables whose template entry has none
+    pub fn with_defaults(mut self, defaults: HashMap<String, String>) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    pub fn is_answered(&self, name: &str) -> bool {
+        self.variables.contains_key(name)
+    }
+
+    pub fn answer(&mut self, name: &str, value: String) {
+        self.variables.insert(name.to_string(), value);
+    }
+
+    /// The default offered for a variable: the template default, then the global config.
+    /// Template defaults may use placeholders such as {{ _git.user_name }}.
+    pub fn default_for(&mut self, var: &Variable) -> Result<Option<String>> {
+        let default = match &var.default {
+            Some(default) => Some(self.render_text(default)?),
+            None => self.defaults.get(&var.name).cloned(),
+        };
+        Ok(default
+            .or_else(|| (var.name == "project_name").then(|| self.default_project_name.clone())))
+    }
+
+    /// Answer every remaining variable with its default, as `--yes` does
+    pub fn answer_defaults(&mut self) -> Result<()> {
+        let config = self.config;
+        for var in &config.variables {
+            if self.is_answered(&var.name) {
+                continue;
+            }
+            let default = self.default_for(var)?;
+            let value = match var.var_type.as_deref().unwrap_or("string") {
+                "bool" => default.unwrap_or_else(|| "false".to_string()),
+                "choice" => default
+                    .or_else(|| var.choices.first().cloned())
+                    .unwrap_or_default(),
+                "multi" => {
+                    split_multi(default.as_deref().unwrap_or_default()).join(MULTI_SEPARATOR)
+                }
+                _ => default.unwrap_or_default(),
+            };
+            self.answer(&var.name, value);
+        }
+        Ok(())
+    }
+
+    /// Ask `prompter` for every variable not answered yet, in template order
+    pub fn prompt_missing(&mut self, prompter: &mut dyn Prompter) -> Result<()> {
+        let config = self.config;
+        for var in &config.variables {
+            if self.is_answered(&var.name) {
+                continue;
+            }
+            let default = self.default_for(var)?;
+            let value = match var.var_type.as_deref().unwrap_or("string") {
+                "bool" => {
+                    let default = default
+                        .as_deref()
+                        .is_some_and(|d| d == "true" || d == "yes");
+                    prompter
+                        .bool(&var.name, &var.description, default)?
+                        .to_string()
+                }
+                "choice" => {
+                    ensure_choices(var)?;
+                    let default = default
+                        .and_then(|d| var.choices.iter().position(|c| *c == d))
+                        .unwrap_or(0);
+                    let index =
+                        prompter.choice(&var.name, &var.description, &var.choices, default)?;
+                    var.choices[index].clone()
+                }
+                "multi" => {
+                    ensure_choices(var)?;
+                    let selected = split_multi(default.as_deref().unwrap_or_default());
+                    let defaults: Vec<bool> = var
+                        .choices
+                        .iter()
+                        .map(|c| selected.contains(&c.as_str()))
+                        .collect();
+                    let indices =
+                        prompter.multi(&var.name, &var.description, &var.choices, &defaults)?;
+                    indices
+                        .into_iter()
+                        .map(|i| var.choices[i].as_str())
+                        .collect::<Vec<_>>()
+                        .join(MULTI_SEPARATOR)
+                }
+                "secret" => {
+                    let value = prompter.secret(&var.name, &prompt_text(var))?;
+                    match default {
+                        Some(default) if value.is_empty() => default,
+                        _ => value,
+                    }
+                }
+                _ => prompter.string(&var.name, &prompt_text(var), default.as_deref())?,
+            };
+            self.answer(&var.name, value);
+        }
+        Ok(())
+    }
+
+    /// Derive computed variables, run scripts and plan every destination, so invalid
+    /// names and unresolved placeholders are known before anything is written
+    pub fn finish(mut self) -> Result<Generation<'a>> {
+        let config = self.config;
+        let engine = self.engine.clone();
+
+        // Templates that use project_name without declaring it get the default too
+        self.variables
+            .entry("project_name".to_string())
+            .or_insert_with(|| self.default_project_name.clone());
+
+        // Derive computed variables; answers given up front take precedence
+        let mut unresolved = Vec::new();
+        for computed in computed_order(&config.computed, engine.as_ref())? {
+            if self.is_answered(&computed.name) {
+                continue;
+            }
+            let value = self.render_text(&computed.value)?;
+            for name in engine.find_placeholders(&computed.value) {
+                if !self.is_answered(&name) {
+                    unresolved.push(format!(
+                        "template.toml [computed] {}: {}",
+                        computed.name, name
+                    ));
+                }
+            }
+            self.answer(&computed.name, value);
+        }
+
+        // Run embedded scripts (in-process, so they also run for --dry-run)
+        let scripts = config
+            .hooks
+            .as_ref()
+            .map(|h| h.scripts.as_slice())
+            .unwrap_or_default();
+        let script_outcome = if scripts.is_empty() {
+            ScriptOutcome::default()
+        } else {
+            run_scripts(
+                self.source,
+                scripts,
+                self.template_path,
+                &mut self.variables,
+            )?
+        };
+
+        // Build exclusion set from conditionals, script requests and the scripts themselves
+        let mut excluded_files = evaluate_conditionals(&config.conditionals, &self.variables);
+        excluded_files.extend(script_outcome.excluded);
+        excluded_files.extend(scripts.iter().cloned());
+
+        let render_options = RenderOptions {
+            excluded: excluded_files,
+            permissions: PermissionRules::new(&config.permissions)?,
+            copy_without_render: PathPatterns::new(&config.copy_without_render)?,
+            engine,
+            renames: config.rename.clone(),
+            ..RenderOptions::default()
+        };
+
+        let Generator {
+            source,
+            template_path,
+            output,
+            mut builtins,
+            mut variables,
+            ..
+        } = self;
+
+        let mut plan = plan_output(source, template_path, &output, &variables, &render_options)?;
+        let used: Vec<String> = plan.unresolved.iter().map(|p| p.name.clone()).collect();
+        if builtins.resolve_into(&mut variables, used)? {
+            // Some placeholders were _env.* or _random_hex(N); plan again with their values
+            plan = plan_output(source, template_path, &output, &variables, &render_options)?;
+        }
+        unresolved.extend(plan.unresolved.iter().map(ToString::to_string));
+
+        Ok(Generation {
+            source,
+            template_path,
+            config,
+            output,
+            builtins,
+            variables,
+            render_options,
+            plan,
+            unresolved,
+            added_files: script_outcome.added_files,
+        })
+    }
+
+    fn render_text(&mut self, text: &str) -> Result<String> {
+        render_text(
+            text,
+            self.engine.as_ref(),
+            &mut self.variables,
+            &mut self.builtins,
+        )
+    }
+}
+
+/// A planned project: the final answers and every file that will be written
+pub struct Generation<'a> {
+    source: &'a dyn Vfs,
+    template_path: &'a Path,
+    config: &'a TemplateConfig,
+    output: PathBuf,
+    builtins: Builtins,
+    pub variables: HashMap<String, String>,
+    pub render_options: RenderOptions,
+    pub plan: OutputPlan,
+    /// Placeholders without a value, e.g. "README.md:3: author"
+    pub unresolved: Vec<String>,
+    /// Files added by scripts, as (output-relative path, contents)
+    pub added_files: Vec<(String, String)>,
+}
+
+impl Generation<'_> {
+    /// Fail on unresolved placeholders unless the template or `lenient` allows them
+    pub fn check_unresolved(&self, lenient: bool) -> Result<()> {
+        if self.unresolved.is_empty() || lenient || !self.config.render.strict {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Unresolved placeholders (no value for these variables):\n{}\n\
+             Define them in template.toml, pass them with -v name=value, \
+             or use --lenient to leave them as-is.",
+            self.unresolved_list()
+        );
+    }
+
+    /// The unresolved placeholders, one indented line each
+    pub fn unresolved_list(&self) -> String {
+        self.unresolved
+            .iter()
+            .map(|p| format!("  {}", p))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Output-relative paths of every planned file, including files from scripts
+    pub fn files(&self) -> Vec<String> {
+        self.plan
+            .files
+            .iter()
+            .filter_map(|f| relative_path(f, &self.output))
+            .chain(self.added_files.iter().map(|(p, _)| p.clone()))
+            .collect()
+    }
+
+    /// Copy and render the template into the output directory
+    pub fn write(&self, progress: Option<&ProgressBar>) -> Result<()> {
+        self.write_to(&mut DiskFs, progress)
+    }
+
+    /// Copy and render the template into the output directory of `out`, e.g. a
+    /// [`MemoryFs`] for a preview
+    ///
+    /// [`MemoryFs`]: crate::vfs::MemoryFs
+    pub fn write_to(&self, out: &mut dyn Vfs, progress: Option<&ProgressBar>) -> Result<()> {
+        copy_template_recursive(
+            self.source,
+            self.template_path,
+            out,
+            &self.output,
+            &self.variables,
+            &self.render_options,
+            progress,
+        )?;
+        write_generated_files(out, &self.output, &self.added_files)
+    }
+
+    /// Write the tree of files that would be written to `out` (for --dry-run)
+    pub fn preview(&self, out: &mut dyn Write) -> Result<()> {
+        preview_template(
+            self.source,
+            self.template_path,
+            &self.output,
+            &self.variables,
+            &self.render_options,
+            out,
+        )
+    }
+
+    /// The template's post-create commands with placeholders substituted
+    ///
+    /// Values are shell-quoted, so an answer like `x; rm -rf ~` stays one argument.
+    fn post_create_commands(&mut self) -> Result<Vec<String>> {
+        let config = self.config;
+        let engine = Arc::clone(&self.render_options.engine);
+        let mut commands = Vec::new();
+        for cmd in config.hooks.iter().flat_map(|h| &h.post_create) {
+            // Generate parameterized built-ins first, so they are quoted too
+            self.builtins
+                .resolve_into(&mut self.variables, engine.find_placeholders(cmd))?;
+            let quoted: HashMap<String, String> = self
+                .variables
+                .iter()
+                .map(|(name, value)| (name.clone(), shell_quote(value)))
+                .collect();
+            commands.push(engine.render(cmd, &quoted)?);
+        }
+        Ok(commands)
+    }
+
+    /// Run the template's post-create commands in the output directory
+    ///
+    /// Each command's start and result are passed to `progress`; failures are
+    /// reported in the results.
+    pub fn run_post_create(
+        &mut self,
+        progress: &mut dyn FnMut(HookEvent),
+    ) -> Result<Vec<HookResult>> {
+        let commands = self.post_create_commands()?;
+        execute_hooks(&commands, &self.output, progress)
+    }
+
+    /// Initialize a git repository in the output directory, with `[git]` fields rendered
+    pub fn init_git(&mut self, git: &GitConfig) -> Result<()> {
+        let commit_message = self.render_text(&git.commit_message)?;
+        let author_name = git
+            .author_name
+            .as_deref()
+            .map(|name| self.render_text(name))
+            .transpose()?;
+        let author_email = git
+            .author_email
+            .as_deref()
+            .map(|email| self.render_text(email))
+            .transpose()?;
+
+        init_repository(
+            &self.output,
+            &GitInit {
+                branch: &git.branch,
+                commit_message: git.commit.then_some(commit_message.as_str()),
+                author_name: author_name.as_deref(),
+                author_email: author_email.as_deref(),
+            },
+        )
+        .context("Project files were created, but git initialization failed")
+    }
+
+    /// Substitute variables in a template.toml string (git fields, hook commands)
+    pub fn render_text(&mut self, text: &str) -> Result<String> {
+        render_text(
+            text,
+            self.render_options.engine.as_ref(),
+            &mut self.variables,
+            &mut self.builtins,
+        )
+    }
+}
+
+/// The values of a `multi` answer or default, e.g. "docker, ci" or "docker,ci"
+pub fn split_multi(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+fn ensure_choices(var: &Variable) -> Result<()> {
+    anyhow::ensure!(
+        !var.choices.is_empty(),
+        "Variable '{}' is type '{}' but has no choices defined",
+        var.name,
+        var.var_type.as_deref().unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// String prompts show the variable name too, since the answer is free text
+fn prompt_text(var: &Variable) -> String {
+    if var.description.is_empty() {
+        var.name.clone()
+    } else {
+        format!("{} ({})", var.description, var.name)
+    }
+}
+
+/// Substitute variables in text, generating any parameterized built-ins it uses
+fn render_text(
+    text: &str,
+    engine: &dyn Renderer,
+    variables: &mut HashMap<String, String>,
+    builtins: &mut Builtins,
+) -> Result<String> {
+    builtins.resolve_into(variables, engine.find_placeholders(text))?;
+    engine.render(text, variables)
+}
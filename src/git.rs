@@ -1,8 +1,7 @@
 use anyhow::{Context, Result};
-use console::style;
-use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 use std::process::Command;
+use tempfile::TempDir;
 
 /// Parsed remote URL with optional subpath
 pub struct RemoteUrl {
@@ -20,7 +19,7 @@ pub fn is_git_url(path: &str) -> bool {
 }
 
 /// Parse a git URL, extracting the repo URL and optional subpath
-/// Supports: https://github.com/org/repo.git#path/to/template
+/// Supports: `https://github.com/org/repo.git#path/to/template`
 ///           github:org/repo#path/to/template
 pub fn parse_git_url(url: &str) -> RemoteUrl {
     // Handle github: shorthand
@@ -47,31 +46,99 @@ pub fn parse_git_url(url: &str) -> RemoteUrl {
     RemoteUrl { repo_url, subpath }
 }
 
-/// Clone a git repository to a temporary directory
-pub fn clone_repo(url: &str) -> Result<PathBuf> {
-    let temp_dir = std::env::temp_dir().join(format!("scaffold-{}", std::process::id()));
+/// Settings for initializing a repository in a generated project
+pub struct GitInit<'a> {
+    pub branch: &'a str,
+    /// Initial commit message; no commit is made when None
+    pub commit_message: Option<&'a str>,
+    pub author_name: Option<&'a str>,
+    pub author_email: Option<&'a str>,
+}
 
-    // Clean up if exists from previous failed run
-    if temp_dir.exists() {
-        fs::remove_dir_all(&temp_dir).context("Failed to clean up temp directory")?;
+/// Run a git command, returning its trimmed stdout
+fn run_git(args: &[&str], working_dir: Option<&Path>) -> Result<String> {
+    let mut command = Command::new("git");
+    command.args(args);
+    if let Some(dir) = working_dir {
+        command.current_dir(dir);
     }
 
-    println!("Cloning {}...", style(url).dim());
-
-    let temp_path = temp_dir
-        .to_str()
-        .context("Temp directory path contains invalid UTF-8")?;
-
-    let output = Command::new("git")
-        .args(["clone", "--depth", "1", url, temp_path])
+    let subcommand = subcommand(args);
+    let output = command
         .output()
-        .context("Failed to execute git clone. Is git installed?")?;
+        .with_context(|| format!("Failed to execute git {}. Is git installed?", subcommand))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Git clone failed: {}", stderr.trim());
+        anyhow::bail!("Git {} failed: {}", subcommand, stderr.trim());
     }
 
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The git subcommand in `args`, skipping `-c name=value` options before it
+fn subcommand<'a>(args: &[&'a str]) -> &'a str {
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        if arg == "-c" {
+            args.next();
+        } else {
+            return arg;
+        }
+    }
+    ""
+}
+
+/// Initialize a git repository, stage every file and optionally make an initial commit
+pub fn init_repository(dir: &Path, options: &GitInit) -> Result<()> {
+    run_git(&["init", "--quiet"], Some(dir))?;
+    // Set the branch via HEAD rather than `init -b`, which needs git 2.28+
+    let head = format!("refs/heads/{}", options.branch);
+    run_git(&["symbolic-ref", "HEAD", &head], Some(dir))?;
+    run_git(&["add", "--all"], Some(dir))?;
+
+    if let Some(message) = options.commit_message {
+        let mut args = Vec::new();
+        let name_config;
+        let email_config;
+        if let Some(name) = options.author_name.filter(|n| !n.is_empty()) {
+            name_config = format!("user.name={}", name);
+            args.extend(["-c", &name_config]);
+        }
+        if let Some(email) = options.author_email.filter(|e| !e.is_empty()) {
+            email_config = format!("user.email={}", email);
+            args.extend(["-c", &email_config]);
+        }
+        args.extend(["commit", "--quiet", "--allow-empty", "-m", message]);
+        run_git(&args, Some(dir))?;
+    }
+
+    Ok(())
+}
+
+/// Read a value from the user's git configuration (e.g. `user.email`)
+///
+/// Returns None if git is not installed or the key is unset.
+pub fn config_value(key: &str) -> Option<String> {
+    run_git(&["config", "--get", key], None)
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+/// Clone a git repository to a new temporary directory, removed when it is dropped
+pub fn clone_repo(url: &str) -> Result<TempDir> {
+    let temp_dir = tempfile::Builder::new()
+        .prefix("scaffold-")
+        .tempdir()
+        .context("Failed to create temp directory")?;
+
+    let temp_path = temp_dir
+        .path()
+        .to_str()
+        .context("Temp directory path contains invalid UTF-8")?;
+
+    run_git(&["clone", "--depth", "1", url, temp_path], None)?;
+
     Ok(temp_dir)
 }
 
@@ -79,6 +146,23 @@ pub fn clone_repo(url: &str) -> Result<PathBuf> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_subcommand_skips_config_options() {
+        assert_eq!(subcommand(&["init", "--quiet"]), "init");
+        assert_eq!(
+            subcommand(&[
+                "-c",
+                "user.name=A",
+                "-c",
+                "user.email=a@b",
+                "commit",
+                "-m",
+                "x"
+            ]),
+            "commit"
+        );
+    }
+
     #[test]
     fn test_is_git_url() {
         assert!(is_git_url("https://github.com/org/repo.git"));
@@ -112,6 +196,27 @@ mod tests {
         assert!(parsed.subpath.is_none());
     }
 
+    #[test]
+    fn test_init_repository_with_commit() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join("README.md"), "# test").unwrap();
+
+        let options = GitInit {
+            branch: "trunk",
+            commit_message: Some("Initial commit"),
+            author_name: Some("Test Author"),
+            author_email: Some("test@example.com"),
+        };
+        init_repository(temp.path(), &options).unwrap();
+
+        let branch = run_git(&["rev-parse", "--abbrev-ref", "HEAD"], Some(temp.path())).unwrap();
+        assert_eq!(branch, "trunk");
+        let log = run_git(&["log", "--format=%an <%ae> %s"], Some(temp.path())).unwrap();
+        assert_eq!(log, "Test Author <test@example.com> Initial commit");
+        let files = run_git(&["ls-files"], Some(temp.path())).unwrap();
+        assert_eq!(files, "README.md");
+    }
+
     #[test]
     fn test_parse_git_url_github_shorthand_with_subpath() {
         let parsed = parse_git_url("github:org/repo#templates/api");
@@ -1,15 +1,60 @@
 use anyhow::{Context, Result};
-use console::style;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::process::Command;
+use std::rc::Rc;
+
+use crate::paths::check_relative_path;
+use crate::vfs::Vfs;
+
+/// Upper bound on operations a single script may perform (guards against infinite loops)
+const MAX_SCRIPT_OPERATIONS: u64 = 1_000_000;
+
+/// Changes requested by template scripts, applied when rendering the project
+#[derive(Debug, Default)]
+pub struct ScriptOutcome {
+    /// Files or directories to leave out (file names or template-relative paths)
+    pub excluded: HashSet<String>,
+    /// Extra files to write, as (output-relative path, contents)
+    pub added_files: Vec<(String, String)>,
+}
+
+/// Outcome of a single post-create command
+#[derive(Debug, Serialize)]
+pub struct HookResult {
+    pub command: String,
+    pub success: bool,
+    /// Trimmed stderr of a failed command
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub error: String,
+}
+
+/// Progress of [`execute_hooks`], for callers that show it
+#[derive(Debug)]
+pub enum HookEvent<'a> {
+    /// A command is about to run
+    Started(&'a str),
+    /// A command has finished
+    Finished(&'a HookResult),
+}
 
 /// Execute shell commands as hooks
 ///
 /// On Unix systems, commands are executed via `sh -c`.
 /// On Windows, commands are executed via `cmd /C`.
-pub fn execute_hooks(commands: &[String], working_dir: &Path) -> Result<()> {
+/// Each command's start and result are passed to `progress`; failures are
+/// reported in the results.
+pub fn execute_hooks(
+    commands: &[String],
+    working_dir: &Path,
+    progress: &mut dyn FnMut(HookEvent),
+) -> Result<Vec<HookResult>> {
+    let mut results = Vec::new();
     for cmd in commands {
-        println!("  Running: {}", style(cmd).dim());
+        progress(HookEvent::Started(cmd));
 
         let output = if cfg!(target_os = "windows") {
             Command::new("cmd")
@@ -25,16 +70,237 @@ pub fn execute_hooks(commands: &[String], working_dir: &Path) -> Result<()> {
         }
         .with_context(|| format!("Failed to execute hook: {}", cmd))?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            eprintln!(
-                "  {}: Hook failed: {}",
-                style("Warning").yellow(),
-                stderr.trim()
-            );
+        let error = if output.status.success() {
+            String::new()
         } else {
-            println!("  {}", style("ok").green());
+            String::from_utf8_lossy(&output.stderr).trim().to_string()
+        };
+        let result = HookResult {
+            command: cmd.clone(),
+            success: output.status.success(),
+            error,
+        };
+        progress(HookEvent::Finished(&result));
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Quote a value for the shell hooks run in, unless it is plain enough not to need it
+///
+/// Unix values are single-quoted; Windows values are double-quoted with inner
+/// quotes doubled.
+pub fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:=@+,".contains(c));
+    if plain {
+        value.to_string()
+    } else if cfg!(target_os = "windows") {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Run embedded Rhai scripts from the template directory
+///
+/// Scripts run in-process before any files are written, so they work the same on
+/// every platform and also run for --dry-run. They see the answers as a `variables`
+/// map they can read and modify, and can call:
+///
+/// - `exclude(path)` to leave a file or directory out of the output
+/// - `add_file(path, contents)` to write an extra file into the output
+/// - `abort(message)` to stop project creation with an error
+pub fn run_scripts(
+    fs: &dyn Vfs,
+    scripts: &[String],
+    template_dir: &Path,
+    variables: &mut HashMap<String, String>,
+) -> Result<ScriptOutcome> {
+    let outcome = Rc::new(RefCell::new(ScriptOutcome::default()));
+    let engine = build_engine(&outcome);
+
+    for script in scripts {
+        let script_path = template_dir.join(script);
+        let source = fs.read_to_string(&script_path).with_context(|| {
+            format!(
+                "Failed to read script '{}'. Check that it exists in the template.",
+                script_path.display()
+            )
+        })?;
+
+        let mut scope = Scope::new();
+        let answers: Map = variables
+            .iter()
+            .map(|(k, v)| (k.as_str().into(), Dynamic::from(v.clone())))
+            .collect();
+        scope.push("variables", answers);
+
+        engine
+            .run_with_scope(&mut scope, &source)
+            .map_err(|e| match *e {
+                EvalAltResult::ErrorRuntime(message, _) => {
+                    anyhow::anyhow!("Script '{}' aborted: {}", script, message)
+                }
+                other => anyhow::anyhow!("Script '{}' failed: {}", script, other),
+            })?;
+
+        let answers = scope.get_value::<Map>("variables").ok_or_else(|| {
+            anyhow::anyhow!(
+                "Script '{}' replaced 'variables' with a non-map value",
+                script
+            )
+        })?;
+
+        variables.clear();
+        for (key, value) in answers {
+            if value.is_unit() {
+                continue;
+            }
+            variables.insert(key.to_string(), value.to_string());
         }
     }
-    Ok(())
+
+    drop(engine);
+    let outcome = Rc::try_unwrap(outcome)
+        .map(RefCell::into_inner)
+        .unwrap_or_default();
+    Ok(outcome)
+}
+
+/// Check that a script parses, without running it
+pub fn check_script(source: &str) -> Result<()> {
+    Engine::new()
+        .compile(source)
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+fn build_engine(outcome: &Rc<RefCell<ScriptOutcome>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+
+    let state = Rc::clone(outcome);
+    engine.register_fn("exclude", move |path: &str| {
+        state.borrow_mut().excluded.insert(path.to_string());
+    });
+
+    let state = Rc::clone(outcome);
+    engine.register_fn(
+        "add_file",
+        move |path: &str, contents: &str| -> Result<(), Box<EvalAltResult>> {
+            check_relative_path(path).map_err(|e| e.to_string())?;
+            state
+                .borrow_mut()
+                .added_files
+                .push((path.to_string(), contents.to_string()));
+            Ok(())
+        },
+    );
+
+    engine.register_fn("abort", |message: &str| -> Result<(), Box<EvalAltResult>> {
+        Err(message.into())
+    });
+
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    fn run(source: &str, variables: &mut HashMap<String, String>) -> Result<ScriptOutcome> {
+        let mut fs = MemoryFs::new();
+        fs.write(Path::new("setup.rhai"), source.as_bytes(), None)
+            .unwrap();
+        run_scripts(&fs, &["setup.rhai".to_string()], Path::new(""), variables)
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("my-app_2.0"), "my-app_2.0");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a; b $(c)"), "'a; b $(c)'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn test_script_reads_and_writes_variables() {
+        let mut vars = HashMap::new();
+        vars.insert("project_name".to_string(), "my-app".to_string());
+
+        run(
+            r#"
+            let crate_name = variables.project_name;
+            crate_name.replace("-", "_");
+            variables.crate_name = crate_name;
+            variables.has_tests = true;
+            "#,
+            &mut vars,
+        )
+        .unwrap();
+
+        assert_eq!(vars.get("crate_name").unwrap(), "my_app");
+        assert_eq!(vars.get("has_tests").unwrap(), "true");
+        assert_eq!(vars.get("project_name").unwrap(), "my-app");
+    }
+
+    #[test]
+    fn test_script_excludes_and_adds_files() {
+        let mut vars = HashMap::new();
+        let outcome = run(
+            r#"
+            exclude("src/cli.rs");
+            add_file("VERSION", "0.1.0\n");
+            "#,
+            &mut vars,
+        )
+        .unwrap();
+
+        assert!(outcome.excluded.contains("src/cli.rs"));
+        assert_eq!(
+            outcome.added_files,
+            vec![("VERSION".to_string(), "0.1.0\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_script_add_file_outside_output() {
+        let mut vars = HashMap::new();
+        let err = run(r#"add_file("../escape.txt", "");"#, &mut vars).unwrap_err();
+        assert!(err.to_string().contains("Invalid path '../escape.txt'"));
+    }
+
+    #[test]
+    fn test_script_abort() {
+        let mut vars = HashMap::new();
+        vars.insert("project_name".to_string(), "".to_string());
+
+        let err = run(
+            r#"if variables.project_name == "" { abort("project_name is required"); }"#,
+            &mut vars,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Script 'setup.rhai' aborted: project_name is required"
+        );
+    }
+
+    #[test]
+    fn test_script_missing_file() {
+        let mut vars = HashMap::new();
+        let result = run_scripts(
+            &MemoryFs::new(),
+            &["missing.rhai".to_string()],
+            Path::new(""),
+            &mut vars,
+        );
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,76 @@
+//! Project scaffolding from directory templates: the engine behind the `scaffold` CLI.
+//!
+//! [`Scaffolder`] renders a template in one call and returns a [`Report`]; it never
+//! prompts and never prints to stdout:
+//!
+//! ```no_run
+//! use project_scaffold::Scaffolder;
+//!
+//! let report = Scaffolder::new()
+//!     .templates_dir("/srv/templates")
+//!     .template("fastapi")
+//!     .answers([("project_name", "billing"), ("include_docker", "true")])
+//!     .output("services/billing")
+//!     .git(false)
+//!     .render()?;
+//! for file in &report.files {
+//!     println!("{}", file);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! The steps it runs are available on their own:
+//!
+//! - [`template::get_available_templates`], [`template::find_template`] and
+//!   [`template::load_template_config`] discover templates and parse their
+//!   [`template::TemplateConfig`]
+//! - [`generate::Generator`] resolves answers (defaults, computed variables,
+//!   scripts) and plans every output path; its [`generate::Generation`] writes them
+//! - [`prompt::Prompter`] asks for the answers that are still missing, through
+//!   [`generate::Generator::prompt_missing`]
+//! - [`copy::copy_template_recursive`] renders a template directory with a set of
+//!   variables
+//! - [`hooks::run_scripts`] and [`hooks::execute_hooks`] run Rhai scripts and
+//!   post-create commands
+//! - [`requirements::check_all`] checks the tools a template declares in
+//!   `[requirements]`
+//! - [`engine::Renderer`] renders text in the template's language: the built-in
+//!   [`variables::Syntax`], Jinja or Handlebars
+//! - [`vfs::Vfs`] is the filesystem templates are read from and rendered into: the
+//!   disk, memory, the embedded bundled templates ([`bundled::embedded`]) or a tar or
+//!   zip archive
+
+pub mod builtins;
+pub mod bundled;
+pub mod copy;
+pub mod engine;
+pub mod generate;
+pub mod hooks;
+pub mod prompt;
+pub mod requirements;
+mod scaffolder;
+pub mod template;
+pub mod variables;
+pub mod vfs;
+
+// Used by the scaffold binary; not part of the stable API
+#[doc(hidden)]
+pub mod archive;
+#[doc(hidden)]
+pub mod diff;
+#[doc(hidden)]
+pub mod git;
+#[doc(hidden)]
+pub mod global_config;
+#[doc(hidden)]
+pub mod matrix;
+#[doc(hidden)]
+pub mod output;
+#[doc(hidden)]
+pub mod paths;
+#[doc(hidden)]
+pub mod snapshot;
+#[doc(hidden)]
+pub mod version;
+
+pub use scaffolder::{GitSummary, Report, Scaffolder, ensure_new_directory};
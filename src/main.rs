@@ -1,25 +1,26 @@
-mod bundled;
 mod commands;
-mod copy;
-mod git;
-mod global_config;
-mod hooks;
-mod template;
-mod variables;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+use project_scaffold::matrix;
+use project_scaffold::output::OutputFormat;
+
 use commands::{
-    ConfigAction, add_template, create_project_interactive, handle_config_command, list_templates,
-    remove_template, show_template_info, validate_template,
+    ConfigAction, CreateOptions, ImportFormat, TemplateAction, TestOptions, add_template,
+    create_project_interactive, extract_template, handle_config_command, handle_template_command,
+    import_template, list_templates, pack_template, remove_template, run_doctor,
+    run_template_tests, show_template_info, validate_template,
 };
 
 #[derive(Parser)]
 #[command(name = "scaffold")]
 #[command(version, about = "A simple project template scaffolder", long_about = None)]
 struct Cli {
+    /// Output format: styled text, or JSON for scripts (list, info, validate, create, test)
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
     #[command(subcommand)]
     command: Commands,
 }
@@ -27,7 +28,11 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// List available templates
-    List,
+    List {
+        /// Only list templates with this tag (repeat to require several)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
     /// Create a new project from a template
     Create {
         /// Name of the template to use (interactive if not provided)
@@ -44,13 +49,59 @@ enum Commands {
         /// Preview what would be created without creating files
         #[arg(long)]
         dry_run: bool,
+        /// Initialize a git repository in the new project
+        #[arg(long, overrides_with = "no_git")]
+        git: bool,
+        /// Don't initialize a git repository, even if the template enables it
+        #[arg(long, overrides_with = "git")]
+        no_git: bool,
+        /// Leave placeholders without a value as-is instead of failing
+        #[arg(long)]
+        lenient: bool,
+        /// Create the project even if tools in the template's [requirements] are missing or outdated
+        #[arg(long)]
+        skip_requirements: bool,
     },
-    /// Add a new template from local path or git URL
+    /// Add a new template from local path, git URL or template archive
     Add {
+        /// Local path, git URL (use #path for subdirectory, e.g., https://github.com/org/repo.git#templates/api), or .tar.gz/.zip archive path, file:// or https:// URL
+        path: String,
+        /// Name to register the template under
+        name: String,
+        /// Expected SHA-256 of an archive; required for URLs (default for local archives: read from <archive>.sha256)
+        #[arg(long, value_name = "HEX")]
+        sha256: Option<String>,
+    },
+    /// Convert a cookiecutter or copier template (local path or git URL) into a new template
+    Import {
         /// Local path or git URL (use #path for subdirectory, e.g., https://github.com/org/repo.git#templates/api)
         path: String,
         /// Name to register the template under
         name: String,
+        /// Generator the template was written for
+        #[arg(long, value_enum)]
+        from: ImportFormat,
+    },
+    /// Create a template from an existing project, replacing literal strings with variables
+    Extract {
+        /// Project directory to turn into a template
+        project: String,
+        /// Name to register the template under
+        name: String,
+        /// Literal to replace, in literal=variable format (e.g., -r myapp=project_name -r "Jane Doe"=author)
+        #[arg(short, long = "replace", value_parser = parse_key_val)]
+        replace: Vec<(String, String)>,
+    },
+    /// Validate a template and pack it into a versioned .tar.gz or .zip archive
+    Pack {
+        /// Path to the template directory
+        path: String,
+        /// Directory to write the archive and its .sha256 file into
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+        /// Write a .zip instead of a .tar.gz
+        #[arg(long)]
+        zip: bool,
     },
     /// Show detailed information about a template
     Info {
@@ -66,6 +117,45 @@ enum Commands {
     Validate {
         /// Path to the template directory
         path: String,
+        /// Treat warnings as errors (exit non-zero if there are any)
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Run the test cases defined in a template's [[tests]] section
+    Test {
+        /// Name of the template, or path to a template directory
+        template: String,
+        /// Only run the test case with this name
+        #[arg(long)]
+        case: Option<String>,
+        /// Render the answers in this TOML file instead of the [[tests]] cases
+        #[arg(long, value_name = "FILE", conflicts_with = "case")]
+        answers: Option<PathBuf>,
+        /// Compare the rendered files with snapshots (default directory: snapshots/<template>)
+        #[arg(long, value_name = "DIR", num_args = 0..=1)]
+        snapshot: Option<Option<PathBuf>>,
+        /// Write the rendered files as the new snapshots instead of comparing
+        #[arg(long, requires = "snapshot")]
+        update: bool,
+        /// Render every combination of bool and choice variables instead of the [[tests]] cases
+        #[arg(long, conflicts_with_all = ["case", "answers", "snapshot"])]
+        matrix: bool,
+        /// Most combinations to render with --matrix; more are sampled at random
+        #[arg(long, requires = "matrix", default_value_t = matrix::DEFAULT_MAX_COMBINATIONS)]
+        max: usize,
+        /// Seed for --matrix sampling, to repeat a previous sample
+        #[arg(long, requires = "matrix")]
+        seed: Option<u64>,
+    },
+    /// Check that the tools templates declare in [requirements] are installed
+    Doctor {
+        /// Name of the template, or path to a template directory (default: every template with requirements)
+        template: Option<String>,
+    },
+    /// Author templates (e.g. `scaffold template new <name>`)
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
     },
     /// Manage global configuration (saved defaults for variables)
     Config {
@@ -89,18 +179,80 @@ fn main() -> Result<()> {
     let templates_dir = PathBuf::from(".templates");
 
     match cli.command {
-        Commands::List => list_templates(&templates_dir),
+        Commands::List { tags } => list_templates(&templates_dir, &tags, cli.format),
         Commands::Create {
             template,
             output,
             vars,
             yes,
             dry_run,
-        } => create_project_interactive(&templates_dir, template, output, vars, yes, dry_run),
-        Commands::Add { path, name } => add_template(&templates_dir, &path, &name),
-        Commands::Info { template } => show_template_info(&templates_dir, &template),
+            git,
+            no_git,
+            lenient,
+            skip_requirements,
+        } => {
+            let git = match (git, no_git) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            create_project_interactive(
+                &templates_dir,
+                CreateOptions {
+                    template,
+                    output,
+                    vars,
+                    use_defaults: yes,
+                    dry_run,
+                    git,
+                    lenient,
+                    skip_requirements,
+                    format: cli.format,
+                },
+            )
+        }
+        Commands::Add { path, name, sha256 } => {
+            add_template(&templates_dir, &path, &name, sha256.as_deref())
+        }
+        Commands::Extract {
+            project,
+            name,
+            replace,
+        } => extract_template(&templates_dir, &project, &name, &replace),
+        Commands::Import { path, name, from } => {
+            import_template(&templates_dir, &path, &name, from)
+        }
+        Commands::Doctor { template } => {
+            run_doctor(&templates_dir, template.as_deref(), cli.format)
+        }
+        Commands::Pack { path, output, zip } => pack_template(&path, &output, zip),
+        Commands::Info { template } => show_template_info(&templates_dir, &template, cli.format),
         Commands::Remove { template } => remove_template(&templates_dir, &template),
-        Commands::Validate { path } => validate_template(&path),
+        Commands::Validate { path, strict } => validate_template(&path, strict, cli.format),
+        Commands::Test {
+            template,
+            case,
+            answers,
+            snapshot,
+            update,
+            matrix,
+            max,
+            seed,
+        } => run_template_tests(
+            &templates_dir,
+            TestOptions {
+                template,
+                case,
+                answers,
+                snapshot,
+                update,
+                matrix,
+                max,
+                seed,
+                format: cli.format,
+            },
+        ),
+        Commands::Template { action } => handle_template_command(&templates_dir, action),
         Commands::Config { action } => handle_config_command(action),
     }
 }
@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::template::TemplateConfig;
+
+/// Combinations rendered by `scaffold test --matrix` unless `--max` says otherwise
+pub const DEFAULT_MAX_COMBINATIONS: usize = 256;
+
+/// A variable that varies across the matrix, with every value it can take
+#[derive(Debug, PartialEq, Eq)]
+pub struct Axis {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// Every bool and choice variable of a template, except those with a fixed answer
+pub fn axes(config: &TemplateConfig) -> Vec<Axis> {
+    config
+        .variables
+        .iter()
+        .filter(|var| !config.matrix.answers.contains_key(&var.name))
+        .filter_map(|var| {
+            let values = match var.var_type.as_deref() {
+                Some("bool") => vec!["false".to_string(), "true".to_string()],
+                Some("choice") if !var.choices.is_empty() => var.choices.clone(),
+                _ => return None,
+            };
+            Some(Axis {
+                name: var.name.clone(),
+                values,
+            })
+        })
+        .collect()
+}
+
+/// How many combinations the axes make (saturating for absurdly large templates)
+pub fn total(axes: &[Axis]) -> u128 {
+    axes.iter()
+        .fold(1u128, |n, axis| n.saturating_mul(axis.values.len() as u128))
+}
+
+/// The `index`th combination, counting with the last axis changing fastest
+pub fn combination(axes: &[Axis], mut index: u128) -> BTreeMap<String, String> {
+    let mut answers = BTreeMap::new();
+    for axis in axes.iter().rev() {
+        let len = axis.values.len() as u128;
+        answers.insert(
+            axis.name.clone(),
+            axis.values[(index % len) as usize].clone(),
+        );
+        index /= len;
+    }
+    answers
+}
+
+/// Indices of the combinations to render: all of them, or `max` picked at random
+/// (reproducibly for the same seed) when there are more
+pub fn select(total: u128, max: usize, seed: u64) -> Vec<u128> {
+    if total <= max as u128 {
+        return (0..total).collect();
+    }
+    let mut random = SplitMix64(seed);
+    let mut picked = BTreeSet::new();
+    while picked.len() < max {
+        let value = (u128::from(random.next()) << 64) | u128::from(random.next());
+        picked.insert(value % total);
+    }
+    picked.into_iter().collect()
+}
+
+/// Short label for a combination, e.g. "docker=true, db=postgres"
+pub fn label(answers: &BTreeMap<String, String>, axes: &[Axis]) -> String {
+    let parts: Vec<String> = axes
+        .iter()
+        .map(|axis| format!("{}={}", axis.name, answers[&axis.name]))
+        .collect();
+    if parts.is_empty() {
+        "defaults".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Small, fast PRNG; sampling only needs to be spread out and repeatable
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TemplateConfig {
+        toml::from_str(
+            r#"
+name = "Test"
+description = "Test"
+
+[[variables]]
+name = "project_name"
+description = "Name"
+
+[[variables]]
+name = "docker"
+description = "Docker?"
+type = "bool"
+
+[[variables]]
+name = "kind"
+description = "Kind"
+type = "choice"
+choices = ["bin", "lib", "both"]
+
+[[variables]]
+name = "license"
+description = "License"
+type = "choice"
+choices = ["MIT", "Apache-2.0"]
+
+[matrix]
+answers = { license = "MIT" }
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_axes_and_combinations() {
+        let axes = axes(&config());
+        let names: Vec<&str> = axes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["docker", "kind"]);
+        assert_eq!(total(&axes), 6);
+
+        let first = combination(&axes, 0);
+        assert_eq!(label(&first, &axes), "docker=false, kind=bin");
+        let last = combination(&axes, 5);
+        assert_eq!(label(&last, &axes), "docker=true, kind=both");
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(select(3, 10, 0), vec![0, 1, 2]);
+
+        let sample = select(1000, 10, 42);
+        assert_eq!(sample.len(), 10);
+        assert!(sample.iter().all(|&i| i < 1000));
+        assert_eq!(sample, select(1000, 10, 42));
+        assert_ne!(sample, select(1000, 10, 43));
+    }
+}
@@ -0,0 +1,26 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How commands report their results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Styled text for humans
+    #[default]
+    Text,
+    /// A single JSON document on stdout, for scripts and CI
+    Json,
+}
+
+impl OutputFormat {
+    pub fn is_text(self) -> bool {
+        self == OutputFormat::Text
+    }
+}
+
+/// Print a value as pretty JSON on stdout
+pub fn print_json(value: &impl Serialize) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).context("Failed to serialize output")?;
+    println!("{}", json);
+    Ok(())
+}
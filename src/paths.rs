@@ -0,0 +1,296 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobBuilder, GlobMatcher};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Path of `path` relative to `root`, using `/` separators on every platform.
+/// Returns None for the root itself or paths outside it.
+pub fn relative_path(path: &Path, root: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    if relative.as_os_str().is_empty() {
+        return None;
+    }
+    Some(
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+/// Lexically normalize a path, resolving `.` and `..` without touching the filesystem
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Device names Windows reserves regardless of extension (e.g. `con.txt`)
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Why a file or directory name can't be used on every OS, or None if it can
+pub fn invalid_name_reason(name: &str) -> Option<&'static str> {
+    if name.is_empty() {
+        return Some("the name is empty");
+    }
+    if name == "." || name == ".." {
+        return Some("'.' and '..' are not allowed");
+    }
+    if name.contains(['/', '\\']) {
+        return Some("it contains a path separator");
+    }
+    if name
+        .chars()
+        .any(|c| c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*'))
+    {
+        return Some("it contains a character that is not allowed in file names on Windows");
+    }
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Some("it ends with a dot or space, which Windows strips");
+    }
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    if RESERVED_NAMES.contains(&stem.to_ascii_uppercase().as_str()) {
+        return Some("it is a reserved device name on Windows");
+    }
+    None
+}
+
+/// Ensure a relative path (e.g. from a script) is made of valid names only
+pub fn check_relative_path(path: &str) -> Result<()> {
+    if path.starts_with(['/', '\\']) || Path::new(path).is_absolute() {
+        anyhow::bail!("Path '{}' must be relative to the project root", path);
+    }
+    for component in path.split(['/', '\\']) {
+        if let Some(reason) = invalid_name_reason(component) {
+            anyhow::bail!("Invalid path '{}': {}", path, reason);
+        }
+    }
+    Ok(())
+}
+
+/// Ensure `path` stays under `root` once `.` and `..` are resolved
+pub fn ensure_within(path: &Path, root: &Path) -> Result<()> {
+    if !normalize_path(path).starts_with(normalize_path(root)) {
+        anyhow::bail!(
+            "Refusing to write '{}': it is outside the output directory '{}'",
+            path.display(),
+            root.display()
+        );
+    }
+    Ok(())
+}
+
+/// Compile a template glob pattern.
+///
+/// Patterns containing `/` match the template-relative path (`*` stays within one
+/// directory, `**` crosses directories). Patterns without `/` match the file name
+/// at any depth, like .gitignore.
+fn compile_pattern(pattern: &str) -> Result<(GlobMatcher, bool)> {
+    let anchored = pattern.contains('/');
+    let glob = if anchored {
+        GlobBuilder::new(pattern.trim_start_matches('/'))
+            .literal_separator(true)
+            .build()
+    } else {
+        Glob::new(pattern)
+    }
+    .with_context(|| format!("Invalid glob pattern '{}'", pattern))?;
+    Ok((glob.compile_matcher(), anchored))
+}
+
+fn pattern_matches(matcher: &GlobMatcher, anchored: bool, relative: &str) -> bool {
+    if anchored {
+        matcher.is_match(relative)
+    } else {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        matcher.is_match(name)
+    }
+}
+
+/// A set of glob patterns matched against template-relative paths.
+/// A path matches if it or any of its parent directories matches a pattern.
+#[derive(Default)]
+pub struct PathPatterns {
+    patterns: Vec<(GlobMatcher, bool)>,
+}
+
+impl PathPatterns {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| compile_pattern(p.trim_end_matches('/')))
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    pub fn is_match(&self, relative: &str) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let prefixes = relative
+            .match_indices('/')
+            .map(|(i, _)| &relative[..i])
+            .chain(std::iter::once(relative));
+        prefixes.into_iter().any(|prefix| {
+            self.patterns
+                .iter()
+                .any(|(matcher, anchored)| pattern_matches(matcher, *anchored, prefix))
+        })
+    }
+}
+
+/// File mode overrides from the `[permissions]` table, e.g. `"bin/*" = "755"`
+#[derive(Default)]
+pub struct PermissionRules {
+    rules: Vec<(String, GlobMatcher, bool, u32)>,
+}
+
+impl PermissionRules {
+    /// Parse `pattern = "octal mode"` entries
+    pub fn new(permissions: &BTreeMap<String, String>) -> Result<Self> {
+        let mut rules = Vec::new();
+        for (pattern, mode) in permissions {
+            let (matcher, anchored) = compile_pattern(pattern)?;
+            let mode = u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+                .ok()
+                .filter(|m| *m <= 0o7777)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Invalid permission mode '{}' for '{}'. Use an octal mode such as \"755\".",
+                        mode,
+                        pattern
+                    )
+                })?;
+            rules.push((pattern.clone(), matcher, anchored, mode));
+        }
+        Ok(Self { rules })
+    }
+
+    /// Mode for a template-relative path; the longest matching pattern wins
+    pub fn mode_for(&self, relative: &str) -> Option<u32> {
+        self.rules
+            .iter()
+            .filter(|(_, matcher, anchored, _)| pattern_matches(matcher, *anchored, relative))
+            .max_by_key(|(pattern, ..)| pattern.len())
+            .map(|(.., mode)| *mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_path() {
+        let root = Path::new("templates/api");
+        assert_eq!(
+            relative_path(Path::new("templates/api/src/main.rs"), root),
+            Some("src/main.rs".to_string())
+        );
+        assert_eq!(relative_path(root, root), None);
+        assert_eq!(relative_path(Path::new("other/file"), root), None);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new("a/b/../c/./d")),
+            PathBuf::from("a/c/d")
+        );
+        assert_eq!(
+            normalize_path(Path::new("a/../../b")),
+            PathBuf::from("../b")
+        );
+    }
+
+    #[test]
+    fn test_invalid_name_reason() {
+        assert!(invalid_name_reason("my_app").is_none());
+        assert!(invalid_name_reason(".gitignore").is_none());
+        assert!(invalid_name_reason("console.log").is_none());
+        assert!(invalid_name_reason("").is_some());
+        assert!(invalid_name_reason("..").is_some());
+        assert!(invalid_name_reason("a/b").is_some());
+        assert!(invalid_name_reason("a\\b").is_some());
+        assert!(invalid_name_reason("what?").is_some());
+        assert!(invalid_name_reason("trailing.").is_some());
+        assert!(invalid_name_reason("CON").is_some());
+        assert!(invalid_name_reason("aux.rs").is_some());
+        assert!(invalid_name_reason("lpt1").is_some());
+    }
+
+    #[test]
+    fn test_check_relative_path() {
+        assert!(check_relative_path("docs/VERSION").is_ok());
+        assert!(check_relative_path("../outside").is_err());
+        assert!(check_relative_path("/etc/passwd").is_err());
+        assert!(check_relative_path("docs//VERSION").is_err());
+    }
+
+    #[test]
+    fn test_ensure_within() {
+        let root = Path::new("out");
+        assert!(ensure_within(Path::new("out/src/main.rs"), root).is_ok());
+        assert!(ensure_within(Path::new("out/../etc"), root).is_err());
+    }
+
+    #[test]
+    fn test_path_patterns() {
+        let patterns = PathPatterns::new(&[
+            ".github/**".to_string(),
+            "*.hbs".to_string(),
+            "charts/".to_string(),
+        ])
+        .unwrap();
+
+        assert!(patterns.is_match(".github/workflows/ci.yml"));
+        assert!(patterns.is_match("views/layout.hbs"));
+        assert!(patterns.is_match("charts/api/values.yaml"));
+        assert!(!patterns.is_match("src/main.rs"));
+        assert!(!patterns.is_match("docs/.github"));
+    }
+
+    #[test]
+    fn test_path_patterns_invalid() {
+        assert!(PathPatterns::new(&["src/[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_permission_rules() {
+        let mut permissions = BTreeMap::new();
+        permissions.insert("bin/*".to_string(), "755".to_string());
+        permissions.insert("bin/secret".to_string(), "700".to_string());
+        permissions.insert("*.sh".to_string(), "0o755".to_string());
+        let rules = PermissionRules::new(&permissions).unwrap();
+
+        assert_eq!(rules.mode_for("bin/run"), Some(0o755));
+        assert_eq!(rules.mode_for("bin/secret"), Some(0o700));
+        assert_eq!(rules.mode_for("bin/nested/run"), None);
+        assert_eq!(rules.mode_for("scripts/dev.sh"), Some(0o755));
+        assert_eq!(rules.mode_for("README.md"), None);
+    }
+
+    #[test]
+    fn test_permission_rules_invalid_mode() {
+        let mut permissions = BTreeMap::new();
+        permissions.insert("bin/*".to_string(), "rwx".to_string());
+        assert!(PermissionRules::new(&permissions).is_err());
+
+        permissions.insert("bin/*".to_string(), "999".to_string());
+        assert!(PermissionRules::new(&permissions).is_err());
+    }
+}
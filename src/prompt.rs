@@ -0,0 +1,410 @@
+use anyhow::{Context, Result};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, FuzzySelect, Input, MultiSelect, Password, Select};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::generate::split_multi;
+
+/// Separator between the selected values of a `multi` variable
+pub const MULTI_SEPARATOR: &str = ", ";
+
+/// Asks the user for answers; `create` goes through this instead of a terminal library
+/// so other front ends (and tests) can supply them
+///
+/// `name` identifies what is asked (a variable name, or `template`/`output` for the
+/// template and output directory), `message` is the text shown to the user.
+pub trait Prompter {
+    /// Free text; an empty answer returns `default` when there is one
+    fn string(&mut self, name: &str, message: &str, default: Option<&str>) -> Result<String>;
+
+    /// Yes or no
+    fn bool(&mut self, name: &str, message: &str, default: bool) -> Result<bool>;
+
+    /// One of `choices`, returned as its index
+    fn choice(
+        &mut self,
+        name: &str,
+        message: &str,
+        choices: &[String],
+        default: usize,
+    ) -> Result<usize>;
+
+    /// Any number of `choices`, returned as indices in order
+    fn multi(
+        &mut self,
+        name: &str,
+        message: &str,
+        choices: &[String],
+        defaults: &[bool],
+    ) -> Result<Vec<usize>>;
+
+    /// Text that isn't echoed, e.g. a token
+    fn secret(&mut self, name: &str, message: &str) -> Result<String>;
+}
+
+/// Interactive terminal prompts (arrow-key menus, fuzzy search, hidden input)
+#[derive(Default)]
+pub struct DialoguerPrompter {
+    theme: ColorfulTheme,
+}
+
+impl Prompter for DialoguerPrompter {
+    fn string(&mut self, _name: &str, message: &str, default: Option<&str>) -> Result<String> {
+        let input = Input::with_theme(&self.theme).with_prompt(message);
+        Ok(match default {
+            Some(default) => input.default(default.to_string()).interact_text()?,
+            None => input.allow_empty(true).interact_text()?,
+        })
+    }
+
+    fn bool(&mut self, _name: &str, message: &str, default: bool) -> Result<bool> {
+        Ok(Confirm::with_theme(&self.theme)
+            .with_prompt(message)
+            .default(default)
+            .interact()?)
+    }
+
+    fn choice(
+        &mut self,
+        name: &str,
+        message: &str,
+        choices: &[String],
+        default: usize,
+    ) -> Result<usize> {
+        // Template lists can be long; let the user type to filter them
+        if name == "template" {
+            return Ok(FuzzySelect::with_theme(&self.theme)
+                .with_prompt(message)
+                .items(choices)
+                .default(default)
+                .interact()?);
+        }
+        Ok(Select::with_theme(&self.theme)
+            .with_prompt(message)
+            .items(choices)
+            .default(default)
+            .interact()?)
+    }
+
+    fn multi(
+        &mut self,
+        _name: &str,
+        message: &str,
+        choices: &[String],
+        defaults: &[bool],
+    ) -> Result<Vec<usize>> {
+        Ok(MultiSelect::with_theme(&self.theme)
+            .with_prompt(message)
+            .items(choices)
+            .defaults(defaults)
+            .interact()?)
+    }
+
+    fn secret(&mut self, _name: &str, message: &str) -> Result<String> {
+        Ok(Password::with_theme(&self.theme)
+            .with_prompt(message)
+            .allow_empty_password(true)
+            .interact()?)
+    }
+}
+
+/// Plain line-by-line prompts for pipes and CI logs: each question is written on
+/// one line and each answer read from one line of input
+///
+/// Choices are listed with numbers and can be answered by number or value; `multi`
+/// takes a comma-separated list. An empty line (or end of input) takes the default.
+pub struct LinePrompter<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> LinePrompter<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self { input, output }
+    }
+
+    /// Ask once and return the trimmed answer, or None at end of input
+    fn ask(&mut self, message: &str, hint: &str) -> Result<Option<String>> {
+        if hint.is_empty() {
+            write!(self.output, "{}: ", message)?;
+        } else {
+            write!(self.output, "{} [{}]: ", message, hint)?;
+        }
+        self.output.flush()?;
+
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            writeln!(self.output)?;
+            return Ok(None);
+        }
+        Ok(Some(line.trim().to_string()))
+    }
+
+    fn list(&mut self, choices: &[String]) -> Result<()> {
+        for (i, choice) in choices.iter().enumerate() {
+            writeln!(self.output, "  {}) {}", i + 1, choice)?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead, W: Write> Prompter for LinePrompter<R, W> {
+    fn string(&mut self, _name: &str, message: &str, default: Option<&str>) -> Result<String> {
+        match self.ask(message, default.unwrap_or_default())? {
+            Some(answer) if !answer.is_empty() => Ok(answer),
+            _ => Ok(default.unwrap_or_default().to_string()),
+        }
+    }
+
+    fn bool(&mut self, name: &str, message: &str, default: bool) -> Result<bool> {
+        let hint = if default { "Y/n" } else { "y/N" };
+        loop {
+            let Some(answer) = self.ask(message, hint)? else {
+                return Ok(default);
+            };
+            match parse_bool(&answer) {
+                Some(value) => return Ok(value),
+                None if answer.is_empty() => return Ok(default),
+                None => writeln!(self.output, "Please answer y or n ({})", name)?,
+            }
+        }
+    }
+
+    fn choice(
+        &mut self,
+        name: &str,
+        message: &str,
+        choices: &[String],
+        default: usize,
+    ) -> Result<usize> {
+        self.list(choices)?;
+        let hint = (default + 1).to_string();
+        loop {
+            let Some(answer) = self.ask(message, &hint)? else {
+                return Ok(default);
+            };
+            if answer.is_empty() {
+                return Ok(default);
+            }
+            match find_choice(choices, &answer) {
+                Some(index) => return Ok(index),
+                None => writeln!(
+                    self.output,
+                    "'{}' is not one of the choices for {}",
+                    answer, name
+                )?,
+            }
+        }
+    }
+
+    fn multi(
+        &mut self,
+        name: &str,
+        message: &str,
+        choices: &[String],
+        defaults: &[bool],
+    ) -> Result<Vec<usize>> {
+        self.list(choices)?;
+        let default: Vec<usize> = (0..choices.len())
+            .filter(|&i| defaults.get(i).copied().unwrap_or(false))
+            .collect();
+        let hint = default
+            .iter()
+            .map(|i| (i + 1).to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        loop {
+            let Some(answer) = self.ask(message, &hint)? else {
+                return Ok(default);
+            };
+            if answer.is_empty() {
+                return Ok(default);
+            }
+            let picked: Option<Vec<usize>> = split_multi(&answer)
+                .into_iter()
+                .map(|part| find_choice(choices, part))
+                .collect();
+            match picked {
+                Some(mut picked) => {
+                    picked.sort_unstable();
+                    picked.dedup();
+                    return Ok(picked);
+                }
+                None => writeln!(
+                    self.output,
+                    "'{}' has values that are not choices for {}",
+                    answer, name
+                )?,
+            }
+        }
+    }
+
+    fn secret(&mut self, name: &str, message: &str) -> Result<String> {
+        // Without a terminal there is no echo to turn off
+        self.string(name, message, None)
+    }
+}
+
+/// Answers from a map by name, for tests and unattended runs
+///
+/// Unanswered prompts take their default; strings and secrets without one are an
+/// error. Every name asked is recorded in `asked`.
+#[derive(Debug, Default)]
+pub struct ScriptedPrompter {
+    answers: HashMap<String, String>,
+    pub asked: Vec<String>,
+}
+
+impl ScriptedPrompter {
+    pub fn new<K, V>(answers: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Self {
+            answers: answers
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+            asked: Vec::new(),
+        }
+    }
+
+    fn answer(&mut self, name: &str) -> Option<&str> {
+        self.asked.push(name.to_string());
+        self.answers.get(name).map(String::as_str)
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn string(&mut self, name: &str, _message: &str, default: Option<&str>) -> Result<String> {
+        self.answer(name)
+            .or(default)
+            .map(str::to_string)
+            .with_context(|| format!("No scripted answer for '{}'", name))
+    }
+
+    fn bool(&mut self, name: &str, _message: &str, default: bool) -> Result<bool> {
+        match self.answer(name) {
+            Some(answer) => parse_bool(answer)
+                .with_context(|| format!("Scripted answer for '{}' is not a bool", name)),
+            None => Ok(default),
+        }
+    }
+
+    fn choice(
+        &mut self,
+        name: &str,
+        _message: &str,
+        choices: &[String],
+        default: usize,
+    ) -> Result<usize> {
+        match self.answer(name) {
+            Some(answer) => find_choice(choices, answer)
+                .with_context(|| format!("Scripted answer for '{}' is not a choice", name)),
+            None => Ok(default),
+        }
+    }
+
+    fn multi(
+        &mut self,
+        name: &str,
+        _message: &str,
+        choices: &[String],
+        defaults: &[bool],
+    ) -> Result<Vec<usize>> {
+        match self.answer(name) {
+            Some(answer) => split_multi(answer)
+                .into_iter()
+                .map(|part| find_choice(choices, part))
+                .collect::<Option<Vec<_>>>()
+                .with_context(|| format!("Scripted answer for '{}' has unknown choices", name)),
+            None => Ok((0..choices.len())
+                .filter(|&i| defaults.get(i).copied().unwrap_or(false))
+                .collect()),
+        }
+    }
+
+    fn secret(&mut self, name: &str, _message: &str) -> Result<String> {
+        self.answer(name)
+            .map(str::to_string)
+            .with_context(|| format!("No scripted answer for '{}'", name))
+    }
+}
+
+fn parse_bool(answer: &str) -> Option<bool> {
+    match answer.to_lowercase().as_str() {
+        "y" | "yes" | "true" => Some(true),
+        "n" | "no" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Index of a choice given by value or by 1-based number
+fn find_choice(choices: &[String], answer: &str) -> Option<usize> {
+    choices.iter().position(|c| c == answer).or_else(|| {
+        answer
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=choices.len()).contains(n))
+            .map(|n| n - 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choices() -> Vec<String> {
+        vec!["bin".to_string(), "lib".to_string(), "both".to_string()]
+    }
+
+    #[test]
+    fn test_line_prompter() {
+        let input = "my-app\n\nmaybe\ny\n2\nboth\n1, 3\n";
+        let mut output = Vec::new();
+        let mut prompter = LinePrompter::new(input.as_bytes(), &mut output);
+
+        assert_eq!(prompter.string("name", "Name", None).unwrap(), "my-app");
+        assert_eq!(
+            prompter.string("author", "Author", Some("Me")).unwrap(),
+            "Me"
+        );
+        // "maybe" is rejected and asked again
+        assert!(prompter.bool("docker", "Docker?", false).unwrap());
+        assert_eq!(prompter.choice("kind", "Kind", &choices(), 0).unwrap(), 1);
+        assert_eq!(prompter.choice("kind", "Kind", &choices(), 0).unwrap(), 2);
+        assert_eq!(
+            prompter
+                .multi("features", "Features", &choices(), &[false; 3])
+                .unwrap(),
+            vec![0, 2]
+        );
+        // End of input takes the default
+        assert!(!prompter.bool("tests", "Tests?", false).unwrap());
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Author [Me]: "));
+        assert!(output.contains("Please answer y or n (docker)"));
+        assert!(output.contains("  2) lib\n"));
+    }
+
+    #[test]
+    fn test_scripted_prompter() {
+        let mut prompter = ScriptedPrompter::new([("kind", "lib"), ("features", "bin,both")]);
+        assert_eq!(prompter.choice("kind", "", &choices(), 0).unwrap(), 1);
+        assert_eq!(
+            prompter.multi("features", "", &choices(), &[]).unwrap(),
+            vec![0, 2]
+        );
+        assert!(prompter.bool("docker", "", true).unwrap());
+        assert_eq!(prompter.string("author", "", Some("Me")).unwrap(), "Me");
+        assert!(prompter.string("name", "", None).is_err());
+        assert!(prompter.secret("token", "").is_err());
+        assert_eq!(
+            prompter.asked,
+            vec!["kind", "features", "docker", "author", "name", "token"]
+        );
+    }
+}
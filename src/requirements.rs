@@ -0,0 +1,186 @@
+//! Checks for the tools a template declares in `[requirements]`
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use crate::template::Requirement;
+use crate::version::{Version, VersionReq};
+
+/// Finds the version in a tool's output when the requirement has no `regex`
+pub const DEFAULT_VERSION_PATTERN: &str = r"\d+(?:\.\d+)*";
+
+/// Exit codes shells use for a command that doesn't exist
+const NOT_FOUND_EXIT_CODES: &[i32] = &[127, 9009];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Met,
+    /// The version command couldn't be run
+    Missing,
+    /// Installed, but the version doesn't satisfy the requirement
+    Outdated,
+    /// The version command failed or printed no version
+    Unknown,
+}
+
+/// The result of checking one requirement on this machine
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub tool: String,
+    /// The version requirement, e.g. `>=0.4`; empty for any version
+    pub required: String,
+    /// The version found, if the tool ran and printed one
+    pub found: Option<String>,
+    pub status: Status,
+}
+
+impl Check {
+    /// Whether the requirement stops `create`: missing or outdated tools
+    pub fn is_blocking(&self) -> bool {
+        matches!(self.status, Status::Missing | Status::Outdated)
+    }
+
+    /// A one-line summary, e.g. `node 18.19.0 (need >=20)`
+    pub fn describe(&self) -> String {
+        let need = if self.required.is_empty() {
+            String::new()
+        } else {
+            format!(" (need {})", self.required)
+        };
+        match (&self.status, &self.found) {
+            (Status::Missing, _) => format!("{} not found{}", self.tool, need),
+            (Status::Unknown, _) => format!("{} version unknown{}", self.tool, need),
+            (_, Some(found)) => format!("{} {}{}", self.tool, found, need),
+            (_, None) => format!("{}{}", self.tool, need),
+        }
+    }
+}
+
+/// A requirement's version constraint and version pattern, or an error naming
+/// what's invalid
+pub fn compile(tool: &str, requirement: &Requirement) -> Result<(VersionReq, Regex)> {
+    let version = VersionReq::parse(&requirement.version).with_context(|| {
+        format!(
+            "Invalid version requirement '{}' for '{}'",
+            requirement.version, tool
+        )
+    })?;
+    let pattern = requirement
+        .regex
+        .as_deref()
+        .unwrap_or(DEFAULT_VERSION_PATTERN);
+    let regex = Regex::new(pattern)
+        .with_context(|| format!("Invalid version regex '{}' for '{}'", pattern, tool))?;
+    Ok((version, regex))
+}
+
+/// Run a requirement's version command and compare what it prints
+pub fn check(tool: &str, requirement: &Requirement) -> Result<Check> {
+    let (version_req, regex) = compile(tool, requirement)?;
+    let command = requirement.command(tool);
+    let output = if cfg!(target_os = "windows") {
+        Command::new("cmd").args(["/C", &command]).output()
+    } else {
+        Command::new("sh").arg("-c").arg(&command).output()
+    };
+
+    let mut check = Check {
+        tool: tool.to_string(),
+        required: requirement.version.trim().to_string(),
+        found: None,
+        status: Status::Missing,
+    };
+    let output = match output {
+        Ok(output) => output,
+        Err(_) => return Ok(check),
+    };
+    if output
+        .status
+        .code()
+        .is_some_and(|code| NOT_FOUND_EXIT_CODES.contains(&code))
+    {
+        return Ok(check);
+    }
+
+    // Some tools print their version to stderr
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    check.found = regex.captures(&text).and_then(|captures| {
+        captures
+            .get(1)
+            .or_else(|| captures.get(0))
+            .map(|m| m.as_str().to_string())
+    });
+    let version = check.found.as_deref().and_then(Version::parse);
+    check.status = match version {
+        Some(version) if version_req.matches(&version) => Status::Met,
+        Some(_) => Status::Outdated,
+        // Any version will do, so a working command is enough
+        None if output.status.success() && check.required.is_empty() => Status::Met,
+        None => Status::Unknown,
+    };
+    Ok(check)
+}
+
+/// Check every requirement, in name order
+pub fn check_all(requirements: &BTreeMap<String, Requirement>) -> Result<Vec<Check>> {
+    requirements
+        .iter()
+        .map(|(tool, requirement)| check(tool, requirement))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requirement(version: &str, command: &str) -> Requirement {
+        Requirement {
+            version: version.to_string(),
+            command: Some(command.to_string()),
+            regex: None,
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_check_statuses() {
+        let met = check("tool", &requirement(">=1.2", "echo tool 1.4.0")).unwrap();
+        assert_eq!(met.status, Status::Met);
+        assert_eq!(met.found.as_deref(), Some("1.4.0"));
+
+        let outdated = check("tool", &requirement(">=2", "echo tool v1.4.0")).unwrap();
+        assert_eq!(outdated.status, Status::Outdated);
+        assert_eq!(outdated.describe(), "tool 1.4.0 (need >=2)");
+
+        let missing = check(
+            "tool",
+            &requirement(">=1", "scaffold-no-such-tool --version"),
+        );
+        assert_eq!(missing.unwrap().status, Status::Missing);
+
+        let unknown = check("tool", &requirement(">=1", "echo no version here")).unwrap();
+        assert_eq!(unknown.status, Status::Unknown);
+        assert!(!unknown.is_blocking());
+
+        // Version on stderr, picked out by a capture group
+        let mut custom = requirement("<3", "echo 'build 7 / release 2.5' >&2");
+        custom.regex = Some(r"release (\S+)".to_string());
+        assert_eq!(check("tool", &custom).unwrap().status, Status::Met);
+    }
+
+    #[test]
+    fn test_compile_rejects_invalid_requirements() {
+        assert!(compile("uv", &requirement(">=soon", "uv --version")).is_err());
+        let mut bad_regex = requirement(">=1", "uv --version");
+        bad_regex.regex = Some("(".to_string());
+        assert!(compile("uv", &bad_regex).is_err());
+    }
+}
@@ -0,0 +1,298 @@
+use anyhow::{Context, Result, ensure};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::builtins::Builtins;
+use crate::generate::{Generator, git_settings};
+use crate::hooks::HookResult;
+use crate::template::{AvailableTemplate, TemplateSource, find_template, read_template_config};
+use crate::vfs::{DiskFs, Vfs};
+
+/// What a render wrote (or, for a dry run, would write)
+///
+/// The CLI prints this as JSON for `scaffold create --format json`.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Name of the template that was rendered
+    pub template: String,
+    pub output: PathBuf,
+    pub dry_run: bool,
+    /// Output-relative paths of every file and symlink
+    pub files: Vec<String>,
+    /// Template-relative paths left out by conditionals or scripts
+    pub skipped: Vec<String>,
+    /// Placeholders left without a value (only when lenient, or with `strict = false`)
+    pub unresolved: Vec<String>,
+    /// Post-create commands that ran, in order
+    pub hooks: Vec<HookResult>,
+    /// The repository that was initialized, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitSummary {
+    pub branch: String,
+    pub commit: bool,
+}
+
+/// Renders a template into a new directory without prompting or printing
+///
+/// ```no_run
+/// use project_scaffold::Scaffolder;
+///
+/// let report = Scaffolder::new()
+///     .template("fastapi")
+///     .answer("project_name", "billing")
+///     .output("services/billing")
+///     .render()?;
+/// println!("wrote {} files", report.files.len());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// Variables without an answer get their template default (bools default to
+/// `false`, choices to the first choice). Unresolved placeholders are an error
+/// unless [`Scaffolder::lenient`] is set or the template disables `strict`.
+///
+/// Templates can also be read from another filesystem with [`Scaffolder::source`]
+/// and rendered anywhere with [`Scaffolder::render_to`]:
+///
+/// ```
+/// use project_scaffold::Scaffolder;
+/// use project_scaffold::bundled::embedded;
+/// use project_scaffold::vfs::MemoryFs;
+///
+/// let mut out = MemoryFs::new();
+/// Scaffolder::new()
+///     .source(embedded())
+///     .template("fastapi")
+///     .output("billing")
+///     .render_to(&mut out)?;
+/// assert!(out.get("billing/README.md".as_ref()).is_some());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct Scaffolder {
+    templates_dir: PathBuf,
+    source: Option<Box<dyn Vfs>>,
+    template: Option<String>,
+    output: Option<PathBuf>,
+    answers: HashMap<String, String>,
+    defaults: HashMap<String, String>,
+    builtins: Option<Builtins>,
+    hooks: bool,
+    git: Option<bool>,
+    lenient: bool,
+    dry_run: bool,
+}
+
+impl Default for Scaffolder {
+    fn default() -> Self {
+        Self {
+            templates_dir: PathBuf::from(".templates"),
+            source: None,
+            template: None,
+            output: None,
+            answers: HashMap::new(),
+            defaults: HashMap::new(),
+            builtins: None,
+            hooks: true,
+            git: None,
+            lenient: false,
+            dry_run: false,
+        }
+    }
+}
+
+impl Scaffolder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directory of local templates searched before the bundled ones (default `.templates`)
+    pub fn templates_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.templates_dir = dir.into();
+        self
+    }
+
+    /// Read the template from `fs` instead of the disk; [`Scaffolder::template`] then
+    /// names its directory in `fs`
+    pub fn source(mut self, fs: impl Vfs + 'static) -> Self {
+        self.source = Some(Box::new(fs));
+        self
+    }
+
+    /// Template to render: a template name, or a path to a directory with template.toml
+    pub fn template(mut self, name_or_path: impl Into<String>) -> Self {
+        self.template = Some(name_or_path.into());
+        self
+    }
+
+    /// Directory to create; it must not exist yet
+    pub fn output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output = Some(path.into());
+        self
+    }
+
+    /// Answer one variable (also overrides built-ins and computed variables)
+    pub fn answer(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.answers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Answer several variables at once
+    pub fn answers<K, V>(mut self, answers: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.answers
+            .extend(answers.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Fallback defaults for variables whose template entry has none, like the
+    /// CLI's global config
+    pub fn defaults(mut self, defaults: HashMap<String, String>) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    /// Source of `_now.*`, `_uuid` and friends (default: system clock and OS randomness)
+    pub fn builtins(mut self, builtins: Builtins) -> Self {
+        self.builtins = Some(builtins);
+        self
+    }
+
+    /// Run the template's post-create commands (default true)
+    pub fn hooks(mut self, run: bool) -> Self {
+        self.hooks = run;
+        self
+    }
+
+    /// Force git initialization on or off, overriding the template's `[git] init`
+    pub fn git(mut self, init: bool) -> Self {
+        self.git = Some(init);
+        self
+    }
+
+    /// Leave unresolved placeholders as-is instead of failing
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Plan the output and run scripts, but write nothing
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Render the template and report what was written
+    pub fn render(self) -> Result<Report> {
+        self.render_with(&mut DiskFs, true)
+    }
+
+    /// Render the template into `out`, e.g. a [`MemoryFs`] or [`TarFs`]
+    ///
+    /// Post-create commands and git initialization need a real directory, so they
+    /// are skipped.
+    ///
+    /// [`MemoryFs`]: crate::vfs::MemoryFs
+    /// [`TarFs`]: crate::vfs::TarFs
+    pub fn render_to(self, out: &mut dyn Vfs) -> Result<Report> {
+        self.render_with(out, false)
+    }
+
+    fn render_with(self, out: &mut dyn Vfs, on_disk: bool) -> Result<Report> {
+        let name = self
+            .template
+            .context("No template given; call Scaffolder::template first")?;
+        let output = self
+            .output
+            .context("No output directory given; call Scaffolder::output first")?;
+        let template = match &self.source {
+            Some(fs) => source_template(fs.as_ref(), &name)?,
+            None => find_template(&self.templates_dir, &name)?,
+        };
+        if !self.dry_run {
+            ensure!(
+                !out.exists(&output),
+                "Output directory '{}' already exists",
+                output.display()
+            );
+        }
+
+        let source: &dyn Vfs = self.source.as_deref().unwrap_or(&DiskFs);
+        let builtins = self.builtins.unwrap_or_else(Builtins::system);
+        let mut generator = Generator::new(
+            &template.path,
+            &template.config,
+            &output,
+            self.answers,
+            builtins,
+        )?
+        .with_source(source)
+        .with_defaults(self.defaults);
+        generator.answer_defaults()?;
+        let mut generation = generator.finish()?;
+
+        let git = git_settings(&template.config, self.git).filter(|_| on_disk);
+        let mut report = Report {
+            template: template.dir_name.clone(),
+            output: output.clone(),
+            dry_run: self.dry_run,
+            files: generation.files(),
+            skipped: generation.plan.skipped.clone(),
+            unresolved: generation.unresolved.clone(),
+            hooks: Vec::new(),
+            git: git.map(|g| GitSummary {
+                branch: g.branch.clone(),
+                commit: g.commit,
+            }),
+        };
+        if self.dry_run {
+            return Ok(report);
+        }
+
+        generation.check_unresolved(self.lenient)?;
+        generation.write_to(out, None)?;
+        if !on_disk {
+            return Ok(report);
+        }
+        if self.hooks {
+            report.hooks = generation.run_post_create(&mut |_| {})?;
+        }
+        // Initialize git last so files produced by hooks (e.g. lockfiles) are committed
+        if let Some(git) = git {
+            generation.init_git(git)?;
+        }
+        Ok(report)
+    }
+}
+
+/// Load the template in directory `path` of `fs`
+fn source_template(fs: &dyn Vfs, path: &str) -> Result<AvailableTemplate> {
+    let path = Path::new(path);
+    let config = read_template_config(fs, &path.join("template.toml"))
+        .with_context(|| format!("No template at '{}'", path.display()))?;
+    Ok(AvailableTemplate {
+        dir_name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        config,
+        source: TemplateSource::Local,
+        path: path.to_path_buf(),
+    })
+}
+
+/// Fail if `path` exists, so a render never mixes into existing files
+pub fn ensure_new_directory(path: &Path) -> Result<()> {
+    ensure!(
+        !path.exists(),
+        "Output directory '{}' already exists",
+        path.display()
+    );
+    Ok(())
+}
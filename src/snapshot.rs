@@ -0,0 +1,363 @@
+use anyhow::{Context, Result, ensure};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::diff::unified_diff;
+use crate::paths::relative_path;
+
+/// Lists every path in the snapshot with its kind and mode
+const MANIFEST_FILE: &str = "manifest";
+/// Holds the contents of every file in the snapshot
+const FILES_DIR: &str = "files";
+
+/// One path in a rendered tree
+#[derive(Debug, PartialEq, Eq)]
+enum Entry {
+    Dir,
+    /// Unix permission bits are only known on Unix
+    File {
+        mode: Option<u32>,
+    },
+    Symlink(String),
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Dir => write!(f, "dir"),
+            Entry::File { mode: Some(mode) } => write!(f, "{:o}", mode),
+            Entry::File { mode: None } => write!(f, "file"),
+            Entry::Symlink(_) => write!(f, "link"),
+        }
+    }
+}
+
+/// A difference between a rendered tree and its snapshot
+#[derive(Debug)]
+pub struct Mismatch {
+    pub message: String,
+    /// Unified diff of the contents, for changed text files
+    pub diff: String,
+}
+
+impl Mismatch {
+    fn new(message: String) -> Self {
+        Self {
+            message,
+            diff: String::new(),
+        }
+    }
+}
+
+/// Golden output for one rendered project, kept in a directory that can be committed:
+/// `manifest` lists every path with its kind and mode (which git doesn't fully keep,
+/// and empty directories it doesn't keep at all) and `files/` holds the contents.
+pub struct Snapshot {
+    dir: PathBuf,
+}
+
+impl Snapshot {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// The snapshot called `name` in `root`, refusing names like `..` or `/tmp/x`
+    /// that would put it somewhere else
+    pub fn named(root: &Path, name: &str) -> Result<Self> {
+        let mut components = Path::new(name).components();
+        ensure!(
+            matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none(),
+            "Invalid snapshot name '{}': it must be a single directory name",
+            name
+        );
+        Ok(Self::new(root.join(name)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn exists(&self) -> bool {
+        self.dir.join(MANIFEST_FILE).is_file()
+    }
+
+    /// Replace the snapshot with the tree at `rendered`
+    ///
+    /// An existing directory is only replaced if it is a snapshot (has a manifest)
+    /// or empty, so a mistaken path never deletes anything else.
+    pub fn update(&self, rendered: &Path) -> Result<()> {
+        if self.dir.exists() {
+            let empty = fs::read_dir(&self.dir).is_ok_and(|mut entries| entries.next().is_none());
+            ensure!(
+                self.exists() || empty,
+                "Refusing to replace '{}': it has no {} file, so it isn't a snapshot",
+                self.dir.display(),
+                MANIFEST_FILE
+            );
+            fs::remove_dir_all(&self.dir).with_context(|| {
+                format!("Failed to remove old snapshot '{}'", self.dir.display())
+            })?;
+        }
+        let files_dir = self.dir.join(FILES_DIR);
+        fs::create_dir_all(&files_dir).with_context(|| {
+            format!(
+                "Failed to create snapshot directory '{}'",
+                files_dir.display()
+            )
+        })?;
+
+        let mut manifest = String::new();
+        for (path, entry) in read_tree(rendered)? {
+            match &entry {
+                Entry::Dir => fs::create_dir_all(files_dir.join(&path))?,
+                Entry::File { .. } => {
+                    fs::copy(rendered.join(&path), files_dir.join(&path))
+                        .with_context(|| format!("Failed to copy '{}' into snapshot", path))?;
+                }
+                Entry::Symlink(_) => {}
+            }
+            manifest.push_str(&manifest_line(&path, &entry));
+            manifest.push('\n');
+        }
+        fs::write(self.dir.join(MANIFEST_FILE), manifest).context("Failed to write manifest")
+    }
+
+    /// Compare the tree at `rendered` with the snapshot, path by path
+    pub fn compare(&self, rendered: &Path) -> Result<Vec<Mismatch>> {
+        let expected = self.read_manifest()?;
+        let actual = read_tree(rendered)?;
+        let files_dir = self.dir.join(FILES_DIR);
+        let mut mismatches = Vec::new();
+
+        for (path, expected_entry) in &expected {
+            let Some(actual_entry) = actual.get(path) else {
+                mismatches.push(Mismatch::new(format!("{} is missing", path)));
+                continue;
+            };
+            match (expected_entry, actual_entry) {
+                (Entry::Dir, Entry::Dir) => {}
+                (Entry::Symlink(old), Entry::Symlink(new)) => {
+                    if old != new {
+                        mismatches.push(Mismatch::new(format!(
+                            "{} links to {} instead of {}",
+                            path, new, old
+                        )));
+                    }
+                }
+                (Entry::File { mode: old_mode }, Entry::File { mode: new_mode }) => {
+                    match (old_mode, new_mode) {
+                        (Some(old), Some(new)) if old != new => mismatches.push(Mismatch::new(
+                            format!("{} mode changed from {:o} to {:o}", path, old, new),
+                        )),
+                        _ => {}
+                    }
+                    let old = fs::read(files_dir.join(path))
+                        .with_context(|| format!("Failed to read snapshot of '{}'", path))?;
+                    let new = fs::read(rendered.join(path))?;
+                    if old != new {
+                        mismatches.push(contents_mismatch(path, &old, &new));
+                    }
+                }
+                (old, new) => mismatches.push(Mismatch::new(format!(
+                    "{} changed from {} to {}",
+                    path,
+                    kind(old),
+                    kind(new)
+                ))),
+            }
+        }
+        for path in actual.keys().filter(|p| !expected.contains_key(*p)) {
+            mismatches.push(Mismatch::new(format!("{} is new", path)));
+        }
+
+        Ok(mismatches)
+    }
+
+    fn read_manifest(&self) -> Result<BTreeMap<String, Entry>> {
+        let path = self.dir.join(MANIFEST_FILE);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read snapshot '{}'", path.display()))?;
+        content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                parse_manifest_line(line)
+                    .with_context(|| format!("Invalid line in '{}': {}", path.display(), line))
+            })
+            .collect()
+    }
+}
+
+fn manifest_line(path: &str, entry: &Entry) -> String {
+    match entry {
+        Entry::Symlink(target) => format!("{} {} -> {}", entry, path, target),
+        _ => format!("{} {}", entry, path),
+    }
+}
+
+fn parse_manifest_line(line: &str) -> Option<(String, Entry)> {
+    let (kind, rest) = line.split_once(' ')?;
+    let entry = match kind {
+        "dir" => Entry::Dir,
+        "file" => Entry::File { mode: None },
+        "link" => {
+            let (path, target) = rest.rsplit_once(" -> ")?;
+            return Some((path.to_string(), Entry::Symlink(target.to_string())));
+        }
+        mode => Entry::File {
+            mode: Some(u32::from_str_radix(mode, 8).ok()?),
+        },
+    };
+    Some((rest.to_string(), entry))
+}
+
+fn kind(entry: &Entry) -> &'static str {
+    match entry {
+        Entry::Dir => "a directory",
+        Entry::File { .. } => "a file",
+        Entry::Symlink(_) => "a symlink",
+    }
+}
+
+fn contents_mismatch(path: &str, old: &[u8], new: &[u8]) -> Mismatch {
+    match (std::str::from_utf8(old), std::str::from_utf8(new)) {
+        (Ok(old), Ok(new)) => {
+            let diff = unified_diff(
+                old,
+                new,
+                &format!("snapshot/{}", path),
+                &format!("rendered/{}", path),
+            );
+            if diff.is_empty() {
+                return Mismatch::new(format!(
+                    "{} differs in line endings or the final newline",
+                    path
+                ));
+            }
+            Mismatch {
+                message: format!("{} differs", path),
+                diff,
+            }
+        }
+        _ => Mismatch::new(format!(
+            "{} differs (binary, {} -> {} bytes)",
+            path,
+            old.len(),
+            new.len()
+        )),
+    }
+}
+
+/// Every path under `root` (not following symlinks), keyed by `/`-separated relative path
+fn read_tree(root: &Path) -> Result<BTreeMap<String, Entry>> {
+    let mut tree = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read directory '{}'", dir.display()))?
+        {
+            let path = entry?.path();
+            let Some(relative) = relative_path(&path, root) else {
+                continue;
+            };
+            let metadata = fs::symlink_metadata(&path)?;
+            let entry = if metadata.file_type().is_symlink() {
+                Entry::Symlink(fs::read_link(&path)?.to_string_lossy().replace('\\', "/"))
+            } else if metadata.is_dir() {
+                pending.push(path);
+                Entry::Dir
+            } else {
+                Entry::File {
+                    mode: file_mode(&metadata),
+                }
+            };
+            tree.insert(relative, entry);
+        }
+    }
+    Ok(tree)
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_roundtrip_and_compare() {
+        let temp = TempDir::new().unwrap();
+        let rendered = temp.path().join("rendered");
+        fs::create_dir_all(rendered.join("src")).unwrap();
+        fs::create_dir_all(rendered.join("empty")).unwrap();
+        fs::write(rendered.join("README.md"), "# demo\n\nA project\n").unwrap();
+        fs::write(rendered.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        let snapshot = Snapshot::new(temp.path().join("snapshot"));
+        assert!(!snapshot.exists());
+        snapshot.update(&rendered).unwrap();
+        assert!(snapshot.exists());
+        assert!(snapshot.compare(&rendered).unwrap().is_empty());
+
+        fs::write(rendered.join("README.md"), "# other\n\nA project\n").unwrap();
+        fs::remove_file(rendered.join("src/main.rs")).unwrap();
+        fs::write(rendered.join("extra.txt"), "").unwrap();
+
+        let mismatches = snapshot.compare(&rendered).unwrap();
+        let messages: Vec<&str> = mismatches.iter().map(|m| m.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "README.md differs",
+                "src/main.rs is missing",
+                "extra.txt is new"
+            ]
+        );
+        assert!(mismatches[0].diff.contains("-# demo\n+# other\n"));
+    }
+
+    #[test]
+    fn test_snapshot_never_replaces_other_directories() {
+        let temp = TempDir::new().unwrap();
+        assert!(Snapshot::named(temp.path(), "default").is_ok());
+        for name in ["..", "../victim", "/tmp/victim", "a/b", ""] {
+            assert!(Snapshot::named(temp.path(), name).is_err(), "{}", name);
+        }
+
+        let rendered = temp.path().join("rendered");
+        fs::create_dir_all(&rendered).unwrap();
+        let victim = temp.path().join("victim");
+        fs::create_dir_all(&victim).unwrap();
+        fs::write(victim.join("important.txt"), "keep").unwrap();
+        assert!(Snapshot::new(victim.clone()).update(&rendered).is_err());
+        assert!(victim.join("important.txt").exists());
+    }
+
+    #[test]
+    fn test_parse_manifest_line() {
+        assert_eq!(
+            parse_manifest_line("755 bin/run"),
+            Some(("bin/run".to_string(), Entry::File { mode: Some(0o755) }))
+        );
+        assert_eq!(
+            parse_manifest_line("link docs/latest -> v2"),
+            Some(("docs/latest".to_string(), Entry::Symlink("v2".to_string())))
+        );
+        assert_eq!(
+            parse_manifest_line("dir src"),
+            Some(("src".to_string(), Entry::Dir))
+        );
+        assert_eq!(parse_manifest_line("rwx src"), None);
+    }
+}
@@ -1,8 +1,16 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::engine::{Engine, HandlebarsRenderer, JinjaRenderer, Renderer};
+use crate::paths::invalid_name_reason;
+use crate::variables::Syntax;
+use crate::version::Version;
 
 /// Indicates where a template comes from
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TemplateSource {
     /// User-added template in local .templates/ directory
     Local,
@@ -18,20 +26,244 @@ pub struct AvailableTemplate {
     pub path: PathBuf,
 }
 
+/// A directory among the templates that isn't a usable template
+#[derive(Debug)]
+pub struct SkippedTemplate {
+    pub dir_name: String,
+    /// Why it was skipped, e.g. "no template.toml found"
+    pub reason: String,
+}
+
+/// The templates found while scanning, and the directories skipped on the way
+#[derive(Default)]
+pub struct TemplateScan {
+    pub templates: Vec<AvailableTemplate>,
+    pub skipped: Vec<SkippedTemplate>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TemplateConfig {
     pub name: String,
     pub description: String,
+    /// Template version, used to name packed archives
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Oldest scaffold release that can render the template
+    #[serde(default)]
+    pub min_scaffold_version: Option<String>,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// SPDX license identifier, e.g. `"MIT"`
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    /// Keywords for `scaffold list --tag`
+    #[serde(default)]
+    pub tags: Vec<String>,
     #[serde(default)]
     pub variables: Vec<Variable>,
+    /// Variables derived from other answers, never prompted for
+    #[serde(default)]
+    pub computed: Vec<ComputedVariable>,
     #[serde(default)]
     pub conditionals: Vec<Conditional>,
     #[serde(default)]
     pub hooks: Option<HooksConfig>,
+    #[serde(default)]
+    pub git: Option<GitConfig>,
+    /// File mode overrides by glob pattern, e.g. `"bin/*" = "755"`
+    #[serde(default)]
+    pub permissions: BTreeMap<String, String>,
+    /// Glob patterns for files copied without variable substitution, e.g. `".github/**"`
+    #[serde(default)]
+    pub copy_without_render: Vec<String>,
+    /// Template language of files, names and template.toml values
+    #[serde(default)]
+    pub engine: Engine,
+    #[serde(default)]
+    pub render: RenderConfig,
+    /// Output names by template-relative path; targets may contain {{variables}}
+    #[serde(default)]
+    pub rename: BTreeMap<String, String>,
+    /// Test cases run by `scaffold test`
+    #[serde(default)]
+    pub tests: Vec<TemplateTest>,
+    /// Checks for every combination rendered by `scaffold test --matrix`
+    #[serde(default)]
+    pub matrix: MatrixConfig,
+    /// Tools the generated project needs, by name, checked before `create` renders
+    #[serde(default, deserialize_with = "deserialize_requirements")]
+    pub requirements: BTreeMap<String, Requirement>,
+}
+
+/// Placeholder delimiters, for templates whose files already use `{{ }}`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderConfig {
+    #[serde(default = "default_variable_start")]
+    pub variable_start: String,
+    #[serde(default = "default_variable_end")]
+    pub variable_end: String,
+    /// Delimiters around `raw`/`endraw` markers
+    #[serde(default = "default_block_start")]
+    pub block_start: String,
+    #[serde(default = "default_block_end")]
+    pub block_end: String,
+    /// Fail `create` when a placeholder has no value (override with --lenient)
+    #[serde(default = "default_true")]
+    pub strict: bool,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            variable_start: default_variable_start(),
+            variable_end: default_variable_end(),
+            block_start: default_block_start(),
+            block_end: default_block_end(),
+            strict: true,
+        }
+    }
+}
+
+impl TemplateConfig {
+    /// Fail with an upgrade hint if the template needs a newer scaffold
+    pub fn check_scaffold_version(&self) -> anyhow::Result<()> {
+        let Some(required) = &self.min_scaffold_version else {
+            return Ok(());
+        };
+        let required = Version::parse(required)
+            .ok_or_else(|| anyhow::anyhow!("Invalid min_scaffold_version '{}'", required))?;
+        let current = Version::current();
+        anyhow::ensure!(
+            current >= required,
+            "Template '{}' requires scaffold {} or newer, but this is {}. \
+             Upgrade with: cargo install project-scaffold",
+            self.name,
+            required,
+            current
+        );
+        Ok(())
+    }
+
+    /// Whether the template has `tag`, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// The renderer for the template's engine
+    pub fn renderer(&self) -> anyhow::Result<Arc<dyn Renderer>> {
+        let renderer: Arc<dyn Renderer> = match self.engine {
+            Engine::Simple => {
+                // project_name is always set, even when not declared
+                let declared = self
+                    .variables
+                    .iter()
+                    .map(|var| var.name.clone())
+                    .chain(self.computed.iter().map(|computed| computed.name.clone()))
+                    .chain(std::iter::once("project_name".to_string()));
+                return Ok(Arc::new(self.render.syntax()?.with_declared(declared)));
+            }
+            Engine::Jinja => Arc::new(JinjaRenderer::default()),
+            Engine::Handlebars => Arc::new(HandlebarsRenderer::default()),
+        };
+        anyhow::ensure!(
+            self.render.has_default_delimiters(),
+            "[render] delimiters only apply to the simple engine, not '{}'",
+            self.engine.name()
+        );
+        Ok(renderer)
+    }
+}
+
+impl RenderConfig {
+    fn has_default_delimiters(&self) -> bool {
+        self.variable_start == default_variable_start()
+            && self.variable_end == default_variable_end()
+            && self.block_start == default_block_start()
+            && self.block_end == default_block_end()
+    }
+
+    /// Compile the configured delimiters into a placeholder syntax
+    pub fn syntax(&self) -> anyhow::Result<Syntax> {
+        Syntax::new(
+            &self.variable_start,
+            &self.variable_end,
+            &self.block_start,
+            &self.block_end,
+        )
+    }
+}
+
+fn default_variable_start() -> String {
+    "{{".to_string()
+}
+
+fn default_variable_end() -> String {
+    "}}".to_string()
+}
+
+fn default_block_start() -> String {
+    "{%".to_string()
+}
+
+fn default_block_end() -> String {
+    "%}".to_string()
+}
+
+/// Git repository initialization for generated projects
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitConfig {
+    /// Initialize a repository (can be overridden with --git/--no-git)
+    #[serde(default = "default_true")]
+    pub init: bool,
+    /// Initial branch name
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    /// Make an initial commit of all rendered files
+    #[serde(default = "default_true")]
+    pub commit: bool,
+    /// Initial commit message (supports {{variables}})
+    #[serde(default = "default_commit_message")]
+    pub commit_message: String,
+    /// Commit author name (supports {{variables}}, e.g. "{{author}}")
+    #[serde(default)]
+    pub author_name: Option<String>,
+    /// Commit author email (supports {{variables}}, e.g. "{{author_email}}")
+    #[serde(default)]
+    pub author_email: Option<String>,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            init: true,
+            branch: default_branch(),
+            commit: true,
+            commit_message: default_commit_message(),
+            author_name: None,
+            author_email: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+fn default_commit_message() -> String {
+    "Initial commit".to_string()
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct HooksConfig {
+    /// Rhai scripts (paths relative to the template root) run in-process before rendering
+    #[serde(default)]
+    pub scripts: Vec<String>,
     #[serde(default)]
     pub post_create: Vec<String>,
 }
@@ -42,14 +274,22 @@ pub struct Variable {
     pub description: String,
     #[serde(default)]
     pub default: Option<String>,
-    /// Variable type: "string" (default), "choice", or "bool"
+    /// Variable type: "string" (default), "choice", "multi", "bool" or "secret"
     #[serde(rename = "type", default)]
     pub var_type: Option<String>,
-    /// Choices for "choice" type variables
+    /// Choices for "choice" and "multi" type variables
     #[serde(default)]
     pub choices: Vec<String>,
 }
 
+/// A variable whose value is an expression over other variables,
+/// e.g. `value = "{{ project_name | snake_case }}"`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComputedVariable {
+    pub name: String,
+    pub value: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Conditional {
     /// File or directory to include (mutually exclusive with exclude)
@@ -62,6 +302,136 @@ pub struct Conditional {
     pub when: String,
 }
 
+/// A `scaffold test` case: render with these answers, then check the output
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TemplateTest {
+    pub name: String,
+    /// Variable values; anything not answered uses its default
+    #[serde(default, deserialize_with = "deserialize_answers")]
+    pub answers: BTreeMap<String, String>,
+    /// Output directory name (defaults to the test name in kebab-case)
+    #[serde(default)]
+    pub output: Option<String>,
+    /// Output-relative paths that must exist
+    #[serde(default)]
+    pub exists: Vec<String>,
+    /// Output-relative paths that must not exist
+    #[serde(default)]
+    pub absent: Vec<String>,
+    /// Regexes each file's contents must match, by output-relative path
+    #[serde(default)]
+    pub matches: BTreeMap<String, Vec<String>>,
+    /// Run the template's post_create hooks after rendering
+    #[serde(default)]
+    pub hooks: bool,
+    /// Shell commands that must succeed in the generated project, e.g. `cargo check`
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+impl TemplateTest {
+    /// Fail unless `output`, if set, is a single plain directory name
+    pub fn check_output(&self) -> anyhow::Result<()> {
+        let Some(output) = &self.output else {
+            return Ok(());
+        };
+        match invalid_name_reason(output) {
+            Some(reason) => anyhow::bail!(
+                "Test '{}' has an invalid output '{}': {}",
+                self.name,
+                output,
+                reason
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `scaffold test --matrix` renders every combination of bool and choice variables
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MatrixConfig {
+    /// Answers shared by every combination; these variables don't vary
+    #[serde(default, deserialize_with = "deserialize_answers")]
+    pub answers: BTreeMap<String, String>,
+    /// Run the template's post_create hooks in every combination
+    #[serde(default)]
+    pub hooks: bool,
+    /// Shell commands that must succeed in every combination
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+/// A tool the template needs, e.g. `uv = ">=0.4"` or
+/// `node = { version = ">=20", command = "node --version" }`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Requirement {
+    /// Version requirement, e.g. `">=0.4"` or `">=1.2, <2"`; empty accepts any version
+    #[serde(default)]
+    pub version: String,
+    /// Command printing the tool's version (default: `<tool> --version`)
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Regex finding the version in the command's output, using its first capture
+    /// group if it has one (default: the first dotted number)
+    #[serde(default)]
+    pub regex: Option<String>,
+}
+
+impl Requirement {
+    /// The command that prints the tool's version
+    pub fn command(&self, tool: &str) -> String {
+        self.command
+            .clone()
+            .unwrap_or_else(|| format!("{} --version", tool))
+    }
+}
+
+/// Accept a bare version requirement as well as a table, so `uv = ">=0.4"` works
+/// like `uv = { version = ">=0.4" }`
+fn deserialize_requirements<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Requirement>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values = BTreeMap::<String, toml::Value>::deserialize(deserializer)?;
+    values
+        .into_iter()
+        .map(|(tool, value)| {
+            let requirement = match value {
+                toml::Value::String(version) => Requirement {
+                    version,
+                    ..Requirement::default()
+                },
+                other => other.try_into().map_err(|e| {
+                    serde::de::Error::custom(format!("requirement '{}': {}", tool, e))
+                })?,
+            };
+            Ok((tool, requirement))
+        })
+        .collect()
+}
+
+/// Accept any TOML scalar as an answer, so `docker = true` works like `docker = "true"`
+fn deserialize_answers<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values = BTreeMap::<String, toml::Value>::deserialize(deserializer)?;
+    Ok(values
+        .into_iter()
+        .map(|(name, value)| (name, answer_value(value)))
+        .collect())
+}
+
+/// An answer as the string templates see, e.g. `true` -> "true"
+pub fn answer_value(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,6 +471,120 @@ description = "Test description"
         assert!(config.hooks.is_none());
     }
 
+    #[test]
+    fn test_parse_template_with_scripts() {
+        let content = r#"
+name = "Test Template"
+description = "Test description"
+
+[hooks]
+scripts = ["setup.rhai"]
+"#;
+
+        let config: TemplateConfig = toml::from_str(content).unwrap();
+        let hooks = config.hooks.unwrap();
+        assert_eq!(hooks.scripts, vec!["setup.rhai"]);
+        assert!(hooks.post_create.is_empty());
+    }
+
+    #[test]
+    fn test_parse_template_with_git() {
+        let content = r#"
+name = "Test Template"
+description = "Test description"
+
+[git]
+branch = "trunk"
+author_name = "{{author}}"
+"#;
+
+        let config: TemplateConfig = toml::from_str(content).unwrap();
+        let git = config.git.unwrap();
+        assert!(git.init);
+        assert!(git.commit);
+        assert_eq!(git.branch, "trunk");
+        assert_eq!(git.commit_message, "Initial commit");
+        assert_eq!(git.author_name.as_deref(), Some("{{author}}"));
+        assert!(git.author_email.is_none());
+    }
+
+    #[test]
+    fn test_parse_template_with_render_delimiters() {
+        let content = r#"
+name = "Test Template"
+description = "Test description"
+
+[render]
+variable_start = "[["
+variable_end = "]]"
+"#;
+
+        let config: TemplateConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.render.variable_start, "[[");
+        assert_eq!(config.render.variable_end, "]]");
+        assert_eq!(config.render.block_start, "{%");
+        assert!(config.render.strict);
+        assert!(config.render.syntax().is_ok());
+    }
+
+    #[test]
+    fn test_parse_template_with_rename() {
+        let content = r#"
+name = "Test Template"
+description = "Test description"
+
+[rename]
+"gitignore.txt" = ".gitignore"
+"src/lib.rs" = "{{crate_name}}.rs"
+"#;
+
+        let config: TemplateConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.rename.len(), 2);
+        assert_eq!(config.rename["src/lib.rs"], "{{crate_name}}.rs");
+    }
+
+    #[test]
+    fn test_parse_template_with_computed() {
+        let content = r#"
+name = "Test Template"
+description = "Test description"
+
+[[computed]]
+name = "crate_name"
+value = "{{ project_name | snake_case }}"
+"#;
+
+        let config: TemplateConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.computed.len(), 1);
+        assert_eq!(config.computed[0].name, "crate_name");
+        assert_eq!(config.computed[0].value, "{{ project_name | snake_case }}");
+    }
+
+    #[test]
+    fn test_parse_template_with_tests() {
+        let content = r#"
+name = "Test Template"
+description = "Test description"
+
+[[tests]]
+name = "with docker"
+answers = { project_name = "demo", docker = true, workers = 4 }
+exists = ["Dockerfile"]
+matches = { "README.md" = ["^# demo"] }
+"#;
+
+        let config: TemplateConfig = toml::from_str(content).unwrap();
+        let test = &config.tests[0];
+        assert_eq!(test.name, "with docker");
+        assert_eq!(test.answers["docker"], "true");
+        assert_eq!(test.answers["workers"], "4");
+        assert_eq!(test.answers["project_name"], "demo");
+        assert_eq!(test.exists, vec!["Dockerfile"]);
+        assert_eq!(test.matches["README.md"], vec!["^# demo"]);
+        assert!(!test.hooks);
+        assert!(test.output.is_none());
+    }
+
     #[test]
     fn test_parse_nextjs_template() {
         let content = r#"name = "Next.js App"
@@ -134,4 +618,67 @@ post_create = [
         assert_eq!(hooks.post_create.len(), 1);
         assert_eq!(hooks.post_create[0], "pnpm install");
     }
+
+    #[test]
+    fn test_parse_template_metadata() {
+        let content = r#"
+name = "Test Template"
+description = "Test description"
+version = "1.4.0"
+min_scaffold_version = "0.1"
+authors = ["Jane Doe <jane@example.com>"]
+license = "MIT"
+homepage = "https://example.com/templates"
+tags = ["python", "API"]
+"#;
+
+        let config: TemplateConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.version.as_deref(), Some("1.4.0"));
+        assert_eq!(config.authors, vec!["Jane Doe <jane@example.com>"]);
+        assert_eq!(config.license.as_deref(), Some("MIT"));
+        assert!(config.has_tag("api"));
+        assert!(!config.has_tag("rust"));
+        assert!(config.check_scaffold_version().is_ok());
+    }
+
+    #[test]
+    fn test_template_requiring_newer_scaffold() {
+        let content = r#"
+name = "Future"
+description = "Test description"
+min_scaffold_version = "999.0"
+"#;
+
+        let config: TemplateConfig = toml::from_str(content).unwrap();
+        let error = config.check_scaffold_version().unwrap_err().to_string();
+        assert!(
+            error.contains("requires scaffold 999.0 or newer"),
+            "{}",
+            error
+        );
+        assert!(error.contains("cargo install project-scaffold"));
+    }
+
+    #[test]
+    fn test_parse_requirements() {
+        let content = r#"
+name = "Test Template"
+description = "Test description"
+
+[requirements]
+uv = ">=0.4"
+node = { version = ">=20", command = "node -v", regex = "v(\\S+)" }
+"#;
+
+        let config: TemplateConfig = toml::from_str(content).unwrap();
+        let uv = &config.requirements["uv"];
+        assert_eq!(uv.version, ">=0.4");
+        assert_eq!(uv.command("uv"), "uv --version");
+        let node = &config.requirements["node"];
+        assert_eq!(node.command("node"), "node -v");
+        assert_eq!(node.regex.as_deref(), Some("v(\\S+)"));
+
+        let invalid = "name = \"T\"\ndescription = \"T\"\n[requirements]\nuv = 4\n";
+        assert!(toml::from_str::<TemplateConfig>(invalid).is_err());
+    }
 }
@@ -1,97 +1,194 @@
 use anyhow::{Context, Result};
-use console::style;
 use std::collections::HashSet;
-use std::fs;
 use std::path::Path;
 
 use super::TemplateConfig;
-use super::config::{AvailableTemplate, TemplateSource};
+use super::config::{AvailableTemplate, SkippedTemplate, TemplateScan, TemplateSource};
 use crate::bundled::ensure_bundled_templates;
+use crate::vfs::{DiskFs, EntryKind, Vfs};
 
 /// Get all available templates from local .templates/ and bundled templates.
 /// Local templates take precedence over bundled templates with the same name.
-pub fn get_available_templates(local_dir: &Path) -> Result<Vec<AvailableTemplate>> {
-    let mut templates = Vec::new();
+pub fn get_available_templates(local_dir: &Path) -> Result<TemplateScan> {
+    let mut scan = TemplateScan::default();
     let mut seen_names = HashSet::new();
 
     // 1. First scan local .templates/ directory (highest priority)
     if local_dir.exists() {
-        for template in scan_directory(local_dir, TemplateSource::Local)? {
+        let local = scan_directory(local_dir, TemplateSource::Local)?;
+        for template in local.templates {
             seen_names.insert(template.dir_name.clone());
-            templates.push(template);
+            scan.templates.push(template);
         }
+        scan.skipped.extend(local.skipped);
     }
 
     // 2. Then scan bundled templates (skip if local exists with same name)
     if let Ok(bundled_dir) = ensure_bundled_templates() {
-        for template in scan_directory(&bundled_dir, TemplateSource::Bundled)? {
+        let bundled = scan_directory(&bundled_dir, TemplateSource::Bundled)?;
+        for template in bundled.templates {
             if !seen_names.contains(&template.dir_name) {
-                templates.push(template);
+                scan.templates.push(template);
             }
         }
+        scan.skipped.extend(bundled.skipped);
     }
 
-    Ok(templates)
+    Ok(scan)
 }
 
 /// Scan a directory for templates
-fn scan_directory(dir: &Path, source: TemplateSource) -> Result<Vec<AvailableTemplate>> {
+fn scan_directory(dir: &Path, source: TemplateSource) -> Result<TemplateScan> {
     if !dir.exists() {
-        return Ok(Vec::new());
+        return Ok(TemplateScan::default());
     }
+    scan_templates(&DiskFs, dir, source)
+}
 
-    let entries = fs::read_dir(dir).context("Failed to read templates directory")?;
-    let mut templates = Vec::new();
+/// Every template in a directory of `fs`, plus the directories skipped because
+/// they have no valid template.toml
+pub fn scan_templates(fs: &dyn Vfs, dir: &Path, source: TemplateSource) -> Result<TemplateScan> {
+    let entries = fs
+        .read_dir(dir)
+        .context("Failed to read templates directory")?;
+    let mut scan = TemplateScan::default();
 
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            let dir_name = entry.file_name().to_string_lossy().to_string();
+    for path in entries {
+        // Symlinked template directories count too
+        if fs.kind(&path)? == EntryKind::File {
+            continue;
+        }
+        let dir_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
 
-            // Skip hidden directories and version marker
-            if dir_name.starts_with('.') {
-                continue;
-            }
+        // Skip hidden directories and version marker
+        if dir_name.starts_with('.') {
+            continue;
+        }
 
-            let config_path = path.join("template.toml");
+        let config_path = path.join("template.toml");
 
-            if !config_path.exists() {
-                eprintln!(
-                    "{}: Skipping '{}' (no template.toml found)",
-                    style("Warning").yellow(),
-                    dir_name
-                );
-                continue;
-            }
+        if !fs.exists(&config_path) {
+            scan.skipped.push(SkippedTemplate {
+                dir_name,
+                reason: "no template.toml found".to_string(),
+            });
+            continue;
+        }
 
-            match load_template_config(&config_path) {
-                Ok(config) => {
-                    templates.push(AvailableTemplate {
-                        dir_name,
-                        config,
-                        source,
-                        path: path.clone(),
-                    });
-                }
-                Err(e) => {
-                    eprintln!(
-                        "{}: Skipping '{}': {}",
-                        style("Warning").yellow(),
-                        dir_name,
-                        e
-                    );
-                }
+        match read_template_config(fs, &config_path) {
+            Ok(config) => {
+                scan.templates.push(AvailableTemplate {
+                    dir_name,
+                    config,
+                    source,
+                    path,
+                });
+            }
+            Err(e) => {
+                scan.skipped.push(SkippedTemplate {
+                    dir_name,
+                    reason: e.to_string(),
+                });
             }
         }
     }
 
-    Ok(templates)
+    Ok(scan)
+}
+
+/// Find a template by name (local first, then bundled), or load one from a directory
+/// containing template.toml
+pub fn find_template(local_dir: &Path, name_or_path: &str) -> Result<AvailableTemplate> {
+    let path = Path::new(name_or_path);
+    if path.join("template.toml").is_file() {
+        let config = load_template_config(&path.join("template.toml"))?;
+        let dir_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| name_or_path.to_string());
+        return Ok(AvailableTemplate {
+            dir_name,
+            config,
+            source: TemplateSource::Local,
+            path: path.to_path_buf(),
+        });
+    }
+
+    let scan = get_available_templates(local_dir)?;
+    if let Some(skipped) = scan.skipped.iter().find(|s| s.dir_name == name_or_path) {
+        anyhow::bail!(
+            "Template '{}' can't be used: {}",
+            name_or_path,
+            skipped.reason
+        );
+    }
+    scan.templates
+        .into_iter()
+        .find(|t| t.dir_name == name_or_path)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Template '{}' not found. Run 'scaffold list' to see available templates.",
+                name_or_path
+            )
+        })
 }
 
 pub fn load_template_config(path: &Path) -> Result<TemplateConfig> {
-    let content = fs::read_to_string(path).context("Failed to read template config")?;
+    read_template_config(&DiskFs, path)
+}
+
+/// Parse a template.toml from any filesystem, e.g. the embedded bundled templates
+pub fn read_template_config(fs: &dyn Vfs, path: &Path) -> Result<TemplateConfig> {
+    let content = fs
+        .read_to_string(path)
+        .context("Failed to read template config")?;
     let config: TemplateConfig =
         toml::from_str(&content).context("Failed to parse template config")?;
+    config.check_scaffold_version()?;
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    #[test]
+    fn test_scan_templates_reports_skipped_directories() {
+        let mut fs = MemoryFs::new();
+        for dir in ["templates/api", "templates/empty", "templates/broken"] {
+            fs.create_dir_all(Path::new(dir)).unwrap();
+        }
+        let files = [
+            (
+                "templates/api/template.toml",
+                "name = \"API\"\ndescription = \"\"\n",
+            ),
+            ("templates/broken/template.toml", "name = "),
+        ];
+        for (path, contents) in files {
+            fs.write(Path::new(path), contents.as_bytes(), None)
+                .unwrap();
+        }
+
+        let scan = scan_templates(&fs, Path::new("templates"), TemplateSource::Local).unwrap();
+        let names: Vec<&str> = scan.templates.iter().map(|t| t.dir_name.as_str()).collect();
+        assert_eq!(names, vec!["api"]);
+        let mut skipped: Vec<(&str, &str)> = scan
+            .skipped
+            .iter()
+            .map(|s| (s.dir_name.as_str(), s.reason.as_str()))
+            .collect();
+        skipped.sort();
+        assert_eq!(
+            skipped,
+            vec![
+                ("broken", "Failed to parse template config"),
+                ("empty", "no template.toml found")
+            ]
+        );
+    }
+}
@@ -1,5 +1,11 @@
 mod config;
 mod loader;
 
-pub use config::{Conditional, TemplateConfig, TemplateSource};
-pub use loader::{get_available_templates, load_template_config};
+pub use config::{
+    AvailableTemplate, ComputedVariable, Conditional, GitConfig, Requirement, SkippedTemplate,
+    TemplateConfig, TemplateScan, TemplateSource, TemplateTest, Variable, answer_value,
+};
+pub use loader::{
+    find_template, get_available_templates, load_template_config, read_template_config,
+    scan_templates,
+};
@@ -1,27 +1,311 @@
+use anyhow::{Result, ensure};
 use regex::{Captures, Regex};
 use std::collections::{HashMap, HashSet};
 use std::sync::LazyLock;
 
-use crate::template::Conditional;
-
-/// Regex for matching {{variable}} or {{ variable }} patterns
-static VAR_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap());
+use crate::builtins::BUILTIN_PREFIX;
+use crate::engine::Renderer;
+use crate::template::{ComputedVariable, Conditional};
 
 /// Regex for condition evaluation: var == value, var == 'value', var == "value"
 static CONDITION_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r#"^\s*(\w+)\s*==\s*(?:["']([^"']+)["']|(true|false|\w+))\s*$"#).unwrap()
 });
 
-/// Substitute {{variable}} patterns in a string
-pub fn substitute_variables(template: &str, variables: &HashMap<String, String>) -> String {
-    VAR_PATTERN
-        .replace_all(template, |caps: &Captures| {
-            variables
-                .get(&caps[1])
-                .cloned()
-                .unwrap_or_else(|| caps[0].to_string())
+/// Placeholder syntax for a template, compiled from its `[render]` delimiters
+#[derive(Debug, Clone)]
+pub struct Syntax {
+    /// Matches, in order of precedence: a raw region (kept verbatim), an escaped
+    /// variable start (emitted literally) and a variable placeholder. Only built-in
+    /// names may be dotted or take a numeric argument (`_now.year`, `_random_hex(32)`),
+    /// so expressions like GitHub Actions' `${{ github.ref }}` stay literal. Names
+    /// may be followed by filters (`{{ name | snake_case }}`)
+    token_pattern: Regex,
+    /// Names filters may be applied to, besides built-ins; None allows any name
+    declared: Option<HashSet<String>>,
+}
+
+impl Syntax {
+    /// Build a syntax from variable delimiters (e.g. `{{`/`}}`) and block delimiters
+    /// used for `raw`/`endraw` markers (e.g. `{%`/`%}`)
+    pub fn new(
+        variable_start: &str,
+        variable_end: &str,
+        block_start: &str,
+        block_end: &str,
+    ) -> Result<Self> {
+        for (name, value) in [
+            ("variable_start", variable_start),
+            ("variable_end", variable_end),
+            ("block_start", block_start),
+            ("block_end", block_end),
+        ] {
+            ensure!(
+                !value.trim().is_empty(),
+                "Render delimiter '{}' cannot be empty",
+                name
+            );
+        }
+
+        let (vs, ve) = (regex::escape(variable_start), regex::escape(variable_end));
+        let (bs, be) = (regex::escape(block_start), regex::escape(block_end));
+        let token_pattern = Regex::new(&format!(
+            r"(?s){bs}\s*raw\s*{be}(.*?){bs}\s*endraw\s*{be}|\\({vs})|{vs}\s*(_\w+(?:\.\w+)*(?:\(\d+\))?|\w+)((?:\s*\|\s*\w+)*)\s*{ve}"
+        ))?;
+        Ok(Self {
+            token_pattern,
+            declared: None,
         })
-        .to_string()
+    }
+
+    /// Only treat filtered placeholders as ours when they name one of these
+    /// variables or a built-in, so filter expressions meant for other tools
+    /// (Jinja's or Helm's `{{ x | lower }}`) are left as literal text
+    pub fn with_declared(mut self, names: impl IntoIterator<Item = String>) -> Self {
+        self.declared = Some(names.into_iter().collect());
+        self
+    }
+
+    /// Whether a filtered placeholder belongs to another tool and is kept as is
+    fn is_foreign(&self, caps: &Captures) -> bool {
+        let (Some(declared), Some(name)) = (&self.declared, caps.get(3)) else {
+            return false;
+        };
+        let name = name.as_str();
+        filter_names(&caps[4]).next().is_some()
+            && !name.starts_with(BUILTIN_PREFIX)
+            && !declared.contains(name)
+    }
+
+    /// Substitute {{variable}} patterns in a string
+    pub fn substitute_variables(
+        &self,
+        template: &str,
+        variables: &HashMap<String, String>,
+    ) -> String {
+        self.token_pattern
+            .replace_all(template, |caps: &Captures| {
+                if let Some(raw) = caps.get(1) {
+                    raw.as_str().to_string()
+                } else if let Some(escaped) = caps.get(2) {
+                    escaped.as_str().to_string()
+                } else if self.is_foreign(caps) {
+                    caps[0].to_string()
+                } else {
+                    variables
+                        .get(&caps[3])
+                        .and_then(|value| apply_filters(value, filter_names(&caps[4])))
+                        .unwrap_or_else(|| caps[0].to_string())
+                }
+            })
+            .to_string()
+    }
+
+    /// Find the names of all {{variable}} placeholders in a string,
+    /// ignoring raw regions and escaped placeholders
+    ///
+    /// A placeholder with an unknown filter is returned as its whole expression
+    /// (e.g. `name | bogus`), which never matches a variable, so it is reported
+    /// as unresolved.
+    pub fn find_placeholders(&self, text: &str) -> Vec<String> {
+        self.token_pattern
+            .captures_iter(text)
+            .filter_map(|caps| self.placeholder_key(&caps))
+            .collect()
+    }
+
+    /// Like find_placeholders, paired with the 1-based line each placeholder starts on
+    pub fn find_placeholder_lines(&self, text: &str) -> Vec<(usize, String)> {
+        let mut line = 1;
+        let mut last = 0;
+        self.token_pattern
+            .captures_iter(text)
+            .filter_map(|caps| {
+                let key = self.placeholder_key(&caps)?;
+                let start = caps.get(0)?.start();
+                line += text[last..start].matches('\n').count();
+                last = start;
+                Some((line, key))
+            })
+            .collect()
+    }
+
+    /// Name a placeholder is looked up by, or None for raw regions, escapes and
+    /// filter expressions belonging to other tools
+    fn placeholder_key(&self, caps: &Captures) -> Option<String> {
+        let name = caps.get(3)?.as_str();
+        if self.is_foreign(caps) {
+            return None;
+        }
+        let unknown: Vec<&str> = filter_names(&caps[4])
+            .filter(|f| !FILTERS.contains(f))
+            .collect();
+        if unknown.is_empty() {
+            Some(name.to_string())
+        } else {
+            Some(format!("{} | {}", name, unknown.join(" | ")))
+        }
+    }
+}
+
+impl Default for Syntax {
+    fn default() -> Self {
+        Self::new("{{", "}}", "{%", "%}").expect("default delimiters are valid")
+    }
+}
+
+fn filter_names(chain: &str) -> impl Iterator<Item = &str> {
+    chain.split('|').map(str::trim).filter(|f| !f.is_empty())
+}
+
+/// Filters usable in placeholders, e.g. `{{ project_name | snake_case }}`
+pub const FILTERS: &[&str] = &[
+    "lower",
+    "upper",
+    "trim",
+    "snake_case",
+    "kebab_case",
+    "camel_case",
+    "pascal_case",
+    "title_case",
+];
+
+/// Apply filters left to right, or None if one is unknown
+fn apply_filters<'a>(value: &str, mut filters: impl Iterator<Item = &'a str>) -> Option<String> {
+    filters.try_fold(value.to_string(), |value, filter| {
+        apply_filter(filter, &value)
+    })
+}
+
+/// Apply a single filter, or None if the filter is unknown
+pub fn apply_filter(filter: &str, value: &str) -> Option<String> {
+    let words = || split_words(value).into_iter();
+    let filtered = match filter {
+        "lower" => value.to_lowercase(),
+        "upper" => value.to_uppercase(),
+        "trim" => value.trim().to_string(),
+        "snake_case" => words()
+            .map(|w| w.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("_"),
+        "kebab_case" => words()
+            .map(|w| w.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("-"),
+        "camel_case" => words()
+            .enumerate()
+            .map(|(i, w)| {
+                if i == 0 {
+                    w.to_lowercase()
+                } else {
+                    capitalize(&w)
+                }
+            })
+            .collect(),
+        "pascal_case" => words().map(|w| capitalize(&w)).collect(),
+        "title_case" => words()
+            .map(|w| capitalize(&w))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => return None,
+    };
+    Some(filtered)
+}
+
+/// Split a name into words at separators and case changes:
+/// `my-project`, `my_project`, `MyProject` and `my project` all give `my`, `project`
+fn split_words(value: &str) -> Vec<String> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            // Break at "myProject" and at the end of an acronym ("HTTPServer")
+            if !prev.is_uppercase() || next_is_lower {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Order computed variables so each comes after the computed variables it uses
+///
+/// Fails if they depend on each other in a cycle.
+pub fn computed_order<'a>(
+    computed: &'a [ComputedVariable],
+    engine: &dyn Renderer,
+) -> Result<Vec<&'a ComputedVariable>> {
+    fn visit<'a>(
+        index: usize,
+        computed: &'a [ComputedVariable],
+        engine: &dyn Renderer,
+        path: &mut Vec<usize>,
+        done: &mut HashSet<usize>,
+        order: &mut Vec<&'a ComputedVariable>,
+    ) -> Result<()> {
+        if done.contains(&index) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|&i| i == index) {
+            let cycle: Vec<&str> = path[start..]
+                .iter()
+                .chain([&index])
+                .map(|&i| computed[i].name.as_str())
+                .collect();
+            anyhow::bail!("Computed variables form a cycle: {}", cycle.join(" -> "));
+        }
+
+        path.push(index);
+        for name in engine.find_placeholders(&computed[index].value) {
+            if let Some(dep) = computed.iter().position(|c| c.name == name) {
+                visit(dep, computed, engine, path, done, order)?;
+            }
+        }
+        path.pop();
+
+        done.insert(index);
+        order.push(&computed[index]);
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    let mut done = HashSet::new();
+    for index in 0..computed.len() {
+        visit(
+            index,
+            computed,
+            engine,
+            &mut Vec::new(),
+            &mut done,
+            &mut order,
+        )?;
+    }
+    Ok(order)
 }
 
 /// Evaluate conditionals and return set of files/dirs to exclude
@@ -52,6 +336,14 @@ pub fn evaluate_conditionals(
     excluded
 }
 
+/// Name of the variable a condition tests, or None if the condition can't be parsed
+pub fn condition_variable(condition: &str) -> Option<&str> {
+    CONDITION_PATTERN
+        .captures(condition)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str())
+}
+
 /// Evaluate a simple condition like "var == true" or "var == 'value'"
 pub fn evaluate_condition(condition: &str, variables: &HashMap<String, String>) -> bool {
     if let Some(caps) = CONDITION_PATTERN.captures(condition) {
@@ -77,7 +369,7 @@ mod tests {
     fn test_substitute_variables_basic() {
         let mut vars = HashMap::new();
         vars.insert("name".to_string(), "test".to_string());
-        let result = substitute_variables("Hello {{name}}", &vars);
+        let result = Syntax::default().substitute_variables("Hello {{name}}", &vars);
         assert_eq!(result, "Hello test");
     }
 
@@ -85,7 +377,7 @@ mod tests {
     fn test_substitute_variables_spaced() {
         let mut vars = HashMap::new();
         vars.insert("name".to_string(), "test".to_string());
-        let result = substitute_variables("Hello {{ name }}", &vars);
+        let result = Syntax::default().substitute_variables("Hello {{ name }}", &vars);
         assert_eq!(result, "Hello test");
     }
 
@@ -94,17 +386,172 @@ mod tests {
         let mut vars = HashMap::new();
         vars.insert("name".to_string(), "myapp".to_string());
         vars.insert("author".to_string(), "John".to_string());
-        let result = substitute_variables("{{name}} by {{author}}", &vars);
+        let result = Syntax::default().substitute_variables("{{name}} by {{author}}", &vars);
         assert_eq!(result, "myapp by John");
     }
 
     #[test]
     fn test_substitute_variables_no_match() {
         let vars = HashMap::new();
-        let result = substitute_variables("Hello {{name}}", &vars);
+        let result = Syntax::default().substitute_variables("Hello {{name}}", &vars);
         assert_eq!(result, "Hello {{name}}");
     }
 
+    #[test]
+    fn test_substitute_variables_raw_region() {
+        let mut vars = HashMap::new();
+        vars.insert("name".to_string(), "test".to_string());
+        let result = Syntax::default().substitute_variables(
+            "{{name}}: {% raw %}${{ github.ref }} {{name}}{% endraw %}",
+            &vars,
+        );
+        assert_eq!(result, "test: ${{ github.ref }} {{name}}");
+    }
+
+    #[test]
+    fn test_substitute_variables_multiline_raw_region() {
+        let vars = HashMap::new();
+        let result =
+            Syntax::default().substitute_variables("{%raw%}\n{{ a }}\n{{ b }}\n{%endraw%}", &vars);
+        assert_eq!(result, "\n{{ a }}\n{{ b }}\n");
+    }
+
+    #[test]
+    fn test_substitute_variables_escaped() {
+        let mut vars = HashMap::new();
+        vars.insert("name".to_string(), "test".to_string());
+        let result = Syntax::default().substitute_variables("\\{{name}} is {{name}}", &vars);
+        assert_eq!(result, "{{name}} is test");
+    }
+
+    #[test]
+    fn test_find_placeholders() {
+        let found = Syntax::default()
+            .find_placeholders("{{ a }} \\{{ b }} {% raw %}{{ c }}{% endraw %} {{d}}");
+        assert_eq!(found, vec!["a", "d"]);
+    }
+
+    #[test]
+    fn test_substitute_with_filters() {
+        let mut vars = HashMap::new();
+        vars.insert("name".to_string(), "My HTTPServer-app".to_string());
+        let syntax = Syntax::default();
+        let render = |text: &str| syntax.substitute_variables(text, &vars);
+        assert_eq!(render("{{ name | snake_case }}"), "my_http_server_app");
+        assert_eq!(render("{{ name | kebab_case }}"), "my-http-server-app");
+        assert_eq!(render("{{ name | camel_case }}"), "myHttpServerApp");
+        assert_eq!(render("{{ name | pascal_case }}"), "MyHttpServerApp");
+        assert_eq!(render("{{ name | title_case }}"), "My Http Server App");
+        assert_eq!(render("{{name|snake_case|upper}}"), "MY_HTTP_SERVER_APP");
+        // Unknown filters leave the placeholder alone and are reported as unresolved
+        assert_eq!(render("{{ name | bogus }}"), "{{ name | bogus }}");
+        assert_eq!(
+            syntax.find_placeholders("{{ name | upper | bogus }}"),
+            vec!["name | bogus"]
+        );
+    }
+
+    #[test]
+    fn test_computed_order() {
+        let computed = |name: &str, value: &str| ComputedVariable {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        let syntax = Syntax::default();
+
+        let list = [
+            computed("module", "{{ crate_name | snake_case }}"),
+            computed("crate_name", "{{ project_name | kebab_case }}"),
+        ];
+        let order: Vec<&str> = computed_order(&list, &syntax)
+            .unwrap()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(order, vec!["crate_name", "module"]);
+
+        let cycle = [
+            computed("a", "{{ b }}"),
+            computed("b", "{{ c | upper }}"),
+            computed("c", "{{ a }}"),
+        ];
+        let err = computed_order(&cycle, &syntax).unwrap_err().to_string();
+        assert!(err.contains("a -> b -> c -> a"), "{}", err);
+    }
+
+    #[test]
+    fn test_find_builtin_placeholders() {
+        let found = Syntax::default()
+            .find_placeholders("{{ _now.year }} {{_random_hex(32)}} {{ _env.HOME }} {{ a. }}");
+        assert_eq!(found, vec!["_now.year", "_random_hex(32)", "_env.HOME"]);
+    }
+
+    #[test]
+    fn test_dotted_names_outside_builtins_are_literal() {
+        let text = "ref: ${{ github.ref }}\ntoken: ${{ secrets.TOKEN }}\nrun: {{ hash(2) }}\n";
+        let syntax = Syntax::default();
+        assert!(syntax.find_placeholders(text).is_empty());
+        assert_eq!(syntax.substitute_variables(text, &HashMap::new()), text);
+    }
+
+    #[test]
+    fn test_filters_on_undeclared_names_are_literal() {
+        let text = "{{ name | upper }} {{ x | lower }} {{ _output.name | snake_case }} {{ other }}";
+        let syntax = Syntax::default().with_declared(["name".to_string()]);
+        assert_eq!(
+            syntax.find_placeholders(text),
+            vec!["name", "_output.name", "other"]
+        );
+        let variables = HashMap::from([
+            ("name".to_string(), "demo".to_string()),
+            ("x".to_string(), "X".to_string()),
+            ("_output.name".to_string(), "My App".to_string()),
+        ]);
+        assert_eq!(
+            syntax.substitute_variables(text, &variables),
+            "DEMO {{ x | lower }} my_app {{ other }}"
+        );
+    }
+
+    #[test]
+    fn test_find_placeholder_lines() {
+        let found = Syntax::default()
+            .find_placeholder_lines("{{ a }}\n{% raw %}\n{{ b }}\n{% endraw %}\n\n{{c}} {{ d }}");
+        assert_eq!(
+            found,
+            vec![
+                (1, "a".to_string()),
+                (6, "c".to_string()),
+                (6, "d".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_delimiters() {
+        let syntax = Syntax::new("[[", "]]", "[%", "%]").unwrap();
+        let mut vars = HashMap::new();
+        vars.insert("name".to_string(), "test".to_string());
+
+        let result = syntax.substitute_variables(
+            "[[name]] {{ .Values.name }} \\[[name]] [% raw %][[ name ]][% endraw %] {% raw %}",
+            &vars,
+        );
+        assert_eq!(
+            result,
+            "test {{ .Values.name }} [[name]] [[ name ]] {% raw %}"
+        );
+        assert_eq!(
+            syntax.find_placeholders("[[ a ]] {{ b }} \\[[c]]"),
+            vec!["a"]
+        );
+    }
+
+    #[test]
+    fn test_custom_delimiters_empty() {
+        assert!(Syntax::new("", "]]", "{%", "%}").is_err());
+    }
+
     #[test]
     fn test_evaluate_condition_bool_true() {
         let mut vars = HashMap::new();
@@ -0,0 +1,168 @@
+//! Dotted version numbers (`1.4`, `0.12.3`, `v2.0.1-beta`), compared numerically,
+//! and requirements on them (`>=1.2, <2`)
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A version made of numeric components; missing components count as 0, so
+/// `1.4` equals `1.4.0`. Pre-release and build suffixes (`-beta`, `+abc`) are
+/// kept for display but ignored when comparing.
+#[derive(Debug, Clone)]
+pub struct Version {
+    parts: Vec<u64>,
+    text: String,
+}
+
+impl Version {
+    /// Parse a version, with or without a leading `v`
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let numbers = text.strip_prefix('v').unwrap_or(text);
+        let numbers = numbers.split(['-', '+']).next().unwrap_or_default();
+        let parts = numbers
+            .split('.')
+            .map(|part| {
+                if !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) {
+                    part.parse().ok()
+                } else {
+                    None
+                }
+            })
+            .collect::<Option<Vec<u64>>>()?;
+        Some(Self {
+            parts,
+            text: text.to_string(),
+        })
+    }
+
+    /// The version of scaffold itself
+    pub fn current() -> Self {
+        Self::parse(env!("CARGO_PKG_VERSION")).expect("the package version is valid")
+    }
+
+    fn part(&self, index: usize) -> u64 {
+        self.parts.get(index).copied().unwrap_or(0)
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (0..self.parts.len().max(other.parts.len()))
+            .map(|i| self.part(i).cmp(&other.part(i)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Version {}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Comparisons a version must all pass, e.g. `>=1.2, <2`
+///
+/// A bare version means `>=`, and an empty requirement or `*` accepts any version.
+#[derive(Debug, Clone)]
+pub struct VersionReq {
+    comparators: Vec<(Op, Version)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Eq,
+}
+
+impl VersionReq {
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut comparators = Vec::new();
+        for part in text.split(',').map(str::trim) {
+            if part.is_empty() || part == "*" {
+                continue;
+            }
+            // Two-character operators first, so `>=` isn't read as `>`
+            let (op, version) = [
+                (">=", Op::GreaterEq),
+                ("<=", Op::LessEq),
+                ("==", Op::Eq),
+                (">", Op::Greater),
+                ("<", Op::Less),
+                ("=", Op::Eq),
+            ]
+            .into_iter()
+            .find_map(|(prefix, op)| part.strip_prefix(prefix).map(|rest| (op, rest)))
+            .unwrap_or((Op::GreaterEq, part));
+            comparators.push((op, Version::parse(version)?));
+        }
+        Some(Self { comparators })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.comparators.iter().all(|(op, required)| match op {
+            Op::Greater => version > required,
+            Op::GreaterEq => version >= required,
+            Op::Less => version < required,
+            Op::LessEq => version <= required,
+            Op::Eq => version == required,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(v("1.4"), v("1.4.0"));
+        assert_eq!(v("v2.0.1-beta"), v("2.0.1"));
+        assert_eq!(v("v2.0.1-beta").to_string(), "v2.0.1-beta");
+        assert!(Version::parse("").is_none());
+        assert!(Version::parse("1..2").is_none());
+        assert!(Version::parse("latest").is_none());
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(v("0.10.0") > v("0.9.3"));
+        assert!(v("1.2") < v("1.2.1"));
+        assert!(v("20") >= v("20.0"));
+        assert!(Version::current() >= v("0.1"));
+    }
+
+    #[test]
+    fn test_requirements() {
+        let req = VersionReq::parse(">=1.2, <2").unwrap();
+        assert!(req.matches(&v("1.2")));
+        assert!(req.matches(&v("1.9.9")));
+        assert!(!req.matches(&v("2.0")));
+        assert!(!req.matches(&v("1.1.7")));
+        assert!(VersionReq::parse("20").unwrap().matches(&v("22.1.0")));
+        assert!(VersionReq::parse("=3.12").unwrap().matches(&v("3.12.0")));
+        assert!(VersionReq::parse("").unwrap().matches(&v("0.1")));
+        assert!(VersionReq::parse("*").unwrap().matches(&v("0.1")));
+        assert!(VersionReq::parse(">=latest").is_none());
+    }
+}
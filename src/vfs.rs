@@ -0,0 +1,718 @@
+use rust_embed::RustEmbed;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Seek, Write};
+use std::marker::PhantomData;
+use std::path::{Component, Path, PathBuf};
+
+/// What a path points at; symlinks are never followed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// A filesystem that templates are read from or rendered into
+///
+/// [`DiskFs`] is the real filesystem, [`MemoryFs`] keeps everything in a map (for
+/// previews and tests), [`EmbeddedFs`] reads files compiled into the binary, and
+/// [`TarFs`] and [`ZipFs`] write archives. Backends that can only be read or only be
+/// written return [`io::ErrorKind::Unsupported`] for the other half.
+pub trait Vfs {
+    /// The kind of entry at `path`, or [`io::ErrorKind::NotFound`]
+    fn kind(&self, path: &Path) -> io::Result<EntryKind>;
+
+    /// Full paths of a directory's entries, sorted
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Unix permission bits, where the backend has them
+    fn mode(&self, path: &Path) -> io::Result<Option<u32>>;
+
+    fn create_dir_all(&mut self, path: &Path) -> io::Result<()>;
+
+    /// Create or replace a file; `mode` None leaves the backend's default
+    fn write(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()>;
+
+    /// Create a symlink at `link`; `is_dir` says whether the target is a directory,
+    /// which Windows needs to know
+    fn symlink(&mut self, target: &Path, link: &Path, is_dir: bool) -> io::Result<()>;
+
+    fn exists(&self, path: &Path) -> bool {
+        self.kind(path).is_ok()
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// The archive path for `path` under `root`, or None for the root itself
+fn archive_path(root: &Path, path: &Path) -> io::Result<Option<PathBuf>> {
+    let relative = path.strip_prefix(root).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "'{}' is outside the archive root '{}'",
+                path.display(),
+                root.display()
+            ),
+        )
+    })?;
+    Ok((!relative.as_os_str().is_empty()).then(|| relative.to_path_buf()))
+}
+
+fn unsupported(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, what.to_string())
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("'{}' does not exist", path.display()),
+    )
+}
+
+/// The real filesystem
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DiskFs;
+
+impl Vfs for DiskFs {
+    fn kind(&self, path: &Path) -> io::Result<EntryKind> {
+        let file_type = fs::symlink_metadata(path)?.file_type();
+        Ok(if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_dir() {
+            EntryKind::Dir
+        } else {
+            EntryKind::File
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    #[cfg(unix)]
+    fn mode(&self, path: &Path) -> io::Result<Option<u32>> {
+        use std::os::unix::fs::PermissionsExt;
+        Ok(Some(
+            fs::symlink_metadata(path)?.permissions().mode() & 0o7777,
+        ))
+    }
+
+    #[cfg(not(unix))]
+    fn mode(&self, path: &Path) -> io::Result<Option<u32>> {
+        // Unix modes have no equivalent here
+        fs::symlink_metadata(path).map(|_| None)
+    }
+
+    fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn write(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+        fs::write(path, contents)?;
+        match mode {
+            Some(mode) => set_mode(path, mode),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(unix)]
+    fn symlink(&mut self, target: &Path, link: &Path, _is_dir: bool) -> io::Result<()> {
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    #[cfg(windows)]
+    fn symlink(&mut self, target: &Path, link: &Path, is_dir: bool) -> io::Result<()> {
+        if is_dir {
+            std::os::windows::fs::symlink_dir(target, link)
+        } else {
+            std::os::windows::fs::symlink_file(target, link)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+/// An entry of a [`MemoryFs`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryEntry {
+    File { contents: Vec<u8>, mode: u32 },
+    Dir,
+    Symlink(PathBuf),
+}
+
+/// A filesystem held in memory, e.g. to render a template for a preview or a diff
+///
+/// Paths are used as given (`out/src/main.rs` and `./out/src/main.rs` are different
+/// entries). Like the real filesystem, a file can only be written into a directory
+/// that exists; paths with a single component need no parent.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFs {
+    entries: BTreeMap<PathBuf, MemoryEntry>,
+}
+
+impl MemoryFs {
+    /// Mode of files written without one
+    pub const DEFAULT_MODE: u32 = 0o644;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&MemoryEntry> {
+        self.entries.get(path)
+    }
+
+    /// Every entry, sorted by path
+    pub fn entries(&self) -> impl Iterator<Item = (&Path, &MemoryEntry)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_path(), entry))
+    }
+
+    /// Paths of every file and symlink, sorted
+    pub fn files(&self) -> Vec<&Path> {
+        self.entries()
+            .filter(|(_, entry)| !matches!(entry, MemoryEntry::Dir))
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    fn entry(&self, path: &Path) -> io::Result<&MemoryEntry> {
+        self.entries.get(path).ok_or_else(|| not_found(path))
+    }
+
+    /// Fail unless `path`'s parent is a directory (or `path` has no parent)
+    fn check_parent(&self, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => match self.entries.get(parent) {
+                Some(MemoryEntry::Dir) => Ok(()),
+                Some(_) => Err(io::Error::new(
+                    io::ErrorKind::NotADirectory,
+                    format!("'{}' is not a directory", parent.display()),
+                )),
+                None => Err(not_found(parent)),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn insert(&mut self, path: &Path, entry: MemoryEntry) -> io::Result<()> {
+        self.check_parent(path)?;
+        if matches!(self.entries.get(path), Some(MemoryEntry::Dir)) {
+            return Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!("'{}' is a directory", path.display()),
+            ));
+        }
+        self.entries.insert(path.to_path_buf(), entry);
+        Ok(())
+    }
+}
+
+impl Vfs for MemoryFs {
+    fn kind(&self, path: &Path) -> io::Result<EntryKind> {
+        Ok(match self.entry(path)? {
+            MemoryEntry::File { .. } => EntryKind::File,
+            MemoryEntry::Dir => EntryKind::Dir,
+            MemoryEntry::Symlink(_) => EntryKind::Symlink,
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        match self.entry(path)? {
+            MemoryEntry::Dir => Ok(self
+                .entries
+                .keys()
+                .filter(|p| p.parent() == Some(path))
+                .cloned()
+                .collect()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("'{}' is not a directory", path.display()),
+            )),
+        }
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.entry(path)? {
+            MemoryEntry::File { contents, .. } => Ok(contents.clone()),
+            MemoryEntry::Dir => Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!("'{}' is a directory", path.display()),
+            )),
+            // Links aren't followed; templates only read them with read_link
+            MemoryEntry::Symlink(_) => Err(unsupported("reading through a symlink")),
+        }
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.entry(path)? {
+            MemoryEntry::Symlink(target) => Ok(target.clone()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("'{}' is not a symlink", path.display()),
+            )),
+        }
+    }
+
+    fn mode(&self, path: &Path) -> io::Result<Option<u32>> {
+        Ok(match self.entry(path)? {
+            MemoryEntry::File { mode, .. } => Some(*mode),
+            _ => None,
+        })
+    }
+
+    fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+        for dir in path.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            if dir.as_os_str().is_empty() || dir.parent().is_none() {
+                continue;
+            }
+            match self.entries.get(dir) {
+                Some(MemoryEntry::Dir) => {}
+                Some(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("'{}' exists and is not a directory", dir.display()),
+                    ));
+                }
+                None => {
+                    self.entries.insert(dir.to_path_buf(), MemoryEntry::Dir);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+        let mode = mode.unwrap_or(Self::DEFAULT_MODE);
+        self.insert(
+            path,
+            MemoryEntry::File {
+                contents: contents.to_vec(),
+                mode,
+            },
+        )
+    }
+
+    fn symlink(&mut self, target: &Path, link: &Path, _is_dir: bool) -> io::Result<()> {
+        if self.entries.contains_key(link) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' already exists", link.display()),
+            ));
+        }
+        self.insert(link, MemoryEntry::Symlink(target.to_path_buf()))
+    }
+}
+
+/// Read-only view of files embedded with rust-embed, such as the bundled templates
+///
+/// Paths are relative to the embedded folder (`fastapi/template.toml`); directories
+/// exist implicitly wherever a file is nested. Embedded files carry no modes or
+/// symlinks.
+pub struct EmbeddedFs<E> {
+    embed: PhantomData<E>,
+}
+
+impl<E> Default for EmbeddedFs<E> {
+    fn default() -> Self {
+        Self { embed: PhantomData }
+    }
+}
+
+impl<E: RustEmbed> EmbeddedFs<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The embedded key for a path: components joined with '/', without "./"
+    fn key(path: &Path) -> String {
+        path.components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+impl<E: RustEmbed> Vfs for EmbeddedFs<E> {
+    fn kind(&self, path: &Path) -> io::Result<EntryKind> {
+        let key = Self::key(path);
+        if E::get(&key).is_some() {
+            return Ok(EntryKind::File);
+        }
+        let prefix = format!("{}/", key);
+        if key.is_empty() || E::iter().any(|file| file.starts_with(&prefix)) {
+            Ok(EntryKind::Dir)
+        } else {
+            Err(not_found(path))
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let key = Self::key(path);
+        let prefix = if key.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", key)
+        };
+        let mut names: Vec<String> = E::iter()
+            .filter_map(|file| {
+                file.strip_prefix(prefix.as_str())
+                    .and_then(|rest| rest.split('/').next())
+                    .map(str::to_string)
+            })
+            .collect();
+        if names.is_empty() && !key.is_empty() {
+            return Err(not_found(path));
+        }
+        names.sort();
+        names.dedup();
+        Ok(names.into_iter().map(|name| path.join(name)).collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        E::get(&Self::key(path))
+            .map(|file| file.data.into_owned())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(unsupported("embedded files have no symlinks"))
+    }
+
+    fn mode(&self, path: &Path) -> io::Result<Option<u32>> {
+        self.kind(path).map(|_| None)
+    }
+
+    fn create_dir_all(&mut self, _path: &Path) -> io::Result<()> {
+        Err(unsupported("embedded files are read-only"))
+    }
+
+    fn write(&mut self, _path: &Path, _contents: &[u8], _mode: Option<u32>) -> io::Result<()> {
+        Err(unsupported("embedded files are read-only"))
+    }
+
+    fn symlink(&mut self, _target: &Path, _link: &Path, _is_dir: bool) -> io::Result<()> {
+        Err(unsupported("embedded files are read-only"))
+    }
+}
+
+/// Write-only filesystem that appends everything to a tar archive
+///
+/// Entries are stored relative to `root` (the directory a render writes into), so the
+/// archive unpacks to the project's contents. Wrap the writer in a compressor for
+/// `.tar.gz`.
+pub struct TarFs<W: Write> {
+    builder: tar::Builder<W>,
+    root: PathBuf,
+}
+
+impl<W: Write> TarFs<W> {
+    pub fn new(writer: W, root: impl Into<PathBuf>) -> Self {
+        let mut builder = tar::Builder::new(writer);
+        builder.mode(tar::HeaderMode::Deterministic);
+        Self {
+            builder,
+            root: root.into(),
+        }
+    }
+
+    /// Write the archive trailer and return the writer
+    pub fn finish(self) -> io::Result<W> {
+        self.builder.into_inner()
+    }
+
+    fn entry_path(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        archive_path(&self.root, path)
+    }
+
+    fn header(kind: tar::EntryType, mode: u32, size: u64) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(kind);
+        header.set_mode(mode);
+        header.set_size(size);
+        header.set_mtime(0);
+        header
+    }
+}
+
+impl<W: Write> Vfs for TarFs<W> {
+    fn kind(&self, _path: &Path) -> io::Result<EntryKind> {
+        Err(unsupported("tar archives are write-only"))
+    }
+
+    fn read_dir(&self, _path: &Path) -> io::Result<Vec<PathBuf>> {
+        Err(unsupported("tar archives are write-only"))
+    }
+
+    fn read(&self, _path: &Path) -> io::Result<Vec<u8>> {
+        Err(unsupported("tar archives are write-only"))
+    }
+
+    fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(unsupported("tar archives are write-only"))
+    }
+
+    fn mode(&self, _path: &Path) -> io::Result<Option<u32>> {
+        Err(unsupported("tar archives are write-only"))
+    }
+
+    fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+        match self.entry_path(path)? {
+            Some(relative) => {
+                let mut header = Self::header(tar::EntryType::Directory, 0o755, 0);
+                self.builder.append_data(&mut header, relative, io::empty())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn write(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+        let relative = self
+            .entry_path(path)?
+            .ok_or_else(|| unsupported("writing a file at the archive root"))?;
+        let mode = mode.unwrap_or(MemoryFs::DEFAULT_MODE);
+        let mut header = Self::header(tar::EntryType::Regular, mode, contents.len() as u64);
+        self.builder.append_data(&mut header, relative, contents)
+    }
+
+    fn symlink(&mut self, target: &Path, link: &Path, _is_dir: bool) -> io::Result<()> {
+        let relative = self
+            .entry_path(link)?
+            .ok_or_else(|| unsupported("a symlink at the archive root"))?;
+        let mut header = Self::header(tar::EntryType::Symlink, 0o777, 0);
+        self.builder.append_link(&mut header, relative, target)
+    }
+}
+
+/// Writes a zip archive; write-only
+///
+/// Like [`TarFs`], entries are stored relative to `root`. Timestamps are fixed so
+/// the same files always produce the same archive.
+pub struct ZipFs<W: Write + Seek> {
+    writer: zip::ZipWriter<W>,
+    root: PathBuf,
+}
+
+impl<W: Write + Seek> ZipFs<W> {
+    pub fn new(writer: W, root: impl Into<PathBuf>) -> Self {
+        Self {
+            writer: zip::ZipWriter::new(writer),
+            root: root.into(),
+        }
+    }
+
+    /// Write the central directory and return the writer
+    pub fn finish(self) -> io::Result<W> {
+        Ok(self.writer.finish()?)
+    }
+
+    /// The entry name for `path`, always `/`-separated, or None for the root itself
+    fn entry_name(&self, path: &Path) -> io::Result<Option<String>> {
+        Ok(archive_path(&self.root, path)?.map(|relative| {
+            relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        }))
+    }
+
+    fn options(mode: u32) -> zip::write::SimpleFileOptions {
+        zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .last_modified_time(zip::DateTime::default())
+            .unix_permissions(mode)
+    }
+}
+
+impl<W: Write + Seek> Vfs for ZipFs<W> {
+    fn kind(&self, _path: &Path) -> io::Result<EntryKind> {
+        Err(unsupported("zip archives are write-only"))
+    }
+
+    fn read_dir(&self, _path: &Path) -> io::Result<Vec<PathBuf>> {
+        Err(unsupported("zip archives are write-only"))
+    }
+
+    fn read(&self, _path: &Path) -> io::Result<Vec<u8>> {
+        Err(unsupported("zip archives are write-only"))
+    }
+
+    fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(unsupported("zip archives are write-only"))
+    }
+
+    fn mode(&self, _path: &Path) -> io::Result<Option<u32>> {
+        Err(unsupported("zip archives are write-only"))
+    }
+
+    fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+        match self.entry_name(path)? {
+            Some(name) => Ok(self.writer.add_directory(name, Self::options(0o755))?),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+        let name = self
+            .entry_name(path)?
+            .ok_or_else(|| unsupported("writing a file at the archive root"))?;
+        let mode = mode.unwrap_or(MemoryFs::DEFAULT_MODE);
+        self.writer.start_file(name, Self::options(mode))?;
+        self.writer.write_all(contents)
+    }
+
+    fn symlink(&mut self, target: &Path, link: &Path, _is_dir: bool) -> io::Result<()> {
+        let name = self
+            .entry_name(link)?
+            .ok_or_else(|| unsupported("a symlink at the archive root"))?;
+        let target = target.to_string_lossy().replace('\\', "/");
+        Ok(self
+            .writer
+            .add_symlink(name, target, Self::options(0o777))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(RustEmbed)]
+    #[folder = "bundled-templates/"]
+    struct Bundled;
+
+    #[test]
+    fn test_memory_fs() {
+        let mut fs = MemoryFs::new();
+        assert!(fs.write(Path::new("out/a.txt"), b"a", None).is_err());
+
+        fs.create_dir_all(Path::new("out/src")).unwrap();
+        fs.write(Path::new("out/src/main.rs"), b"fn main() {}", Some(0o755))
+            .unwrap();
+        fs.write(Path::new("out/README.md"), b"# hi", None).unwrap();
+        fs.symlink(Path::new("README.md"), Path::new("out/docs"), false)
+            .unwrap();
+
+        assert_eq!(fs.kind(Path::new("out")).unwrap(), EntryKind::Dir);
+        assert_eq!(
+            fs.read_dir(Path::new("out")).unwrap(),
+            vec![
+                PathBuf::from("out/README.md"),
+                PathBuf::from("out/docs"),
+                PathBuf::from("out/src"),
+            ]
+        );
+        assert_eq!(
+            fs.read_to_string(Path::new("out/README.md")).unwrap(),
+            "# hi"
+        );
+        assert_eq!(fs.mode(Path::new("out/src/main.rs")).unwrap(), Some(0o755));
+        assert_eq!(
+            fs.read_link(Path::new("out/docs")).unwrap(),
+            PathBuf::from("README.md")
+        );
+        assert_eq!(fs.files().len(), 3);
+        assert!(!fs.exists(Path::new("out/missing")));
+    }
+
+    #[test]
+    fn test_embedded_fs() {
+        let fs = EmbeddedFs::<Bundled>::new();
+        assert_eq!(fs.kind(Path::new("fastapi")).unwrap(), EntryKind::Dir);
+        assert_eq!(
+            fs.kind(Path::new("./fastapi/template.toml")).unwrap(),
+            EntryKind::File
+        );
+        assert!(
+            fs.read_dir(Path::new(""))
+                .unwrap()
+                .contains(&PathBuf::from("fastapi"))
+        );
+        assert!(
+            fs.read_to_string(Path::new("fastapi/template.toml"))
+                .unwrap()
+                .contains("name")
+        );
+        assert!(fs.read_dir(Path::new("missing")).is_err());
+    }
+
+    #[test]
+    fn test_tar_fs() {
+        let mut fs = TarFs::new(Vec::new(), "out");
+        fs.create_dir_all(Path::new("out/bin")).unwrap();
+        fs.write(Path::new("out/bin/run"), b"#!/bin/sh\n", Some(0o755))
+            .unwrap();
+        assert!(fs.write(Path::new("elsewhere"), b"", None).is_err());
+        let bytes = fs.finish().unwrap();
+
+        let mut archive = tar::Archive::new(bytes.as_slice());
+        let entries: Vec<(String, u32)> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().to_string();
+                (path, entry.header().mode().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![("bin".to_string(), 0o755), ("bin/run".to_string(), 0o755)]
+        );
+    }
+
+    #[test]
+    fn test_zip_fs() {
+        let mut fs = ZipFs::new(io::Cursor::new(Vec::new()), "out");
+        fs.create_dir_all(Path::new("out/bin")).unwrap();
+        fs.write(Path::new("out/bin/run"), b"#!/bin/sh\n", Some(0o755))
+            .unwrap();
+        assert!(fs.write(Path::new("elsewhere"), b"", None).is_err());
+        let bytes = fs.finish().unwrap().into_inner();
+
+        let mut archive = zip::ZipArchive::new(io::Cursor::new(bytes)).unwrap();
+        let mut entries = Vec::new();
+        for index in 0..archive.len() {
+            let entry = archive.by_index(index).unwrap();
+            entries.push((entry.name().to_string(), entry.unix_mode().unwrap() & 0o777));
+        }
+        assert_eq!(
+            entries,
+            vec![("bin/".to_string(), 0o755), ("bin/run".to_string(), 0o755)]
+        );
+    }
+}
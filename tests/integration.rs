@@ -14,6 +14,22 @@ fn test_list_command() {
     assert!(stdout.contains("Available templates") || stdout.contains("No templates found"));
 }
 
+#[test]
+fn test_list_warns_about_skipped_directories() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir_all(temp.path().join(".templates/notes")).unwrap();
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .arg("list")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Skipping 'notes': no template.toml found")
+    );
+}
+
 #[test]
 fn test_info_command_nonexistent() {
     let output = scaffold_cmd()
@@ -445,19 +461,2360 @@ default = "my-project"
     assert!(content.contains("# my_app"));
 }
 
-/// Helper function to recursively copy a directory
-fn copy_dir_all(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<()> {
-    fs::create_dir_all(dst)?;
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let ty = entry.file_type()?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-        if ty.is_dir() {
-            copy_dir_all(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path)?;
-        }
-    }
-    Ok(())
+#[test]
+fn test_create_with_script_hook() {
+    let temp = TempDir::new().unwrap();
+    let templates_dir = temp.path().join(".templates");
+    let template_dir = templates_dir.join("test");
+    fs::create_dir_all(template_dir.join("src")).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+default = "my-project"
+
+[hooks]
+scripts = ["setup.rhai"]
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("setup.rhai"),
+        r#"
+let name = variables.project_name;
+name.replace("-", "_");
+variables.module_name = name;
+exclude("src/cli.rs");
+add_file("VERSION", "0.1.0\n");
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("README.md"), "# {{module_name}}").unwrap();
+    fs::write(template_dir.join("src/cli.rs"), "fn cli() {}").unwrap();
+    fs::write(template_dir.join("src/lib.rs"), "").unwrap();
+
+    let output_dir = temp.path().join("output");
+
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "create",
+            "test",
+            "-o",
+            output_dir.to_str().unwrap(),
+            "-v",
+            "project_name=awesome-app",
+            "-y",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let readme = fs::read_to_string(output_dir.join("README.md")).unwrap();
+    assert_eq!(readme, "# awesome_app");
+    assert!(!output_dir.join("src/cli.rs").exists());
+    assert!(output_dir.join("src/lib.rs").exists());
+    assert!(!output_dir.join("setup.rhai").exists());
+    assert_eq!(
+        fs::read_to_string(output_dir.join("VERSION")).unwrap(),
+        "0.1.0\n"
+    );
+}
+
+#[test]
+fn test_create_with_aborting_script() {
+    let temp = TempDir::new().unwrap();
+    let templates_dir = temp.path().join(".templates");
+    let template_dir = templates_dir.join("test");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[hooks]
+scripts = ["check.rhai"]
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("check.rhai"),
+        r#"abort("unsupported configuration");"#,
+    )
+    .unwrap();
+
+    let output_dir = temp.path().join("output");
+
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "test", "-o", output_dir.to_str().unwrap(), "-y"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unsupported configuration"));
+    assert!(!output_dir.exists());
+}
+
+#[test]
+fn test_create_with_git_init() {
+    let temp = TempDir::new().unwrap();
+    let templates_dir = temp.path().join(".templates");
+    let template_dir = templates_dir.join("test");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "author"
+description = "Author name"
+default = "Jane Doe"
+
+[git]
+branch = "trunk"
+commit_message = "Scaffold {{project_name}}"
+author_name = "{{author}}"
+author_email = "jane@example.com"
+
+[hooks]
+post_create = ["echo lock > generated.lock"]
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("README.md"), "# {{project_name}}").unwrap();
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "test", "-o", output_dir.to_str().unwrap(), "-y"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let log = Command::new("git")
+        .args(["log", "--format=%an <%ae> %s", "trunk"])
+        .current_dir(&output_dir)
+        .output()
+        .unwrap();
+    let log = String::from_utf8_lossy(&log.stdout);
+    assert_eq!(log.trim(), "Jane Doe <jane@example.com> Scaffold output");
+
+    // Files written by hooks are part of the initial commit
+    let files = Command::new("git")
+        .args(["ls-files"])
+        .current_dir(&output_dir)
+        .output()
+        .unwrap();
+    let files = String::from_utf8_lossy(&files.stdout);
+    assert!(files.lines().any(|f| f == "generated.lock"));
+
+    // --no-git overrides the template
+    let output_dir = temp.path().join("output-no-git");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "create",
+            "test",
+            "-o",
+            output_dir.to_str().unwrap(),
+            "-y",
+            "--no-git",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!output_dir.join(".git").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_preserves_modes_and_symlinks() {
+    use std::os::unix::fs::{PermissionsExt, symlink};
+
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join("test-template");
+    fs::create_dir_all(template_dir.join("scripts")).unwrap();
+    fs::create_dir_all(template_dir.join("bin")).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[permissions]
+"bin/*" = "750"
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("scripts/dev.sh"), "#!/bin/sh\necho dev\n").unwrap();
+    fs::set_permissions(
+        template_dir.join("scripts/dev.sh"),
+        fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+    fs::write(template_dir.join("bin/run"), "#!/bin/sh\n").unwrap();
+    fs::write(template_dir.join("README.md"), "# {{project_name}}").unwrap();
+    symlink("README.md", template_dir.join("README")).unwrap();
+
+    // `scaffold add` keeps modes and symlinks
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["add", template_dir.to_str().unwrap(), "test"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let added = temp.path().join(".templates/test");
+    let mode = fs::metadata(added.join("scripts/dev.sh"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o755);
+    assert!(added.join("README").is_symlink());
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "test", "-o", output_dir.to_str().unwrap(), "-y"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let mode = fs::metadata(output_dir.join("scripts/dev.sh"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o755);
+    let mode = fs::metadata(output_dir.join("bin/run"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o750);
+
+    let link = output_dir.join("README");
+    assert!(link.is_symlink());
+    assert_eq!(
+        fs::read_link(&link).unwrap(),
+        std::path::Path::new("README.md")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_rejects_symlink_outside_template() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+"#,
+    )
+    .unwrap();
+    std::os::unix::fs::symlink("../../secret", template_dir.join("leak")).unwrap();
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "test", "-o", output_dir.to_str().unwrap(), "-y"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("points outside the template"));
+}
+
+#[test]
+fn test_create_copy_without_render_and_raw_regions() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join("test-template");
+    fs::create_dir_all(template_dir.join(".github/workflows")).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+copy_without_render = [".github/**", "*.hbs"]
+"#,
+    )
+    .unwrap();
+    let workflow = "name: {{project_name}}\nref: ${{ github.ref }}\n";
+    fs::write(template_dir.join(".github/workflows/ci.yml"), workflow).unwrap();
+    fs::write(
+        template_dir.join("{{project_name}}.hbs"),
+        "<p>{{title}}</p>",
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("README.md"),
+        "# {{project_name}}\n{% raw %}{{ not_a_var }}{% endraw %}\n\\{{project_name}}\n",
+    )
+    .unwrap();
+
+    // `scaffold add` copies the template verbatim
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["add", template_dir.to_str().unwrap(), "test"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let added = fs::read_to_string(temp.path().join(".templates/test/README.md")).unwrap();
+    assert!(added.contains("{% raw %}"));
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "create",
+            "test",
+            "-o",
+            output_dir.to_str().unwrap(),
+            "-v",
+            "project_name=app",
+            "-y",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let ci = fs::read_to_string(output_dir.join(".github/workflows/ci.yml")).unwrap();
+    assert_eq!(ci, workflow);
+    // File names are still rendered for copy-only files
+    let hbs = fs::read_to_string(output_dir.join("app.hbs")).unwrap();
+    assert_eq!(hbs, "<p>{{title}}</p>");
+    let readme = fs::read_to_string(output_dir.join("README.md")).unwrap();
+    assert_eq!(readme, "# app\n{{ not_a_var }}\n{{project_name}}\n");
+
+    // validate ignores placeholders in copy-only files and raw regions
+    let output = scaffold_cmd()
+        .args(["validate", template_dir.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("not_a_var"));
+    assert!(!stdout.contains("'{{title}}'"));
+}
+
+#[test]
+fn test_create_with_custom_delimiters() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(template_dir.join("[[project_name]]")).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+default = "my-project"
+
+[render]
+variable_start = "[["
+variable_end = "]]"
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("[[project_name]]/main.go.tmpl"),
+        "// [[ project_name ]]\n{{ .Name }}\n",
+    )
+    .unwrap();
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "create",
+            "test",
+            "-o",
+            output_dir.to_str().unwrap(),
+            "-v",
+            "project_name=svc",
+            "-y",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let main = fs::read_to_string(output_dir.join("svc/main.go")).unwrap();
+    assert_eq!(main, "// svc\n{{ .Name }}\n");
+
+    // validate scans with the configured delimiters
+    let output = scaffold_cmd()
+        .args(["validate", template_dir.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("All variables are defined and used"));
+}
+
+#[test]
+fn test_create_file_name_transforms() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(template_dir.join("src")).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+default = "my-project"
+
+[rename]
+"src/lib.rs" = "{{project_name}}.rs"
+"npmrc.txt" = ".npmrc"
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("_gitignore"), "target/\n").unwrap();
+    fs::write(template_dir.join("npmrc.txt"), "save-exact=true\n").unwrap();
+    fs::write(template_dir.join("__init__.py"), "").unwrap();
+    fs::write(
+        template_dir.join("config.py.jinja"),
+        "NAME = '{{project_name}}'\n",
+    )
+    .unwrap();
+    fs::write(template_dir.join("src/lib.rs"), "").unwrap();
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "create",
+            "test",
+            "-o",
+            output_dir.to_str().unwrap(),
+            "-v",
+            "project_name=app",
+            "-y",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    assert!(output_dir.join(".gitignore").exists());
+    assert!(!output_dir.join("_gitignore").exists());
+    assert!(output_dir.join(".npmrc").exists());
+    assert!(output_dir.join("__init__.py").exists());
+    assert!(output_dir.join("src/app.rs").exists());
+    let config = fs::read_to_string(output_dir.join("config.py")).unwrap();
+    assert_eq!(config, "NAME = 'app'\n");
+
+    // The preview shows the same names
+    let preview_dir = temp.path().join("preview");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "create",
+            "test",
+            "-o",
+            preview_dir.to_str().unwrap(),
+            "-v",
+            "project_name=app",
+            "--dry-run",
+        ])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(".gitignore"));
+    assert!(stdout.contains("app.rs"));
+    assert!(stdout.contains("config.py\n"));
+}
+
+#[test]
+fn test_create_rejects_path_traversal_in_names() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(template_dir.join("{{module}}")).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "module"
+description = "Module directory"
+default = "app"
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("README.md"), "# readme").unwrap();
+    fs::write(template_dir.join("{{module}}/main.py"), "").unwrap();
+
+    for bad in ["../../etc", "a/b", "", "CON"] {
+        let output_dir = temp.path().join("output");
+        let output = scaffold_cmd()
+            .current_dir(temp.path())
+            .args([
+                "create",
+                "test",
+                "-o",
+                output_dir.to_str().unwrap(),
+                "-v",
+                &format!("module={}", bad),
+                "-y",
+            ])
+            .output()
+            .unwrap();
+
+        assert!(!output.status.success(), "accepted module={:?}", bad);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("'module'"), "stderr: {}", stderr);
+        // Nothing is written before the error
+        assert!(!output_dir.exists());
+    }
+}
+
+#[test]
+fn test_create_strict_mode_for_unresolved_placeholders() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    let config = r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+"#;
+    fs::write(template_dir.join("template.toml"), config).unwrap();
+    fs::write(
+        template_dir.join("README.md"),
+        "# {{project_name}}\n\nContact: {{ author_emial }}\n",
+    )
+    .unwrap();
+    fs::write(template_dir.join("{{modul}}.txt"), "x").unwrap();
+
+    let create = |dir: &str, extra: &[&str]| {
+        let mut args = vec!["create", "test", "-o", dir, "-y"];
+        args.extend_from_slice(extra);
+        scaffold_cmd()
+            .current_dir(temp.path())
+            .args(&args)
+            .output()
+            .unwrap()
+    };
+
+    // Strict by default: every placeholder is listed and nothing is written
+    let output = create("strict", &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("README.md:3: author_emial"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains("{{modul}}.txt (file name): modul"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(!temp.path().join("strict").exists());
+
+    // Dry run shows them below the preview
+    let output = create("preview", &["--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Unresolved placeholders"));
+    assert!(stdout.contains("README.md:3: author_emial"));
+
+    // --lenient leaves them as-is
+    let output = create("lenient", &["--lenient"]);
+    assert!(output.status.success());
+    let readme = fs::read_to_string(temp.path().join("lenient/README.md")).unwrap();
+    assert!(readme.contains("{{ author_emial }}"));
+    assert!(temp.path().join("lenient/{{modul}}.txt").exists());
+
+    // ...as does strict = false in template.toml
+    fs::write(
+        template_dir.join("template.toml"),
+        format!("{}\n[render]\nstrict = false\n", config),
+    )
+    .unwrap();
+    let output = create("relaxed", &[]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_create_example_template_names_project_after_output() {
+    let temp = TempDir::new().unwrap();
+    let example =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("example-templates/rust-cli");
+    copy_dir_all(&example, &temp.path().join(".templates/rust-cli")).unwrap();
+
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "rust-cli", "-o", "my-tool", "-y"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let manifest = fs::read_to_string(temp.path().join("my-tool/Cargo.toml")).unwrap();
+    assert!(manifest.contains("name = \"my_tool\""), "{}", manifest);
+}
+
+#[test]
+fn test_create_keeps_github_actions_expressions() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(template_dir.join(".github/workflows")).unwrap();
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+"#,
+    )
+    .unwrap();
+    let workflow = "name: {{project_name}} CI\non: push\njobs:\n  build:\n    steps:\n      - run: echo ${{ github.ref }} ${{ secrets.TOKEN }}\n";
+    fs::write(template_dir.join(".github/workflows/ci.yml"), workflow).unwrap();
+
+    // Strict mode is on by default, so a misread expression would fail here
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "test", "-o", "myapp", "-y"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let rendered = fs::read_to_string(temp.path().join("myapp/.github/workflows/ci.yml")).unwrap();
+    assert!(rendered.starts_with("name: myapp CI\n"));
+    assert!(rendered.contains("echo ${{ github.ref }} ${{ secrets.TOKEN }}"));
+}
+
+#[test]
+fn test_create_keeps_foreign_filter_expressions() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(&template_dir).unwrap();
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+"#,
+    )
+    .unwrap();
+    let playbook = "- name: {{ project_name | upper }}\n  shell: echo {{ item | lower }}\n";
+    fs::write(template_dir.join("playbook.yml"), playbook).unwrap();
+
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "test", "-o", "myapp", "-y"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let rendered = fs::read_to_string(temp.path().join("myapp/playbook.yml")).unwrap();
+    assert_eq!(
+        rendered,
+        "- name: MYAPP\n  shell: echo {{ item | lower }}\n"
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn test_create_hook_answers_cannot_inject_commands() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(&template_dir).unwrap();
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+
+[hooks]
+post_create = ["echo {{ project_name }} > name.txt"]
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("README.md"), "# {{ project_name }}\n").unwrap();
+
+    let answer = "x; touch PWNED $(touch PWNED2) `touch PWNED3` it's";
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "test", "-o", "out", "-y", "-v"])
+        .arg(format!("project_name={}", answer))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let out = temp.path().join("out");
+    for injected in ["PWNED", "PWNED2", "PWNED3"] {
+        assert!(!out.join(injected).exists(), "{} was created", injected);
+        assert!(!temp.path().join(injected).exists());
+    }
+    assert_eq!(
+        fs::read_to_string(out.join("name.txt")).unwrap(),
+        format!("{}\n", answer)
+    );
+}
+
+#[test]
+fn test_create_with_builtin_variables() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "author"
+description = "Author"
+default = "{{ _env.SCAFFOLD_TEST_AUTHOR }}"
+
+[hooks]
+post_create = ["echo {{ _now.year }} > year.txt"]
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("LICENSE"),
+        "Copyright {{ _now.year }} {{ author }}\n",
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("settings.txt"),
+        "id={{ _uuid }}\nkey={{ _random_hex(32) }}\nsame={{ _random_hex(32) }}\n",
+    )
+    .unwrap();
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .env("SCAFFOLD_TEST_AUTHOR", "Jane Doe")
+        .args(["create", "test", "-o", output_dir.to_str().unwrap(), "-y"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let license = fs::read_to_string(output_dir.join("LICENSE")).unwrap();
+    let year = fs::read_to_string(output_dir.join("year.txt")).unwrap();
+    let year = year.trim();
+    assert_eq!(year.len(), 4);
+    assert_eq!(license, format!("Copyright {} Jane Doe\n", year));
+
+    let settings = fs::read_to_string(output_dir.join("settings.txt")).unwrap();
+    let values: Vec<&str> = settings
+        .lines()
+        .map(|l| l.split_once('=').unwrap().1)
+        .collect();
+    assert_eq!(values[0].len(), 36);
+    assert_eq!(values[0].matches('-').count(), 4);
+    assert_eq!(values[1].len(), 32);
+    assert!(values[1].chars().all(|c| c.is_ascii_hexdigit()));
+    // The same built-in renders the same value throughout a project
+    assert_eq!(values[1], values[2]);
+
+    // validate treats built-ins as defined
+    let output = scaffold_cmd()
+        .args(["validate", template_dir.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("not defined"), "stdout: {}", stdout);
+}
+
+#[test]
+fn test_create_with_computed_variables() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(template_dir.join("src")).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+
+[[computed]]
+name = "module"
+value = "{{ crate_name | upper }}"
+
+[[computed]]
+name = "crate_name"
+value = "{{ project_name | snake_case }}"
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("src/{{crate_name}}.rs"),
+        "// {{ project_name }} / {{ module }} / {{ project_name | pascal_case }}\n",
+    )
+    .unwrap();
+
+    // project_name defaults to the output directory name
+    let output_dir = temp.path().join("my-cool-app");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "test", "-o", output_dir.to_str().unwrap(), "-y"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let lib = fs::read_to_string(output_dir.join("src/my_cool_app.rs")).unwrap();
+    assert_eq!(lib, "// my_cool_app / MY_COOL_APP / MyCoolApp\n");
+
+    // ...and can be set explicitly
+    let output_dir = temp.path().join("other");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "create",
+            "test",
+            "-o",
+            output_dir.to_str().unwrap(),
+            "-v",
+            "project_name=Web Service",
+            "-y",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output_dir.join("src/web_service.rs").exists());
+
+    // Cycles are reported instead of looping
+    let config = fs::read_to_string(template_dir.join("template.toml")).unwrap();
+    fs::write(
+        template_dir.join("template.toml"),
+        config.replace("{{ project_name | snake_case }}", "{{ module }}"),
+    )
+    .unwrap();
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "test", "-o", "cyclic", "-y"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("cycle"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_json_output() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/test");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Test Template"
+description = "A test template"
+
+[[variables]]
+name = "include_docker"
+description = "Include Docker support"
+type = "bool"
+default = "false"
+
+[[conditionals]]
+include = "Dockerfile"
+when = "include_docker == true"
+
+[hooks]
+post_create = ["echo ok", "exit 3"]
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("Dockerfile"), "FROM alpine:latest").unwrap();
+    fs::write(
+        template_dir.join("README.md"),
+        "# {{project_name}} {{ typo }}",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let output = scaffold_cmd()
+            .current_dir(temp.path())
+            .args(args)
+            .output()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+            panic!("{}: {}", e, String::from_utf8_lossy(&output.stdout));
+        });
+        (output.status.success(), json)
+    };
+
+    let (ok, list) = run(&["list", "--format", "json"]);
+    assert!(ok);
+    let test = list["templates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["name"] == "test")
+        .unwrap();
+    assert_eq!(test["source"], "local");
+    assert_eq!(test["description"], "A test template");
+
+    let (ok, info) = run(&["info", "test", "--format", "json"]);
+    assert!(ok);
+    assert_eq!(info["config"]["variables"][0]["type"], "bool");
+    assert_eq!(info["config"]["conditionals"][0]["include"], "Dockerfile");
+    assert_eq!(info["config"]["hooks"]["post_create"][1], "exit 3");
+
+    let (ok, report) = run(&[
+        "validate",
+        template_dir.to_str().unwrap(),
+        "--format",
+        "json",
+    ]);
+    assert!(ok);
+    let diagnostics = report["diagnostics"].as_array().unwrap();
+    let typo = diagnostics
+        .iter()
+        .find(|d| d["message"].as_str().unwrap().contains("typo"))
+        .unwrap();
+    assert_eq!(typo["severity"], "warning");
+    assert_eq!(typo["location"], "README.md:1");
+
+    let (ok, manifest) = run(&[
+        "--format",
+        "json",
+        "create",
+        "test",
+        "-o",
+        "out",
+        "-y",
+        "--lenient",
+    ]);
+    assert!(ok);
+    assert_eq!(manifest["files"], serde_json::json!(["README.md"]));
+    assert_eq!(manifest["skipped"], serde_json::json!(["Dockerfile"]));
+    assert_eq!(
+        manifest["unresolved"],
+        serde_json::json!(["README.md:1: typo"])
+    );
+    assert_eq!(manifest["hooks"][0]["success"], true);
+    assert_eq!(manifest["hooks"][1]["success"], false);
+    assert!(temp.path().join("out/README.md").exists());
+}
+
+#[test]
+fn test_validate_reports_codes_and_exit_status() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join("broken");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Broken"
+description = "A template with mistakes"
+
+[[variables]]
+name = "license"
+description = "License"
+type = "choice"
+choices = ["MIT", "Apache-2.0"]
+default = "GPL"
+
+[[variables]]
+name = "docker"
+description = "Docker?"
+type = "bool"
+default = "maybe"
+
+[[variables]]
+name = "license"
+description = "Declared twice"
+
+[[conditionals]]
+include = "Dockerfile"
+when = "docker == true"
+
+[hooks]
+scripts = ["setup.rhai"]
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("setup.rhai"), "let x = ;").unwrap();
+    fs::write(template_dir.join("README.md"), "{{ license }} {{ typo }}").unwrap();
+
+    let output = scaffold_cmd()
+        .args([
+            "validate",
+            template_dir.to_str().unwrap(),
+            "--format",
+            "json",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["valid"], false);
+    let codes: Vec<&str> = report["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["code"].as_str().unwrap())
+        .collect();
+    for code in ["S001", "S004", "S006", "S007", "S009", "S012"] {
+        assert!(codes.contains(&code), "missing {} in {:?}", code, codes);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("validation failed"), "stderr: {}", stderr);
+
+    // Warnings alone pass, unless --strict
+    let template_dir = temp.path().join("warnings");
+    fs::create_dir_all(&template_dir).unwrap();
+    fs::write(
+        template_dir.join("template.toml"),
+        "name = \"Warnings\"\ndescription = \"\"\n",
+    )
+    .unwrap();
+    fs::write(template_dir.join("README.md"), "{{ undeclared }}").unwrap();
+
+    let validate = |strict: bool| {
+        let mut cmd = scaffold_cmd();
+        cmd.args(["validate", template_dir.to_str().unwrap()]);
+        if strict {
+            cmd.arg("--strict");
+        }
+        cmd.output().unwrap()
+    };
+    let output = validate(false);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[S001 README.md:1]"), "stdout: {}", stdout);
+    assert!(!validate(true).status.success());
+}
+
+/// Helper function to recursively copy a directory
+fn copy_dir_all(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let ty = entry.file_type()?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+        if ty.is_dir() {
+            copy_dir_all(&src_path, &dst_path)?;
+        } else {
+            fs::copy(&src_path, &dst_path)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_template_test_cases() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates").join("svc");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Service"
+description = "A service"
+
+[[variables]]
+name = "docker"
+description = "Include a Dockerfile?"
+type = "bool"
+default = "false"
+
+[[conditionals]]
+include = "Dockerfile"
+when = "docker == true"
+
+[[tests]]
+name = "defaults"
+absent = ["Dockerfile"]
+matches = { "README.md" = ["^# defaults$"] }
+commands = ["test -f README.md"]
+
+[[tests]]
+name = "with docker"
+output = "my-svc"
+answers = { docker = true }
+exists = ["Dockerfile"]
+matches = { "README.md" = ["^# my_svc$"] }
+
+[[tests]]
+name = "broken"
+exists = ["Dockerfile"]
+matches = { "README.md" = ["^# other"] }
+commands = ["exit 3"]
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("README.md"), "# {{ project_name }}\n").unwrap();
+    fs::write(template_dir.join("Dockerfile"), "FROM scratch\n").unwrap();
+
+    let output = scaffold_cmd()
+        .args(["test", "svc", "--case", "defaults"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let output = scaffold_cmd()
+        .args(["test", "svc", "--format", "json"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["passed"], 2);
+    assert_eq!(report["failed"], 1);
+    let broken = &report["tests"][2];
+    assert_eq!(broken["name"], "broken");
+    let messages: Vec<&str> = broken["failures"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["message"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "expected Dockerfile to exist",
+            "README.md does not match /^# other/",
+            "command failed: exit 3",
+        ]
+    );
+    assert!(
+        broken["failures"][1]["detail"]
+            .as_str()
+            .unwrap()
+            .contains("# broken")
+    );
+
+    let output = scaffold_cmd()
+        .args(["test", "svc", "--case", "missing"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no test named 'missing'"));
+}
+
+#[test]
+fn test_template_test_output_must_be_a_name() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates").join("svc");
+    fs::create_dir_all(&template_dir).unwrap();
+    let outside = temp.path().join("outside");
+    fs::write(
+        template_dir.join("template.toml"),
+        format!(
+            r#"
+name = "Service"
+description = "A service"
+
+[[tests]]
+name = "escape"
+output = "../escaped"
+
+[[tests]]
+name = "absolute"
+output = "{}"
+"#,
+            outside.display()
+        ),
+    )
+    .unwrap();
+    fs::write(template_dir.join("README.md"), "# {{ project_name }}\n").unwrap();
+
+    let output = scaffold_cmd()
+        .args(["test", "svc"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid output '../escaped'"));
+    assert!(!outside.exists());
+
+    let output = scaffold_cmd()
+        .args(["validate", ".templates/svc", "--format", "json"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let codes: Vec<&str> = report["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .filter(|code| *code == "S024")
+        .collect();
+    assert_eq!(codes.len(), 2);
+}
+
+#[test]
+fn test_template_snapshots() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates").join("svc");
+    fs::create_dir_all(template_dir.join("bin")).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Service"
+description = "A service"
+
+[[variables]]
+name = "project_name"
+description = "Project name"
+
+[[tests]]
+name = "default"
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("README.md"),
+        "# {{ project_name }}\n\nCreated {{ _now.date }}\n",
+    )
+    .unwrap();
+    fs::write(template_dir.join("bin/run"), "#!/bin/sh\n").unwrap();
+    fs::write(
+        temp.path().join("answers.toml"),
+        "project_name = \"demo\"\n",
+    )
+    .unwrap();
+
+    // No snapshot yet
+    let output = scaffold_cmd()
+        .args(["test", "svc", "--snapshot"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("--update to create it"));
+
+    for args in [
+        vec!["test", "svc", "--snapshot", "--update"],
+        vec![
+            "test",
+            "svc",
+            "--answers",
+            "answers.toml",
+            "--snapshot",
+            "--update",
+        ],
+    ] {
+        let output = scaffold_cmd()
+            .args(&args)
+            .current_dir(temp.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?} failed", args);
+    }
+    let snapshot = temp.path().join("snapshots/svc/answers");
+    assert_eq!(
+        fs::read_to_string(snapshot.join("files/README.md")).unwrap(),
+        "# demo\n\nCreated 2000-01-01\n"
+    );
+    let manifest = fs::read_to_string(snapshot.join("manifest")).unwrap();
+    assert!(manifest.contains("dir bin\n"));
+    assert!(temp.path().join("snapshots/svc/default/manifest").exists());
+
+    // Rendering again matches
+    let output = scaffold_cmd()
+        .args(["test", "svc", "--snapshot"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // A template change shows up as a diff
+    fs::write(template_dir.join("README.md"), "# {{ project_name }}!\n").unwrap();
+    let output = scaffold_cmd()
+        .args(["test", "svc", "--answers", "answers.toml", "--snapshot"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("snapshot: README.md differs"));
+    assert!(stdout.contains("-# demo"));
+    assert!(stdout.contains("+# demo!"));
+    assert!(stdout.contains("-Created 2000-01-01"));
+}
+
+#[test]
+fn test_template_matrix() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates").join("svc");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Service"
+description = "A service"
+
+[[variables]]
+name = "docker"
+description = "Docker?"
+type = "bool"
+
+[[variables]]
+name = "kind"
+description = "Project kind"
+type = "choice"
+choices = ["bin", "lib"]
+
+[[variables]]
+name = "license"
+description = "License"
+type = "choice"
+choices = ["MIT", "Apache-2.0"]
+
+[[conditionals]]
+include = "lib.rs"
+when = "kind == lib"
+
+[[conditionals]]
+include = "Dockerfile"
+when = "docker == true"
+
+[matrix]
+answers = { license = "MIT" }
+commands = ["test -f LICENSE-MIT"]
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("lib.rs"), "// {{ lib_name }}\n").unwrap();
+    fs::write(template_dir.join("Dockerfile"), "FROM scratch\n").unwrap();
+    fs::write(template_dir.join("LICENSE-{{ license }}"), "").unwrap();
+
+    let output = scaffold_cmd()
+        .args(["test", "svc", "--matrix", "--format", "json"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["passed"], 2);
+    assert_eq!(report["failed"], 2);
+    let failing: Vec<&str> = report["tests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|t| t["passed"] == false)
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        failing,
+        vec!["docker=false, kind=lib", "docker=true, kind=lib"]
+    );
+
+    let output = scaffold_cmd()
+        .args(["test", "svc", "--matrix", "--max", "3", "--seed", "7"])
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Rendering 3 of 4 combination(s)"));
+    assert!(stdout.contains("--seed 7"));
+    assert!(stdout.contains("lib_name"));
+}
+
+#[test]
+fn test_library_scaffolder() {
+    use project_scaffold::Scaffolder;
+    use project_scaffold::builtins::Builtins;
+
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join("svc");
+    fs::create_dir_all(&template_dir).unwrap();
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Service"
+description = "A service"
+
+[[variables]]
+name = "project_name"
+description = "Project name"
+
+[[variables]]
+name = "docker"
+description = "Docker?"
+type = "bool"
+
+[[conditionals]]
+include = "Dockerfile"
+when = "docker == true"
+
+[hooks]
+post_create = ["touch hooked"]
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("README.md"),
+        "# {{ project_name }} ({{ _now.year }})\n",
+    )
+    .unwrap();
+    fs::write(template_dir.join("Dockerfile"), "FROM scratch\n").unwrap();
+
+    let output = temp.path().join("out");
+    let report = Scaffolder::new()
+        .template(template_dir.to_str().unwrap())
+        .answers([("project_name", "demo")])
+        .output(&output)
+        .builtins(Builtins::fixed())
+        .render()
+        .unwrap();
+
+    assert_eq!(report.template, "svc");
+    assert_eq!(report.files, vec!["README.md"]);
+    assert_eq!(report.skipped, vec!["Dockerfile"]);
+    assert!(report.hooks[0].success);
+    assert!(output.join("hooked").exists());
+    assert_eq!(
+        fs::read_to_string(output.join("README.md")).unwrap(),
+        "# demo (2000)\n"
+    );
+
+    // The output directory must be new
+    let err = Scaffolder::new()
+        .template(template_dir.to_str().unwrap())
+        .output(&output)
+        .render()
+        .unwrap_err();
+    assert!(err.to_string().contains("already exists"));
+
+    // A dry run writes nothing
+    let planned = temp.path().join("planned");
+    let report = Scaffolder::new()
+        .template(template_dir.to_str().unwrap())
+        .answer("docker", "true")
+        .output(&planned)
+        .dry_run(true)
+        .render()
+        .unwrap();
+    assert!(report.dry_run);
+    assert!(report.files.contains(&"Dockerfile".to_string()));
+    assert!(!planned.exists());
+}
+
+#[test]
+fn test_create_with_piped_answers() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/piped");
+    fs::create_dir_all(&template_dir).unwrap();
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Piped"
+description = "Answers from stdin"
+
+[[variables]]
+name = "project_name"
+description = "Name"
+
+[[variables]]
+name = "author"
+description = "Author"
+default = "Nobody"
+
+[[variables]]
+name = "docker"
+description = "Docker?"
+type = "bool"
+
+[[variables]]
+name = "kind"
+description = "Kind"
+type = "choice"
+choices = ["bin", "lib"]
+
+[[variables]]
+name = "features"
+description = "Features"
+type = "multi"
+choices = ["ci", "docs", "bench"]
+
+[[variables]]
+name = "token"
+description = "Token"
+type = "secret"
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("README.md"),
+        "{{project_name}} by {{author}}\ndocker={{docker}} kind={{kind}}\nfeatures={{features}} token={{token}}\n",
+    )
+    .unwrap();
+
+    // Output directory, then one line per variable; the empty line keeps the author default
+    let mut child = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "piped"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"out\nwidget\n\nyes\nlib\n1,3\ns3cret\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Output directory: "));
+    assert!(stderr.contains("Author (author) [Nobody]: "));
+    assert!(stderr.contains("  3) bench"));
+
+    let readme = fs::read_to_string(temp.path().join("out/README.md")).unwrap();
+    assert_eq!(
+        readme,
+        "widget by Nobody\ndocker=true kind=lib\nfeatures=ci, bench token=s3cret\n"
+    );
+}
+
+#[test]
+fn test_create_with_jinja_engine() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/jinja");
+    fs::create_dir_all(template_dir.join("{{ project_name | snake_case }}")).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Jinja Template"
+description = "A template rendered with Jinja"
+engine = "jinja"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+default = "my-project"
+
+[[variables]]
+name = "docker"
+description = "Include a Dockerfile"
+type = "bool"
+default = "false"
+
+[[variables]]
+name = "features"
+description = "Features"
+type = "multi"
+choices = ["ci", "docs", "bench"]
+default = "ci, docs"
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("{{ project_name | snake_case }}/README.md"),
+        "# {{ project_name | upper }}\n{% if docker %}docker\n{% else %}no docker\n{% endif %}\
+{% for f in features | split(', ') %}- {{ f }}\n{% endfor %}",
+    )
+    .unwrap();
+
+    let validate = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["validate", ".templates/jinja"])
+        .output()
+        .unwrap();
+    assert!(
+        validate.status.success(),
+        "{}",
+        String::from_utf8_lossy(&validate.stdout)
+    );
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "create",
+            "jinja",
+            "-o",
+            output_dir.to_str().unwrap(),
+            "-v",
+            "project_name=my-app",
+            "-y",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let readme = fs::read_to_string(output_dir.join("my_app/README.md")).unwrap();
+    assert_eq!(readme, "# MY-APP\nno docker\n- ci\n- docs\n");
+}
+
+#[test]
+fn test_create_with_handlebars_engine() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/hbs");
+    fs::create_dir_all(&template_dir).unwrap();
+
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "Handlebars Template"
+description = "A template rendered with Handlebars"
+engine = "handlebars"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+default = "my-project"
+
+[[variables]]
+name = "docker"
+description = "Include a Dockerfile"
+type = "bool"
+default = "true"
+"#,
+    )
+    .unwrap();
+    fs::write(
+        template_dir.join("README.md"),
+        "# {{pascal_case project_name}}\n{{#if docker}}docker{{else}}no docker{{/if}}\n",
+    )
+    .unwrap();
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "hbs", "-o", output_dir.to_str().unwrap(), "-y"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let readme = fs::read_to_string(output_dir.join("README.md")).unwrap();
+    assert_eq!(readme, "# MyProject\ndocker\n");
+
+    // A file the engine can't parse fails validation
+    fs::write(template_dir.join("broken.txt"), "{{#if docker}}unclosed\n").unwrap();
+    let validate = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["validate", ".templates/hbs"])
+        .output()
+        .unwrap();
+    assert!(!validate.status.success());
+    assert!(String::from_utf8_lossy(&validate.stdout).contains("S022"));
+}
+
+#[test]
+fn test_import_cookiecutter_template() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("cookiecutter-pkg");
+    let project = source.join("{{cookiecutter.project_slug}}");
+    fs::create_dir_all(project.join("{{cookiecutter.project_slug}}")).unwrap();
+    fs::create_dir_all(source.join("hooks")).unwrap();
+    fs::create_dir_all(project.join("static")).unwrap();
+
+    fs::write(
+        source.join("cookiecutter.json"),
+        r#"{
+    "project_name": "My Package",
+    "project_slug": "{{ cookiecutter.project_name.lower().replace(' ', '_') }}",
+    "license": ["MIT", "Apache-2.0"],
+    "use_docker": false,
+    "settings": {"debug": true},
+    "__year": "2024",
+    "_copy_without_render": ["static"],
+    "__prompts__": {"project_name": "What is your project called?"}
+}"#,
+    )
+    .unwrap();
+    fs::write(source.join("hooks/post_gen_project.py"), "print('done')\n").unwrap();
+    fs::write(
+        project.join("README.md"),
+        "# {{ cookiecutter.project_name }}\n{{ cookiecutter.license }} {{ cookiecutter.__year }}\n\
+{% if cookiecutter.use_docker %}docker\n{% endif %}",
+    )
+    .unwrap();
+    fs::write(
+        project.join("{{cookiecutter.project_slug}}/__init__.py"),
+        "",
+    )
+    .unwrap();
+    fs::write(project.join("static/app.js"), "const x = `{{ raw }}`;\n").unwrap();
+
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "import",
+            source.to_str().unwrap(),
+            "pkg",
+            "--from",
+            "cookiecutter",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("settings: dictionary variables aren't supported"));
+    assert!(stdout.contains("hooks/"));
+
+    let config = fs::read_to_string(temp.path().join(".templates/pkg/template.toml")).unwrap();
+    assert!(config.contains("engine = \"jinja\""));
+    assert!(config.contains("description = \"What is your project called?\""));
+    assert!(config.contains("choices = [\n    \"MIT\",\n    \"Apache-2.0\",\n]"));
+
+    let validate = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["validate", ".templates/pkg"])
+        .output()
+        .unwrap();
+    assert!(
+        validate.status.success(),
+        "{}",
+        String::from_utf8_lossy(&validate.stdout)
+    );
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "pkg", "-o", output_dir.to_str().unwrap(), "-y"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("README.md")).unwrap(),
+        "# My Package\nMIT 2024\n"
+    );
+    assert!(output_dir.join("my_package/__init__.py").exists());
+    assert_eq!(
+        fs::read_to_string(output_dir.join("static/app.js")).unwrap(),
+        "const x = `{{ raw }}`;\n"
+    );
+}
+
+#[test]
+fn test_import_copier_template() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("copier-app");
+    fs::create_dir_all(source.join("template")).unwrap();
+
+    fs::write(
+        source.join("copier.yml"),
+        r#"
+_subdirectory: template
+_tasks:
+  - "echo done"
+project_name:
+  type: str
+  help: Project name
+  default: demo
+features:
+  type: str
+  help: Features
+  choices: [ci, docs, bench]
+  multiselect: true
+  default: [ci]
+port:
+  type: int
+  default: 8080
+  when: "{{ project_name != 'demo' }}"
+use_docker: true
+"#,
+    )
+    .unwrap();
+    fs::write(
+        source.join("template/README.md.jinja"),
+        "# {{ project_name }} on {{ port }}\n{% if use_docker %}docker{% endif %}\n",
+    )
+    .unwrap();
+    fs::write(source.join("template/notes.txt"), "Literal {{ braces }}\n").unwrap();
+    fs::write(source.join("template/~backup"), "skipped\n").unwrap();
+
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "import",
+            source.to_str().unwrap(),
+            "app",
+            "--from",
+            "copier",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("port: imported as a string (was `int`)"));
+    assert!(stdout.contains("port: `when` isn't supported"));
+
+    let template_dir = temp.path().join(".templates/app");
+    assert!(template_dir.join("README.md").exists());
+    assert!(!template_dir.join("~backup").exists());
+    let config = fs::read_to_string(template_dir.join("template.toml")).unwrap();
+    assert!(config.contains("copy_without_render = [\"notes.txt\"]"));
+    assert!(config.contains("post_create = [\"echo done\"]"));
+    assert!(config.contains("type = \"multi\""));
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "app", "-o", output_dir.to_str().unwrap(), "-y"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("README.md")).unwrap(),
+        "# demo on 8080\ndocker\n"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("notes.txt")).unwrap(),
+        "Literal {{ braces }}\n"
+    );
+}
+
+#[test]
+fn test_extract_template_from_project() {
+    let temp = TempDir::new().unwrap();
+    let project = temp.path().join("my-app");
+    fs::create_dir_all(project.join("src/my_app")).unwrap();
+    fs::create_dir_all(project.join("target/debug")).unwrap();
+    fs::create_dir_all(project.join("node_modules/left-pad")).unwrap();
+    fs::create_dir_all(project.join("logs")).unwrap();
+
+    fs::write(project.join(".gitignore"), "logs/\n*.secret\n").unwrap();
+    fs::write(
+        project.join("README.md"),
+        "# MyApp\nmy-app by Jane Doe\nMY_APP_HOME is set; {{ go_template }}\n",
+    )
+    .unwrap();
+    fs::write(
+        project.join("src/my_app/__init__.py"),
+        "NAME = \"my_app\"\n",
+    )
+    .unwrap();
+    fs::write(project.join("target/debug/my-app"), "binary").unwrap();
+    fs::write(project.join("node_modules/left-pad/index.js"), "").unwrap();
+    fs::write(project.join("logs/today.log"), "").unwrap();
+    fs::write(project.join("api.secret"), "hunter2").unwrap();
+
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "extract",
+            project.to_str().unwrap(),
+            "app",
+            "-r",
+            "my-app=project_name",
+            "-r",
+            "Jane Doe=author",
+            "-r",
+            "Nowhere=unused",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("'Nowhere' was not found"));
+
+    let template_dir = temp.path().join(".templates/app");
+    assert!(template_dir.join(".gitignore").exists());
+    assert!(template_dir.join("src/my_app/__init__.py").exists());
+    for skipped in ["target", "node_modules", "logs", "api.secret"] {
+        assert!(!template_dir.join(skipped).exists(), "{}", skipped);
+    }
+    let config = fs::read_to_string(template_dir.join("template.toml")).unwrap();
+    assert!(
+        config.contains(
+            "name = \"project_name\"\ndescription = \"Project name\"\ndefault = \"my-app\""
+        )
+    );
+    assert!(config.contains("\"src/my_app\" = \"{{ project_name | snake_case }}\""));
+
+    let output_dir = temp.path().join("output");
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "create",
+            "app",
+            "-o",
+            output_dir.to_str().unwrap(),
+            "-v",
+            "project_name=cool-tool",
+            "-v",
+            "author=Ann Lee",
+            "-y",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("README.md")).unwrap(),
+        "# CoolTool\ncool-tool by Ann Lee\nCOOL_TOOL_HOME is set; {{ go_template }}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("src/cool_tool/__init__.py")).unwrap(),
+        "NAME = \"cool_tool\"\n"
+    );
+}
+
+#[test]
+fn test_template_new_skeleton_validates() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let temp = TempDir::new().unwrap();
+
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "template",
+            "new",
+            "starter",
+            "-y",
+            "--conditional",
+            "--hook",
+            "--test",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let template_dir = temp.path().join(".templates/starter");
+    for file in ["template.toml", "README.md", "example.txt", "docs/index.md"] {
+        assert!(template_dir.join(file).exists(), "{}", file);
+    }
+
+    let validate = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["validate", ".templates/starter", "--strict"])
+        .output()
+        .unwrap();
+    assert!(
+        validate.status.success(),
+        "{}",
+        String::from_utf8_lossy(&validate.stdout)
+    );
+
+    let test = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["test", "starter"])
+        .output()
+        .unwrap();
+    assert!(
+        test.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&test.stdout),
+        String::from_utf8_lossy(&test.stderr)
+    );
+
+    // Answers can be piped in, one line per prompt, into a given path
+    let mut child = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["template", "new", "piped", "--path", "elsewhere/piped"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"Piped\nA piped template\n\n\n\n\nuse_docker\n\n2\ny\n\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let config = fs::read_to_string(temp.path().join("elsewhere/piped/template.toml")).unwrap();
+    assert!(config.contains(
+        "name = \"use_docker\"\ndescription = \"Use docker\"\ntype = \"bool\"\ndefault = \"true\""
+    ));
+
+    let validate = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["validate", "elsewhere/piped", "--strict"])
+        .output()
+        .unwrap();
+    assert!(
+        validate.status.success(),
+        "{}",
+        String::from_utf8_lossy(&validate.stdout)
+    );
+}
+
+#[test]
+fn test_pack_and_add_archive() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join("api");
+    fs::create_dir_all(&template_dir).unwrap();
+    fs::write(
+        template_dir.join("template.toml"),
+        r#"
+name = "API"
+description = "An API service"
+version = "1.4.0"
+
+[[variables]]
+name = "project_name"
+description = "Project name"
+"#,
+    )
+    .unwrap();
+    fs::write(template_dir.join("README.md"), "# {{project_name}}\n").unwrap();
+
+    let output = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["pack", "api", "-o", "dist"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let archive = temp.path().join("dist/api-1.4.0.tar.gz");
+    assert!(archive.exists());
+    let checksum = fs::read_to_string(temp.path().join("dist/api-1.4.0.tar.gz.sha256")).unwrap();
+    assert!(checksum.ends_with("  api-1.4.0.tar.gz\n"));
+
+    // The checksum file next to the archive is picked up
+    let add = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["add", "dist/api-1.4.0.tar.gz", "api"])
+        .output()
+        .unwrap();
+    assert!(
+        add.status.success(),
+        "{}",
+        String::from_utf8_lossy(&add.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(temp.path().join(".templates/api/README.md")).unwrap(),
+        "# {{project_name}}\n"
+    );
+
+    let tampered = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["add", "dist/api-1.4.0.tar.gz", "other", "--sha256"])
+        .arg("0".repeat(64))
+        .output()
+        .unwrap();
+    assert!(!tampered.status.success());
+    assert!(String::from_utf8_lossy(&tampered.stderr).contains("Checksum mismatch"));
+    assert!(!temp.path().join(".templates/other").exists());
+
+    let zip = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["pack", "api", "-o", "dist", "--zip"])
+        .output()
+        .unwrap();
+    assert!(zip.status.success());
+    let url = format!(
+        "file://{}",
+        temp.path().join("dist/api-1.4.0.zip").display()
+    );
+    let add_url = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["add", &url, "api-zip"])
+        .output()
+        .unwrap();
+    assert!(
+        add_url.status.success(),
+        "{}",
+        String::from_utf8_lossy(&add_url.stderr)
+    );
+    assert!(
+        temp.path()
+            .join(".templates/api-zip/template.toml")
+            .exists()
+    );
+
+    // A checksum from the server hosting the archive proves nothing, so
+    // downloads need one from the user
+    let remote = scaffold_cmd()
+        .current_dir(temp.path())
+        .args([
+            "add",
+            "https://files.example.com/api-1.4.0.tar.gz",
+            "remote",
+        ])
+        .output()
+        .unwrap();
+    assert!(!remote.status.success());
+    assert!(String::from_utf8_lossy(&remote.stderr).contains("--sha256"));
+    assert!(!temp.path().join(".templates/remote").exists());
+}
+
+#[test]
+fn test_template_metadata_and_compatibility() {
+    let temp = TempDir::new().unwrap();
+    let write_template = |name: &str, extra: &str| {
+        let dir = temp.path().join(".templates").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("template.toml"),
+            format!(
+                "name = \"{}\"\ndescription = \"The {} template\"\n{}",
+                name, name, extra
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("README.md"), "# hello\n").unwrap();
+    };
+    write_template(
+        "api",
+        r#"version = "2.1.0"
+authors = ["Jane Doe"]
+license = "MIT"
+homepage = "https://example.com/api"
+tags = ["python", "api"]
+"#,
+    );
+    write_template("web", "tags = [\"typescript\"]\n");
+    write_template("future", "min_scaffold_version = \"999.0\"\n");
+
+    let list = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["list", "--tag", "Python"])
+        .output()
+        .unwrap();
+    assert!(list.status.success());
+    let stdout = String::from_utf8_lossy(&list.stdout);
+    assert!(stdout.contains("api v2.1.0"), "{}", stdout);
+    assert!(!stdout.contains("web"));
+    // Templates needing a newer scaffold are skipped with the reason
+    let stderr = String::from_utf8_lossy(&list.stderr);
+    assert!(
+        stderr.contains("requires scaffold 999.0 or newer"),
+        "{}",
+        stderr
+    );
+
+    let info = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["info", "api"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&info.stdout);
+    assert!(stdout.contains("Version: 2.1.0"), "{}", stdout);
+    assert!(stdout.contains("Authors: Jane Doe"));
+    assert!(stdout.contains("License: MIT"));
+    assert!(stdout.contains("Tags: python, api"));
+
+    let create = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", ".templates/future", "-o", "out", "-y"])
+        .output()
+        .unwrap();
+    assert!(!create.status.success());
+    let stderr = String::from_utf8_lossy(&create.stderr);
+    assert!(
+        stderr.contains("Upgrade with: cargo install project-scaffold"),
+        "{}",
+        stderr
+    );
+    assert!(!temp.path().join("out").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_requirements_and_doctor() {
+    let temp = TempDir::new().unwrap();
+    let template_dir = temp.path().join(".templates/tools");
+    fs::create_dir_all(&template_dir).unwrap();
+    fs::write(template_dir.join("README.md"), "# {{project_name}}\n").unwrap();
+    let write_config = |requirements: &str| {
+        fs::write(
+            template_dir.join("template.toml"),
+            format!(
+                "name = \"Tools\"\ndescription = \"Needs tools\"\n\n[requirements]\n{}",
+                requirements
+            ),
+        )
+        .unwrap();
+    };
+    write_config(
+        r#"fresh = { version = ">=1.2", command = "echo fresh 1.4.0" }
+stale = { version = ">=2", command = "echo stale v1.9" }
+absent = ">=1"
+"#,
+    );
+
+    let doctor = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["doctor", "tools"])
+        .output()
+        .unwrap();
+    assert!(!doctor.status.success());
+    let stdout = String::from_utf8_lossy(&doctor.stdout);
+    assert!(stdout.contains("fresh 1.4.0 (need >=1.2)"), "{}", stdout);
+    assert!(stdout.contains("stale 1.9 (need >=2)"));
+    assert!(stdout.contains("absent not found (need >=1)"));
+    assert!(stdout.contains("1 met, 1 missing, 1 outdated"));
+
+    let json = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["--format", "json", "doctor", "tools"])
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&json.stdout).unwrap();
+    assert_eq!(report["ok"], false);
+    let statuses: Vec<&str> = report["templates"][0]["requirements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, vec!["missing", "met", "outdated"]);
+
+    let create = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "tools", "-o", "out", "-y"])
+        .output()
+        .unwrap();
+    assert!(!create.status.success());
+    let stderr = String::from_utf8_lossy(&create.stderr);
+    assert!(stderr.contains("absent not found"), "{}", stderr);
+    assert!(stderr.contains("stale 1.9"));
+    assert!(!temp.path().join("out").exists());
+
+    let skipped = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "tools", "-o", "out", "-y", "--skip-requirements"])
+        .output()
+        .unwrap();
+    assert!(
+        skipped.status.success(),
+        "{}",
+        String::from_utf8_lossy(&skipped.stderr)
+    );
+
+    write_config("fresh = { version = \">=1.2\", command = \"echo 1.4\" }\n");
+    let ok = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["create", "tools", "-o", "out2", "-y"])
+        .output()
+        .unwrap();
+    assert!(ok.status.success());
+    assert!(temp.path().join("out2/README.md").exists());
+
+    write_config("fresh = { version = \">=soon\" }\n");
+    let validate = scaffold_cmd()
+        .current_dir(temp.path())
+        .args(["validate", ".templates/tools"])
+        .output()
+        .unwrap();
+    assert!(!validate.status.success());
+    let stdout = String::from_utf8_lossy(&validate.stdout);
+    assert!(stdout.contains("S023"), "{}", stdout);
 }